@@ -0,0 +1,72 @@
+pub mod agent {
+    use crate::chat_completions::providers::openai::openai_tools::{
+        chat_with_tools, OpenAIToolMessage,
+    };
+    use crate::chat_completions::tools::registry::registry::ToolRegistry;
+    use anyhow::{bail, Result};
+
+    /// Upper bound on model round-trips so a model that keeps calling tools cannot loop forever.
+    pub const MAX_TOOL_ROUNDS: usize = 8;
+
+    /// The final answer of an agent run and the tools that were used to reach it.
+    #[derive(Debug, Clone)]
+    pub struct AgentOutcome {
+        pub response: String,
+        pub tools_used: Vec<String>,
+    }
+
+    /// Agent bot: answers a query while letting the model call the tools in `registry`.
+    ///
+    /// # Arguments
+    /// * `query` - A `String` representing the user query.
+    /// * `registry` - The tools the model may call.
+    ///
+    /// # Returns
+    /// * `Result<AgentOutcome>` - The model's final answer once it stops requesting tools.
+    pub async fn agent(query: String, registry: &ToolRegistry) -> Result<AgentOutcome> {
+        let messages = vec![
+            OpenAIToolMessage::new(
+                "system".to_string(),
+                "You are a helpful assistant. Use the provided tools whenever they can answer part of the question exactly.".to_string(),
+            ),
+            OpenAIToolMessage::new("user".to_string(), query),
+        ];
+        run_agent(messages, registry).await
+    }
+
+    /// Runs the tool-calling loop over an existing conversation.
+    ///
+    /// Every tool call the model requests is executed and answered with a `tool`
+    /// role message, then the model is asked again, until it replies without
+    /// requesting any tools.
+    pub async fn run_agent(
+        mut messages: Vec<OpenAIToolMessage>,
+        registry: &ToolRegistry,
+    ) -> Result<AgentOutcome> {
+        let tools = registry.definitions();
+        let mut tools_used = Vec::new();
+
+        for _ in 0..MAX_TOOL_ROUNDS {
+            let message = chat_with_tools(&messages, &tools).await?;
+            let tool_calls = message.requested_tool_calls().to_vec();
+
+            if tool_calls.is_empty() {
+                return Ok(AgentOutcome {
+                    response: message.content.unwrap_or_default(),
+                    tools_used,
+                });
+            }
+
+            messages.push(message);
+            for tool_call in tool_calls {
+                let result = registry
+                    .execute(&tool_call.function.name, &tool_call.function.arguments)
+                    .await;
+                tools_used.push(tool_call.function.name);
+                messages.push(OpenAIToolMessage::tool_result(tool_call.id, result));
+            }
+        }
+
+        bail!("Model kept requesting tools after {MAX_TOOL_ROUNDS} rounds.")
+    }
+}
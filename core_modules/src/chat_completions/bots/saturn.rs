@@ -1,61 +1,519 @@
 pub mod saturn {
+    use crate::chat_completions::bots::agent::agent::run_agent;
+    use crate::chat_completions::memory::{
+        corpus::corpus::{corpus_prompt, sources_footer, DocumentCorpus, RetrievedChunk},
+        extract::extract::extract_memories,
+        recall::recall::ConversationRecall,
+        store::store::{Exchange, MemoryStore},
+    };
     use crate::chat_completions::providers::{
-        gemini::gemini::gemini, openai::openai::openai, perplexity::perplexity::perplexity,
+        openai::openai_tools::OpenAIToolMessage,
+        router::router::{
+            complete, complete_with_fallback, fallback_chain, primary_provider, provider_span,
+            record_call, report_fallback, Provider,
+        },
     };
+    use crate::chat_completions::tools::builtin::builtin::builtin_tools;
+    use crate::chat_completions::tools::registry::registry::ToolRegistry;
     use crate::chat_completions::utils::{
+        context_window::context_window::fit_messages,
+        errors::errors::{classify, Cancelled, DeadlineExceeded, ErrorKind, UpstreamError},
         is_satisfactory::is_satisfactory::is_satisfactory,
+        messages::messages::ChatMessage,
+        metrics::metrics::metrics,
         needs_internet::needs_internet::needs_internet,
+        progress::progress::{Progress, ProgressSender},
     };
     use anyhow::Result;
+    use dotenv::dotenv;
+    use reqwest::StatusCode;
+    use std::env;
+    use std::future::pending;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tokio::time::{self, sleep_until};
+    use tokio_util::sync::CancellationToken;
+    use tracing::{error, info, info_span, instrument, warn, Instrument};
+
+    /// Optional state a Saturn turn can draw on. `SaturnOptions::default()` gives
+    /// the plain stateless behaviour of `saturn()`.
+    #[derive(Clone, Default)]
+    pub struct SaturnOptions {
+        /// Long-term memory: relevant memories are injected into the prompt and
+        /// every answered exchange is recorded.
+        pub memory: Option<Arc<MemoryStore>>,
+        /// Semantic recall: the most similar past exchanges are injected into the
+        /// prompt and every answered exchange is embedded.
+        pub recall: Option<Arc<ConversationRecall>>,
+        /// Local documents: relevant chunks ground the answer, which then cites them.
+        pub corpus: Option<Arc<DocumentCorpus>>,
+        /// Earlier turns of the conversation, oldest first. Trimmed to each
+        /// provider's context window before sending.
+        pub history: Vec<ChatMessage>,
+        /// Drafts with this provider instead of `SATURN_PRIMARY_PROVIDER`.
+        pub provider: Option<Provider>,
+        /// Replaces the default "You are a helpful assistant." system prompt.
+        pub system: Option<String>,
+        /// Never hands the query to Perplexity, even when it needs the internet.
+        pub no_search: bool,
+        /// Answers without recording the exchange in memory or recall, for
+        /// clients that bring their own conversation.
+        pub no_remember: bool,
+        /// Abandons the turn once cancelled. It is checked between steps, and a
+        /// provider call in flight is dropped, aborting its request.
+        pub cancel: Option<CancellationToken>,
+        /// Abandons the turn, the same way, once this instant passes.
+        pub deadline: Option<time::Instant>,
+        /// Receives a [`Progress`] report as each step of the turn starts.
+        pub progress: Option<ProgressSender>,
+    }
+
+    impl SaturnOptions {
+        pub fn with_memory(mut self, memory: Arc<MemoryStore>) -> Self {
+            self.memory = Some(memory);
+            self
+        }
+
+        pub fn with_recall(mut self, recall: Arc<ConversationRecall>) -> Self {
+            self.recall = Some(recall);
+            self
+        }
+
+        pub fn with_corpus(mut self, corpus: Arc<DocumentCorpus>) -> Self {
+            self.corpus = Some(corpus);
+            self
+        }
+
+        /// Scopes memory, recall and documents to `client`, so its turns neither
+        /// see nor change another client's data.
+        pub fn with_client(mut self, client: &str) -> Self {
+            self.memory = self
+                .memory
+                .map(|memory| Arc::new(memory.for_client(client)));
+            self.recall = self
+                .recall
+                .map(|recall| Arc::new(recall.for_client(client)));
+            self.corpus = self
+                .corpus
+                .map(|corpus| Arc::new(corpus.for_client(client)));
+            self
+        }
+
+        pub fn with_history(mut self, history: Vec<ChatMessage>) -> Self {
+            self.history = history;
+            self
+        }
+
+        pub fn with_provider(mut self, provider: Provider) -> Self {
+            self.provider = Some(provider);
+            self
+        }
+
+        pub fn with_system(mut self, system: impl Into<String>) -> Self {
+            self.system = Some(system.into());
+            self
+        }
+
+        pub fn without_search(mut self) -> Self {
+            self.no_search = true;
+            self
+        }
+
+        pub fn without_remembering(mut self) -> Self {
+            self.no_remember = true;
+            self
+        }
+
+        pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+            self.cancel = Some(cancel);
+            self
+        }
+
+        pub fn with_deadline(mut self, deadline: time::Instant) -> Self {
+            self.deadline = Some(deadline);
+            self
+        }
+
+        /// Gives the turn `timeout` from now to finish.
+        pub fn with_timeout(self, timeout: Duration) -> Self {
+            self.with_deadline(time::Instant::now() + timeout)
+        }
+
+        pub fn with_progress(mut self, progress: ProgressSender) -> Self {
+            self.progress = Some(progress);
+            self
+        }
+
+        /// Sends `progress` to the listener, if there is one still listening.
+        pub fn report(&self, progress: Progress) {
+            if let Some(sender) = &self.progress {
+                let _ = sender.send(progress);
+            }
+        }
+
+        /// Fails once the turn has been cancelled or has run past its deadline.
+        pub fn check_active(&self) -> Result<()> {
+            if self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.is_cancelled())
+            {
+                return Err(Cancelled.into());
+            }
+            if self
+                .deadline
+                .is_some_and(|deadline| time::Instant::now() >= deadline)
+            {
+                return Err(DeadlineExceeded.into());
+            }
+            Ok(())
+        }
+    }
+
+    impl SaturnOptions {
+        /// Forgets a memory everywhere it could resurface: the memory itself, the
+        /// exchange it was extracted from and that exchange in recall. Returns
+        /// `false` if no memory has that id.
+        pub async fn forget_memory(&self, id: i64) -> Result<bool> {
+            let Some(memory) = &self.memory else {
+                return Ok(false);
+            };
+            let source = memory.source_exchange(id)?;
+            let found = memory.forget_memory(id)?;
+            self.forget_source(source).await?;
+            Ok(found)
+        }
+
+        /// Corrects a memory, dropping the exchange that stated the old fact from
+        /// memory and recall. Returns `false` if no memory has that id.
+        pub async fn edit_memory(&self, id: i64, content: &str) -> Result<bool> {
+            let Some(memory) = &self.memory else {
+                return Ok(false);
+            };
+            let source = memory.source_exchange(id)?;
+            let found = memory.edit_memory(id, content)?;
+            self.forget_source(source).await?;
+            Ok(found)
+        }
+
+        async fn forget_source(&self, source: Option<Exchange>) -> Result<()> {
+            if let (Some(exchange), Some(recall)) = (source, &self.recall) {
+                recall
+                    .forget_exchange(&exchange.query, &exchange.response)
+                    .await?;
+            }
+            Ok(())
+        }
+    }
+
+    /// The default time limit for a turn: `SATURN_TURN_TIMEOUT_SECS`, or two
+    /// minutes. `0` means no limit.
+    pub fn turn_timeout() -> Option<Duration> {
+        dotenv().ok();
+        let secs = env::var("SATURN_TURN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.trim().parse().ok())
+            .unwrap_or(120);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+
+    /// Builds the conversation sent to a provider: a system prompt, any
+    /// remembered context, the earlier turns, then the query.
+    fn build_messages(
+        system: &str,
+        context: Option<&str>,
+        history: &[ChatMessage],
+        query: &str,
+    ) -> Vec<ChatMessage> {
+        let mut messages = vec![ChatMessage::system(system)];
+        if let Some(context) = context {
+            messages.push(ChatMessage::system(context));
+        }
+        messages.extend_from_slice(history);
+        messages.push(ChatMessage::user(query));
+        messages
+    }
 
-    /// Saturn bot: Receives a query and tries to fulfill it using OpenAI first.
-    /// If OpenAI cannot fulfill the query, it falls back to Gemini.
-    /// If internet access is needed, it sends the query to Perplexity.
+    /// Drafts an answer with `primary`, falling back through the configured
+    /// chain. An unconfigured primary is skipped. Returns the draft and whether
+    /// a local tool produced it, or the last failure, as an upstream error,
+    /// when every provider fails.
+    #[instrument(name = "draft", skip_all, fields(provider = %primary))]
+    async fn draft(
+        primary: Provider,
+        messages: &[ChatMessage],
+        tools: &ToolRegistry,
+        options: &SaturnOptions,
+    ) -> Result<(String, bool)> {
+        let primary_error = if primary.is_configured() {
+            options.report(Progress::ProviderSelected {
+                provider: primary.to_string(),
+            });
+            match draft_with(primary, messages, tools).await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    report_fallback(primary, &e, options.progress.as_ref());
+                    Some(e)
+                }
+            }
+        } else {
+            warn!(provider = %primary, "Primary provider is not configured; falling back");
+            None
+        };
+
+        let chain: Vec<Provider> = fallback_chain()
+            .into_iter()
+            .filter(|provider| *provider != primary)
+            .collect();
+        match complete_with_fallback(&chain, messages, options.progress.as_ref()).await {
+            Ok((_, response)) => Ok((response, false)),
+            Err(fallback_error) => {
+                error!(error = %fallback_error, "Fallback providers also failed; no response generated");
+                // Without a configured fallback, the primary's failure is the last one
+                let (provider, last_error) = match (
+                    chain.iter().rev().find(|p| p.is_configured()),
+                    primary_error,
+                ) {
+                    (Some(provider), _) => (*provider, fallback_error),
+                    (None, Some(e)) => (primary, e),
+                    (None, None) => (primary, fallback_error),
+                };
+                if classify(&last_error) == ErrorKind::Internal {
+                    let upstream = UpstreamError::new(provider.name(), StatusCode::BAD_GATEWAY);
+                    return Err(anyhow::Error::new(upstream).context(format!("{last_error:#}")));
+                }
+                Err(last_error)
+            }
+        }
+    }
+
+    /// Asks `primary` alone. OpenAI drafts with the built-in tools; other
+    /// primaries answer directly.
+    async fn draft_with(
+        primary: Provider,
+        messages: &[ChatMessage],
+        tools: &ToolRegistry,
+    ) -> Result<(String, bool)> {
+        if primary != Provider::OpenAI {
+            let response = complete(primary, messages).await?;
+            return Ok((response, false));
+        }
+        let fitted = fit_messages(primary, messages).await?;
+        let span = provider_span(primary);
+        let started = Instant::now();
+        let outcome = run_agent(fitted.iter().map(OpenAIToolMessage::from).collect(), tools)
+            .instrument(span.clone())
+            .await
+            .map(|outcome| (outcome.response, !outcome.tools_used.is_empty()));
+        let response = outcome.as_ref().ok().map(|(response, _)| response.as_str());
+        span.in_scope(|| record_call(primary, &fitted, response, None, started));
+        outcome
+    }
+
+    /// Records a finished exchange, then extracts whatever durable facts it
+    /// revealed in the background so the reply does not wait on that model call.
+    fn remember(memory: &Arc<MemoryStore>, query: &str, response: &str) -> Result<()> {
+        let exchange_id = memory.record_exchange(query, response)?;
+        let memory = memory.clone();
+        let (query, response) = (query.to_string(), response.to_string());
+        tokio::spawn(
+            async move {
+                let facts = match extract_memories(query, response).await {
+                    Ok(facts) => facts,
+                    Err(e) => {
+                        warn!(error = %e, "Failed to extract memories");
+                        return;
+                    }
+                };
+                for fact in facts {
+                    if let Err(e) = memory.add_extracted_memory(&fact, exchange_id) {
+                        warn!(error = %e, "Failed to store memory");
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+        Ok(())
+    }
+
+    /// The document chunks relevant to `query`. Retrieval failures are reported
+    /// and treated as no matches.
+    async fn retrieve_documents(query: &str, options: &SaturnOptions) -> Vec<RetrievedChunk> {
+        let Some(corpus) = &options.corpus else {
+            return Vec::new();
+        };
+        corpus.search(query, 4).await.unwrap_or_else(|e| {
+            warn!(error = %e, "Document retrieval unavailable");
+            Vec::new()
+        })
+    }
+
+    /// Joins the memory, recall and document context for `query`. Recall failures
+    /// (e.g. an unreachable embedding API) are reported but never fail the turn.
+    async fn context_for(
+        query: &str,
+        options: &SaturnOptions,
+        documents: &[RetrievedChunk],
+    ) -> Result<Option<String>> {
+        let mut sections = Vec::new();
+        if let Some(memory) = &options.memory {
+            sections.extend(memory.context_for(query)?);
+        }
+        if let Some(recall) = &options.recall {
+            match recall.context_for(query).await {
+                Ok(section) => sections.extend(section),
+                Err(e) => warn!(error = %e, "Recall unavailable"),
+            }
+        }
+        sections.extend(corpus_prompt(documents));
+        Ok((!sections.is_empty()).then(|| sections.join("\n")))
+    }
+
+    pub async fn saturn(query: String) -> Result<String> {
+        saturn_with_options(query, &SaturnOptions::default()).await
+    }
+
+    /// Saturn bot: Receives a query and tries to fulfill it using the primary provider
+    /// (OpenAI by default, letting it call the built-in offline tools: calculator, clock,
+    /// unit conversion, file reader). If it cannot fulfill the query, it falls back through
+    /// the configured providers (Anthropic, then Gemini by default).
+    /// If internet access is needed and Perplexity is configured, it sends the query to
+    /// Perplexity; answers produced with a local tool skip that check.
+    /// With memory in `options`, relevant memories are added to the prompt and the
+    /// answered exchange is remembered. With recall, the most similar past
+    /// exchanges are retrieved as well. With a document corpus, relevant chunks
+    /// ground the answer and are listed as sources beneath it.
     ///
     /// # Arguments
     /// * `query` - A `String` representing the user query.
+    /// * `options` - Optional state such as long-term memory, recall and a document corpus.
     ///
     /// # Returns
-    /// * `Result<String>` - The response from OpenAI, Gemini, or Perplexity based on the internet check.
-    pub async fn saturn(query: String) -> Result<String> {
+    /// * `Result<String>` - The response from the primary provider, a fallback provider, or Perplexity based on the internet check.
+    ///   Fails with [`Cancelled`] if the options' cancellation token fires first,
+    ///   or [`DeadlineExceeded`] if their deadline passes first.
+    #[instrument(name = "turn", skip_all, fields(query_chars = query.len()))]
+    pub async fn saturn_with_options(query: String, options: &SaturnOptions) -> Result<String> {
+        let cancelled = async {
+            match &options.cancel {
+                Some(cancel) => cancel.cancelled().await,
+                None => pending().await,
+            }
+        };
+        let expired = async {
+            match options.deadline {
+                Some(deadline) => sleep_until(deadline).await,
+                None => pending().await,
+            }
+        };
+        // Dropping the turn drops its in-flight requests, aborting them
+        tokio::select! {
+            biased;
+            _ = cancelled => {
+                info!("Turn cancelled");
+                Err(Cancelled.into())
+            }
+            _ = expired => {
+                warn!("Turn ran past its deadline");
+                Err(DeadlineExceeded.into())
+            }
+            result = run_turn(query, options) => result,
+        }
+    }
+
+    async fn run_turn(query: String, options: &SaturnOptions) -> Result<String> {
+        let documents = retrieve_documents(&query, options).await;
+        let context = context_for(&query, options, &documents).await?;
+        let primary = options.provider.unwrap_or_else(primary_provider);
+        let draft_messages = build_messages(
+            options
+                .system
+                .as_deref()
+                .unwrap_or("You are a helpful assistant."),
+            context.as_deref(),
+            &options.history,
+            &query,
+        );
+        let search_messages = build_messages(
+            "Be precise and concise.",
+            context.as_deref(),
+            &options.history,
+            &query,
+        );
+
         let mut attempts = 0;
         let max_attempts = 10;
         let mut needs_internet_flag = false;
         let mut response = String::from("Unexpected lack of response.");
+        let tools = builtin_tools();
 
         while attempts < max_attempts {
+            options.check_active()?;
+            options.report(Progress::Attempt {
+                number: attempts + 1,
+                max: max_attempts,
+            });
+            let satisfied = async {
+                let mut answered_locally = false;
 
-            if !needs_internet_flag {
-                // Step 1: Try to fulfill the query using OpenAI
-                response = match openai(query.clone()).await {
-                    Ok(res) => res,
-                    Err(_) => {
-                        eprintln!("OpenAI failed; falling back to Gemini.");
-                        // Step 2: If OpenAI fails, try using Gemini
-                        match gemini(query.clone()).await {
-                            Ok(res) => res,
-                            Err(_) => {
-                                eprintln!("Gemini also failed; no response generated.");
-                                "".to_string() // If both fail, return an empty string as a last resort
-                            }
-                        }
-                    }
-                };
-            }
+                if !needs_internet_flag {
+                    // Steps 1 and 2: Draft with the primary provider, then the fallback chain
+                    (response, answered_locally) =
+                        draft(primary, &draft_messages, &tools, options).await?;
+                }
 
-            // Step 3: Check if the response requires internet access
-            if needs_internet_flag || needs_internet(query.clone()).await? {
-                needs_internet_flag = true;
-                println!("Internet access is required; querying Perplexity.");
-                response = perplexity(query.clone()).await?;
+                // Step 3: Check if the response requires internet access
+                options.check_active()?;
+                let can_search =
+                    !options.no_search && !answered_locally && Provider::Perplexity.is_configured();
+                if can_search && !needs_internet_flag {
+                    options.report(Progress::CheckingInternet);
+                    needs_internet_flag = needs_internet(query.clone()).await?;
+                    options.report(Progress::InternetChecked {
+                        needed: needs_internet_flag,
+                    });
+                }
+                if can_search && needs_internet_flag {
+                    info!("Internet access is required; querying Perplexity");
+                    options.report(Progress::Searching {
+                        provider: Provider::Perplexity.to_string(),
+                    });
+                    response = complete(Provider::Perplexity, &search_messages).await?;
+                }
+
+                // Step 4: Check if the response is satisfactory
+                options.check_active()?;
+                is_satisfactory(query.clone(), response.clone()).await
             }
+            .instrument(info_span!("attempt", number = attempts + 1))
+            .await?;
+            options.report(Progress::Verdict {
+                satisfactory: satisfied,
+            });
 
-            // Step 4: Check if the response is satisfactory
-            if is_satisfactory(query.clone(), response.clone()).await? {
-                println!("Satisfied with response after {} attempts", attempts + 1);
+            if satisfied {
+                info!(attempts = attempts + 1, "Satisfied with response");
+                if let Some(memory) = options.memory.as_ref().filter(|_| !options.no_remember) {
+                    if let Err(e) = remember(memory, &query, &response) {
+                        warn!(error = %e, "Failed to update memory");
+                    }
+                }
+                if let Some(recall) = options.recall.as_ref().filter(|_| !options.no_remember) {
+                    if let Err(e) = recall.add_exchange(&query, &response).await {
+                        warn!(error = %e, "Failed to index exchange for recall");
+                    }
+                }
+                // Citations are for the reader, so they are added after storing
+                if !documents.is_empty() {
+                    response = format!("{response}\n\n{}", sources_footer(&documents));
+                }
                 return Ok(response); // Return satisfactory response
             } else {
-                eprintln!("Unsatisfactory response received, retrying...");
+                warn!(
+                    attempt = attempts + 1,
+                    "Unsatisfactory response received; retrying"
+                );
+                metrics().record_retry("unsatisfactory");
                 attempts += 1;
             }
         }
@@ -67,7 +525,10 @@ pub mod saturn {
 
 #[cfg(test)]
 mod tests {
-    use super::saturn::saturn;
+    use super::saturn::{saturn, saturn_with_options, SaturnOptions};
+    use crate::chat_completions::utils::errors::errors::{Cancelled, DeadlineExceeded};
+    use tokio::time::Instant;
+    use tokio_util::sync::CancellationToken;
 
     #[tokio::test]
     async fn test_saturn_bot() {
@@ -84,4 +545,27 @@ mod tests {
             Err(e) => panic!("Saturn bot failed: {:?}", e),
         }
     }
+
+    #[tokio::test]
+    async fn test_cancelled_turn_stops_before_any_call() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let options = SaturnOptions::default().with_cancel(cancel);
+
+        let error = saturn_with_options("Hello".to_string(), &options)
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<Cancelled>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_expired_turn_stops_before_any_call() {
+        let options = SaturnOptions::default().with_deadline(Instant::now());
+        assert!(options.check_active().is_err());
+
+        let error = saturn_with_options("Hello".to_string(), &options)
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<DeadlineExceeded>().is_some());
+    }
 }
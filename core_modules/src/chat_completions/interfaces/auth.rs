@@ -0,0 +1,256 @@
+pub mod auth {
+    use crate::chat_completions::memory::store::store::LOCAL_CLIENT;
+    use crate::chat_completions::utils::paths::paths::saturn_file;
+    use anyhow::{anyhow, bail, Result};
+    use chrono::Utc;
+    use serde::{Deserialize, Serialize};
+    use sha2::{Digest, Sha256};
+    use std::fmt;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    /// Prefix of every client key, so leaked keys are easy to recognise.
+    pub const KEY_PREFIX: &str = "sk-saturn-";
+
+    /// What a client key may do.
+    #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Scope {
+        /// `/query` and `/v1/chat/completions`.
+        Chat,
+        /// The `/sessions` API.
+        Sessions,
+        /// Reading and editing long-term memory.
+        Memory,
+        /// Everything, including operational endpoints.
+        Admin,
+    }
+
+    impl Scope {
+        pub const ALL: [Scope; 4] = [Scope::Chat, Scope::Sessions, Scope::Memory, Scope::Admin];
+
+        pub fn name(&self) -> &'static str {
+            match self {
+                Scope::Chat => "chat",
+                Scope::Sessions => "sessions",
+                Scope::Memory => "memory",
+                Scope::Admin => "admin",
+            }
+        }
+    }
+
+    impl fmt::Display for Scope {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.name())
+        }
+    }
+
+    impl FromStr for Scope {
+        type Err = anyhow::Error;
+
+        fn from_str(name: &str) -> Result<Self> {
+            Scope::ALL
+                .into_iter()
+                .find(|scope| scope.name().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| anyhow!("Unknown scope '{name}'"))
+        }
+    }
+
+    /// Parses a comma separated scope list such as `"chat,sessions"`.
+    pub fn parse_scopes(list: &str) -> Result<Vec<Scope>> {
+        list.split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(Scope::from_str)
+            .collect()
+    }
+
+    /// A client allowed to call the server. Only a hash of its key is stored.
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct ClientKey {
+        pub name: String,
+        pub key_hash: String,
+        pub scopes: Vec<Scope>,
+        pub created_at: String,
+    }
+
+    impl ClientKey {
+        pub fn allows(&self, scope: Scope) -> bool {
+            self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+        }
+    }
+
+    fn hash_key(key: &str) -> String {
+        format!("{:x}", Sha256::digest(key.as_bytes()))
+    }
+
+    /// Client keys kept in a JSON file, by default `clients.json` in the Saturn home.
+    pub struct KeyStore {
+        path: Option<PathBuf>,
+        clients: Vec<ClientKey>,
+    }
+
+    impl KeyStore {
+        pub fn in_memory() -> Self {
+            KeyStore {
+                path: None,
+                clients: Vec::new(),
+            }
+        }
+
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            let path = path.as_ref().to_path_buf();
+            let clients = match fs::read_to_string(&path) {
+                Ok(contents) => serde_json::from_str(&contents)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e.into()),
+            };
+            Ok(KeyStore {
+                path: Some(path),
+                clients,
+            })
+        }
+
+        pub fn open_default() -> Result<Self> {
+            KeyStore::open(saturn_file("clients.json")?)
+        }
+
+        /// Authentication is on as soon as one client key exists.
+        pub fn is_enabled(&self) -> bool {
+            !self.clients.is_empty()
+        }
+
+        pub fn clients(&self) -> &[ClientKey] {
+            &self.clients
+        }
+
+        /// Creates a key for a new client and returns it. The key is shown only
+        /// this once; the store keeps its hash.
+        pub fn add_client(&mut self, name: &str, scopes: Vec<Scope>) -> Result<String> {
+            let name = name.trim();
+            if name.is_empty() {
+                bail!("Client names cannot be empty");
+            }
+            // Keyless requests act as this client, so a key must not share its data
+            if name.eq_ignore_ascii_case(LOCAL_CLIENT) {
+                bail!("'{name}' is reserved for unauthenticated use; pick another name");
+            }
+            if self.clients.iter().any(|client| client.name == name) {
+                bail!("A client named '{name}' already exists");
+            }
+            if scopes.is_empty() {
+                bail!("Give the client at least one scope");
+            }
+            let key = format!(
+                "{KEY_PREFIX}{}{}",
+                Uuid::new_v4().simple(),
+                Uuid::new_v4().simple()
+            );
+            self.clients.push(ClientKey {
+                name: name.to_string(),
+                key_hash: hash_key(&key),
+                scopes,
+                created_at: Utc::now().to_rfc3339(),
+            });
+            self.save()?;
+            Ok(key)
+        }
+
+        /// Returns `false` if no client has that name.
+        pub fn revoke_client(&mut self, name: &str) -> Result<bool> {
+            let before = self.clients.len();
+            self.clients.retain(|client| client.name != name);
+            if self.clients.len() == before {
+                return Ok(false);
+            }
+            self.save()?;
+            Ok(true)
+        }
+
+        /// The client a bearer key belongs to.
+        pub fn authenticate(&self, key: &str) -> Option<&ClientKey> {
+            let hash = hash_key(key.trim());
+            self.clients.iter().find(|client| client.key_hash == hash)
+        }
+
+        fn save(&self) -> Result<()> {
+            let Some(path) = &self.path else {
+                return Ok(());
+            };
+            let temporary = path.with_extension("tmp");
+            fs::write(&temporary, serde_json::to_vec_pretty(&self.clients)?)?;
+            restrict_permissions(&temporary)?;
+            fs::rename(temporary, path)?;
+            Ok(())
+        }
+    }
+
+    /// Keeps the key file readable by its owner only.
+    #[cfg(unix)]
+    fn restrict_permissions(path: &Path) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn restrict_permissions(_path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// The key in an `Authorization: Bearer <key>` header value.
+    pub fn bearer_token(header: &str) -> Option<&str> {
+        let (scheme, token) = header.trim().split_once(' ')?;
+        (scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty()).then(|| token.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::auth::{bearer_token, parse_scopes, KeyStore, Scope, KEY_PREFIX};
+
+    #[test]
+    fn test_add_authenticate_and_revoke() {
+        let mut store = KeyStore::in_memory();
+        assert!(!store.is_enabled());
+
+        let key = store.add_client("editor", vec![Scope::Chat]).unwrap();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert!(store.is_enabled());
+        assert!(store.add_client("editor", vec![Scope::Chat]).is_err());
+        assert!(store.add_client("local", vec![Scope::Chat]).is_err());
+        assert!(store.add_client(" Local ", vec![Scope::Chat]).is_err());
+
+        let client = store.authenticate(&key).unwrap();
+        assert_eq!(client.name, "editor");
+        assert!(client.allows(Scope::Chat));
+        assert!(!client.allows(Scope::Memory));
+        assert_ne!(client.key_hash, key);
+        assert!(store.authenticate("sk-saturn-wrong").is_none());
+
+        assert!(store.revoke_client("editor").unwrap());
+        assert!(store.authenticate(&key).is_none());
+    }
+
+    #[test]
+    fn test_admin_allows_everything() {
+        let mut store = KeyStore::in_memory();
+        let key = store.add_client("ops", vec![Scope::Admin]).unwrap();
+        let client = store.authenticate(&key).unwrap();
+        assert!(Scope::ALL.iter().all(|scope| client.allows(*scope)));
+    }
+
+    #[test]
+    fn test_parsing() {
+        assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearer"), None);
+        assert_eq!(
+            parse_scopes("chat, memory").unwrap(),
+            vec![Scope::Chat, Scope::Memory]
+        );
+        assert!(parse_scopes("chat,root").is_err());
+    }
+}
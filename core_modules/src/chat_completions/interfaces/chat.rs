@@ -1,8 +1,25 @@
-use crate::chat_completions::bots::saturn::saturn::saturn;
-use std::io::{self, Write};
-use tokio::io::stdin as async_stdin;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use crate::chat_completions::bots::saturn::saturn::{
+    saturn_with_options, turn_timeout, SaturnOptions,
+};
+use crate::chat_completions::interfaces::commands::commands::{
+    completions, ChatSession, Command, Outcome,
+};
+use crate::chat_completions::interfaces::input::input::{Input, LineReader};
+use crate::chat_completions::interfaces::markdown::markdown::{render_markdown, terminal_width};
+use crate::chat_completions::memory::{
+    corpus::corpus::DocumentCorpus, recall::recall::ConversationRecall,
+    sessions::sessions::SessionStore, store::store::MemoryStore,
+};
+use crate::chat_completions::utils::errors::errors::Cancelled;
+use crate::chat_completions::utils::progress::progress::Progress;
+use crate::chat_completions::utils::usage::usage::track_usage;
+use crossterm::style::Color;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Arc;
+use tokio::signal::ctrl_c;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
 use tokio::time::{sleep, Duration};
+use tokio_util::sync::CancellationToken;
 
 const USER_COLOR: (u8, u8, u8) = (23, 184, 144); // Cyan color for user
 const AI_COLOR: (u8, u8, u8) = (255, 223, 0); // Yellow color for AI
@@ -25,11 +42,28 @@ fn print_colored(text: &str, color: (u8, u8, u8)) {
     reset_color();
 }
 
-/// Simulates typing effect for AI responses
+/// Simulates typing effect for AI responses. In a terminal the response is
+/// rendered as Markdown first; escape sequences are written without a pause.
 async fn typing_effect(text: &str, color: (u8, u8, u8)) {
+    let text = if io::stdout().is_terminal() {
+        let (r, g, b) = color;
+        render_markdown(text, terminal_width(), Color::Rgb { r, g, b })
+    } else {
+        text.to_string()
+    };
     set_color(color.0, color.1, color.2);
+    let mut in_escape = false;
     for c in text.chars() {
         print!("{}", c);
+        if c == '\x1b' {
+            in_escape = true;
+            continue;
+        }
+        if in_escape {
+            // Escape sequences end with a letter
+            in_escape = !c.is_ascii_alphabetic();
+            continue;
+        }
         io::stdout().flush().unwrap();
         sleep(Duration::from_millis(50)).await;
     }
@@ -37,39 +71,158 @@ async fn typing_effect(text: &str, color: (u8, u8, u8)) {
     reset_color();
 }
 
+/// Redraws the current line with `text`, for a status that updates in place.
+fn print_status(text: &str) {
+    set_color(THOUGHT_COLOR.0, THOUGHT_COLOR.1, THOUGHT_COLOR.2);
+    print!("\r\x1b[2K{}", text);
+    reset_color();
+    io::stdout().flush().unwrap();
+}
+
+/// Shows each progress report on the "THINKING..." line until the turn drops
+/// its sender, then clears the line. Without a terminal the line stays as is.
+async fn show_progress(mut reports: UnboundedReceiver<Progress>) {
+    let live = io::stdout().is_terminal();
+    if live {
+        print_status("THINKING...");
+    } else {
+        print_colored("THINKING...", THOUGHT_COLOR);
+    }
+    while let Some(progress) = reports.recv().await {
+        if live {
+            print_status(&format!("THINKING... {progress}"));
+        }
+    }
+    if live {
+        print_status("");
+    }
+}
+
+/// The options terminal chats start from: the local memory, recall and
+/// document corpus, each skipped with a warning if it can't be opened.
+pub fn chat_options() -> SaturnOptions {
+    let mut options = SaturnOptions::default();
+    match MemoryStore::open_default() {
+        Ok(memory) => options = options.with_memory(Arc::new(memory)),
+        Err(e) => eprintln!("Memory unavailable, continuing without it: {e}"),
+    }
+    match ConversationRecall::open_default() {
+        Ok(recall) => options = options.with_recall(Arc::new(recall)),
+        Err(e) => eprintln!("Recall unavailable, continuing without it: {e}"),
+    }
+    match DocumentCorpus::open_default() {
+        Ok(corpus) => options = options.with_corpus(Arc::new(corpus)),
+        Err(e) => eprintln!("Document corpus unavailable, continuing without it: {e}"),
+    }
+    options
+}
+
 /// Starts the chat interface with Saturn bot
 pub async fn start_chat() {
     println!("Starting new conversation with Saturn bot.");
-    println!("Type 'exit' to end the conversation.\n");
+    println!("Type '/help' for commands and 'exit' to end the conversation.");
+    println!("End a line with \\ or press Alt-Enter to continue on the next line; Tab completes commands.");
+    println!("Press Ctrl-C to cancel an answer in progress.\n");
 
-    let stdin = async_stdin();
-    let mut reader = BufReader::new(stdin).lines();
+    let sessions = SessionStore::open_default()
+        .map_err(|e| eprintln!("Saved sessions unavailable, continuing without them: {e}"))
+        .ok();
+    let mut chat = ChatSession::new(chat_options(), sessions);
+    let mut reader = match LineReader::new(completions()) {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("Failed to set up input: {e}");
+            return;
+        }
+    };
 
     loop {
         print_colored("You:", USER_COLOR);
-        print!("Send a message ('exit' to quit): ");
-        io::stdout().flush().unwrap();
 
-        if let Ok(Some(input)) = reader.next_line().await {
-            if input.trim().eq_ignore_ascii_case("exit") {
+        // Ctrl-C at the prompt ends the conversation; during a turn it only cancels the turn
+        let input = match reader.read("Send a message ('exit' to quit): ").await {
+            Ok(Input::Line(input)) => input,
+            Ok(Input::Interrupted) => {
                 print_colored("Goodbye!", THOUGHT_COLOR);
                 break;
             }
+            Ok(Input::Eof) => break, // End of input
+            Err(e) => {
+                eprintln!("Failed to read input: {e}");
+                break;
+            }
+        };
 
-            // Display "THINKING..." while AI processes the input
-            print_colored("THINKING...", THOUGHT_COLOR);
+        // Slash-commands change the session, or ask again as with `/retry`; `exit` ends it
+        let outcome = match Command::parse(&input) {
+            Some(Ok(command)) => chat.run(command).await,
+            Some(Err(e)) => Err(e),
+            None => Ok(Outcome::Ask {
+                query: input,
+                retry: false,
+            }),
+        };
+        let (query, retry) = match outcome {
+            Ok(Outcome::Ask { query, retry }) => (query, retry),
+            Ok(Outcome::Reply(message)) => {
+                print_colored(&message, THOUGHT_COLOR);
+                continue;
+            }
+            Ok(Outcome::Exit) => {
+                print_colored("Goodbye!", THOUGHT_COLOR);
+                break;
+            }
+            Err(e) => {
+                print_colored(&e.to_string(), THOUGHT_COLOR);
+                continue;
+            }
+        };
 
-            // Send the query to Saturn bot and process the response
-            match saturn(input.clone()).await {
-                Ok(response) => {
-                    print_colored("Saturn:", AI_COLOR);
-                    typing_effect(&response, AI_COLOR).await;
+        let cancel = CancellationToken::new();
+        let watcher = tokio::spawn({
+            let cancel = cancel.clone();
+            async move {
+                if ctrl_c().await.is_ok() {
+                    cancel.cancel();
                 }
-                Err(e) => {
-                    eprintln!("Saturn encountered an error: {:?}", e);
+            }
+        });
+        // Display "THINKING..." and what Saturn is doing while it processes the input
+        let (progress, reports) = mpsc::unbounded_channel();
+        let status = tokio::spawn(show_progress(reports));
+        let mut turn = chat
+            .turn_options(retry)
+            .with_cancel(cancel.clone())
+            .with_progress(progress);
+        if let Some(timeout) = turn_timeout() {
+            turn = turn.with_timeout(timeout);
+        }
+
+        // Send the query to Saturn bot and process the response
+        let (result, usage) = track_usage(saturn_with_options(query.clone(), &turn)).await;
+        drop(turn); // Closes the progress channel, so the status line clears
+        let _ = status.await;
+        match result {
+            Ok(response) => {
+                chat.record(query, response.clone(), retry, usage);
+                print_colored("Saturn:", AI_COLOR);
+                // Ctrl-C while typing skips the rest; the answer is already kept
+                tokio::select! {
+                    _ = typing_effect(&response, AI_COLOR) => {}
+                    _ = cancel.cancelled() => {
+                        reset_color();
+                        println!();
+                    }
                 }
             }
+            Err(e) if e.downcast_ref::<Cancelled>().is_some() => {
+                print_colored("Cancelled.", THOUGHT_COLOR);
+            }
+            Err(e) => {
+                eprintln!("Saturn encountered an error: {:?}", e);
+            }
         }
+        watcher.abort();
     }
 
     println!("Conversation ended.");
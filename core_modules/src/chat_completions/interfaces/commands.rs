@@ -0,0 +1,677 @@
+pub mod commands {
+    use crate::chat_completions::bots::saturn::saturn::SaturnOptions;
+    use crate::chat_completions::interfaces::openai_compat::openai_compat::{
+        list_models, provider_for_model,
+    };
+    use crate::chat_completions::memory::sessions::sessions::SessionStore;
+    use crate::chat_completions::providers::router::router::{primary_provider, Provider};
+    use crate::chat_completions::utils::messages::messages::{ChatMessage, Role};
+    use crate::chat_completions::utils::usage::usage::Usage;
+    use anyhow::{anyhow, bail, Result};
+
+    /// A slash-command as listed by `/help`.
+    pub struct CommandInfo {
+        pub name: &'static str,
+        pub usage: &'static str,
+        pub about: &'static str,
+    }
+
+    pub const COMMANDS: &[CommandInfo] = &[
+        CommandInfo {
+            name: "/provider",
+            usage: "/provider [name | default]",
+            about: "Show or change the provider that drafts answers",
+        },
+        CommandInfo {
+            name: "/model",
+            usage: "/model [saturn | provider/model]",
+            about: "Show the available models or pick one",
+        },
+        CommandInfo {
+            name: "/new",
+            usage: "/new",
+            about: "Start a new conversation",
+        },
+        CommandInfo {
+            name: "/history",
+            usage: "/history",
+            about: "Show this conversation",
+        },
+        CommandInfo {
+            name: "/save",
+            usage: "/save [title]",
+            about: "Save this conversation as a session, or update its saved copy",
+        },
+        CommandInfo {
+            name: "/load",
+            usage: "/load [number | id | title]",
+            about: "List saved sessions or continue one",
+        },
+        CommandInfo {
+            name: "/retry",
+            usage: "/retry",
+            about: "Ask the last message again, replacing its answer",
+        },
+        CommandInfo {
+            name: "/cost",
+            usage: "/cost",
+            about: "Show the estimated tokens and spend of this conversation",
+        },
+        CommandInfo {
+            name: "/search",
+            usage: "/search [on | off]",
+            about: "Allow or stop searching the internet with Perplexity",
+        },
+        CommandInfo {
+            name: "/system",
+            usage: "/system [prompt | default]",
+            about: "Show or replace the system prompt",
+        },
+        CommandInfo {
+            name: "/memory",
+            usage: "/memory [list | add | edit | forget]",
+            about: "Manage what Saturn remembers",
+        },
+        CommandInfo {
+            name: "/help",
+            usage: "/help",
+            about: "List the commands",
+        },
+        CommandInfo {
+            name: "/exit",
+            usage: "/exit",
+            about: "End the conversation",
+        },
+    ];
+
+    /// Everything tab completion offers: each command, and each command with
+    /// its fixed arguments.
+    pub fn completions() -> Vec<String> {
+        let mut completions: Vec<String> = COMMANDS
+            .iter()
+            .map(|command| command.name.to_string())
+            .collect();
+        completions.extend(
+            Provider::ALL
+                .iter()
+                .map(|provider| format!("/provider {provider}")),
+        );
+        completions.extend(
+            [
+                "/provider default",
+                "/model saturn",
+                "/search on",
+                "/search off",
+                "/system default",
+                "/memory list",
+                "/memory add",
+                "/memory edit",
+                "/memory forget",
+            ]
+            .map(String::from),
+        );
+        completions
+    }
+
+    /// A parsed slash-command with its arguments, trimmed.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Command {
+        Provider(String),
+        Model(String),
+        New,
+        History,
+        Save(String),
+        Load(String),
+        Retry,
+        Cost,
+        Search(String),
+        System(String),
+        Memory(String),
+        Help,
+        Exit,
+    }
+
+    impl Command {
+        /// `None` if `input` is not a slash-command; an error for unknown ones.
+        /// A bare `exit` also ends the conversation.
+        pub fn parse(input: &str) -> Option<Result<Command>> {
+            let input = input.trim();
+            if input.eq_ignore_ascii_case("exit") {
+                return Some(Ok(Command::Exit));
+            }
+            if !input.starts_with('/') {
+                return None;
+            }
+            let (name, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+            let args = args.trim().to_string();
+            let command = match name.to_ascii_lowercase().as_str() {
+                "/provider" => Command::Provider(args),
+                "/model" => Command::Model(args),
+                "/new" => Command::New,
+                "/history" => Command::History,
+                "/save" => Command::Save(args),
+                "/load" => Command::Load(args),
+                "/retry" => Command::Retry,
+                "/cost" => Command::Cost,
+                "/search" => Command::Search(args),
+                "/system" => Command::System(args),
+                "/memory" => Command::Memory(args),
+                "/help" => Command::Help,
+                "/exit" | "/quit" => Command::Exit,
+                _ => {
+                    return Some(Err(anyhow!(
+                        "Unknown command {name}. Type /help for the list."
+                    )))
+                }
+            };
+            Some(Ok(command))
+        }
+    }
+
+    /// What the chat does after a command.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Outcome {
+        /// Show this and wait for the next message.
+        Reply(String),
+        /// Run a turn with this query. A retry replaces the last exchange.
+        Ask {
+            query: String,
+            retry: bool,
+        },
+        Exit,
+    }
+
+    /// The state of a terminal conversation that commands change.
+    pub struct ChatSession {
+        pub options: SaturnOptions,
+        /// Estimated across the conversation, reset by `/new`.
+        pub usage: Usage,
+        sessions: Option<SessionStore>,
+        /// The saved session this conversation continues, once saved or loaded.
+        session_id: Option<String>,
+    }
+
+    impl ChatSession {
+        pub fn new(options: SaturnOptions, sessions: Option<SessionStore>) -> Self {
+            ChatSession {
+                options,
+                usage: Usage::default(),
+                sessions,
+                session_id: None,
+            }
+        }
+
+        pub fn session_id(&self) -> Option<&str> {
+            self.session_id.as_deref()
+        }
+
+        /// The saved sessions, if they could be opened.
+        pub fn session_store(&self) -> Option<&SessionStore> {
+            self.sessions.as_ref()
+        }
+
+        /// The provider drafting the next answer.
+        pub fn provider(&self) -> Provider {
+            self.options.provider.unwrap_or_else(primary_provider)
+        }
+
+        /// The options for the next turn. A retry leaves out the exchange it replaces.
+        pub fn turn_options(&self, retry: bool) -> SaturnOptions {
+            let mut options = self.options.clone();
+            if retry {
+                let kept = options.history.len().saturating_sub(2);
+                options.history.truncate(kept);
+            }
+            options
+        }
+
+        /// Adds an answered exchange to the history, and the usage of the turn
+        /// that produced it to the conversation's.
+        pub fn record(&mut self, query: String, answer: String, retry: bool, usage: Usage) {
+            if retry {
+                let kept = self.options.history.len().saturating_sub(2);
+                self.options.history.truncate(kept);
+            }
+            self.usage += usage;
+            self.options.history.push(ChatMessage::user(query));
+            self.options.history.push(ChatMessage::assistant(answer));
+        }
+
+        pub async fn run(&mut self, command: Command) -> Result<Outcome> {
+            let reply = match command {
+                Command::Provider(name) => self.set_provider(&name)?,
+                Command::Model(id) => self.set_model(&id)?,
+                Command::New => {
+                    self.options.history.clear();
+                    self.usage = Usage::default();
+                    self.session_id = None;
+                    "Started a new conversation.".to_string()
+                }
+                Command::History => self.history(),
+                Command::Save(title) => self.save(&title)?,
+                Command::Load(which) => self.load(&which)?,
+                Command::Retry => return self.retry(),
+                Command::Cost => format!("Estimated for this conversation: {}.", self.usage),
+                Command::Search(setting) => self.set_search(&setting)?,
+                Command::System(prompt) => self.set_system(&prompt),
+                Command::Memory(args) => memory_command(&self.options, &args).await?,
+                Command::Help => help(),
+                Command::Exit => return Ok(Outcome::Exit),
+            };
+            Ok(Outcome::Reply(reply))
+        }
+
+        fn set_provider(&mut self, name: &str) -> Result<String> {
+            match name {
+                "" => {}
+                "default" => self.options.provider = None,
+                name => {
+                    let provider: Provider = name.parse()?;
+                    if !provider.is_configured() {
+                        bail!("{provider} is not configured.");
+                    }
+                    self.options.provider = Some(provider);
+                }
+            }
+            let provider = self.provider();
+            Ok(format!("Drafting with {provider} ({}).", provider.model()))
+        }
+
+        /// Picks a model by the ids `/v1/models` lists: `saturn` for the
+        /// configured routing, or `provider/model` to draft with that provider.
+        fn set_model(&mut self, id: &str) -> Result<String> {
+            if id.is_empty() {
+                let models: Vec<String> = list_models()
+                    .data
+                    .into_iter()
+                    .map(|model| model.id)
+                    .collect();
+                return Ok(format!("Models: {}", models.join(", ")));
+            }
+            // Rejects unknown providers and models other than the provider's own
+            let provider = provider_for_model(id)?;
+            if let Some(provider) = provider {
+                if !provider.is_configured() {
+                    bail!("{provider} is not configured.");
+                }
+            }
+            self.options.provider = provider;
+            let provider = self.provider();
+            Ok(format!("Drafting with {provider} ({}).", provider.model()))
+        }
+
+        fn history(&self) -> String {
+            if self.options.history.is_empty() {
+                return "No messages yet.".to_string();
+            }
+            self.options
+                .history
+                .iter()
+                .map(|message| match message.role {
+                    Role::User => format!("You: {}", message.content),
+                    Role::Assistant => format!("Saturn: {}", message.content),
+                    Role::System => format!("System: {}", message.content),
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        }
+
+        fn sessions(&self) -> Result<&SessionStore> {
+            self.sessions
+                .as_ref()
+                .ok_or_else(|| anyhow!("Saved sessions are unavailable."))
+        }
+
+        /// Saves the conversation as a new session, or brings the session it
+        /// continues up to date, retried answers included. A title renames it.
+        pub fn save(&mut self, title: &str) -> Result<String> {
+            if self.options.history.is_empty() {
+                bail!("Nothing to save yet.");
+            }
+            let sessions = self.sessions()?;
+            if let Some(id) = &self.session_id {
+                // The session may have been deleted since; then save a new one
+                if sessions.replace_messages(id, &self.options.history)? {
+                    if !title.is_empty() {
+                        sessions.rename_session(id, title)?;
+                    }
+                    return Ok(format!("Updated session {id}."));
+                }
+            }
+            let session = sessions.create_session(Some(title).filter(|title| !title.is_empty()))?;
+            sessions.append_messages(&session.id, &self.options.history)?;
+            let reply = format!("Saved as session {}.", session.id);
+            self.session_id = Some(session.id);
+            Ok(reply)
+        }
+
+        /// Continues a saved session, chosen by its number in the `/load`
+        /// list, its id or its title.
+        fn load(&mut self, which: &str) -> Result<String> {
+            let sessions = self.sessions()?;
+            let saved = sessions.list_sessions()?;
+            if which.is_empty() {
+                if saved.is_empty() {
+                    return Ok("No saved sessions.".to_string());
+                }
+                return Ok(saved
+                    .iter()
+                    .enumerate()
+                    .map(|(number, session)| format!("{}. {}", number + 1, session.title))
+                    .collect::<Vec<_>>()
+                    .join("\n"));
+            }
+            let session = which
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| saved.get(number.checked_sub(1)?))
+                .or_else(|| saved.iter().find(|session| session.id == which))
+                .or_else(|| {
+                    saved
+                        .iter()
+                        .find(|session| session.title.eq_ignore_ascii_case(which))
+                })
+                .ok_or_else(|| anyhow!("No saved session '{which}'."))?;
+            self.options.history = sessions.history(&session.id)?;
+            self.usage = Usage::default();
+            self.session_id = Some(session.id.clone());
+            Ok(format!(
+                "Loaded \"{}\" ({} messages).",
+                session.title,
+                self.options.history.len()
+            ))
+        }
+
+        fn retry(&self) -> Result<Outcome> {
+            match self.options.history.as_slice() {
+                [.., query, answer]
+                    if query.role == Role::User && answer.role == Role::Assistant =>
+                {
+                    Ok(Outcome::Ask {
+                        query: query.content.clone(),
+                        retry: true,
+                    })
+                }
+                _ => bail!("Nothing to retry yet."),
+            }
+        }
+
+        fn set_search(&mut self, setting: &str) -> Result<String> {
+            match setting.to_ascii_lowercase().as_str() {
+                "" => {}
+                "on" => self.options.no_search = false,
+                "off" => self.options.no_search = true,
+                _ => bail!("Usage: /search [on | off]"),
+            }
+            Ok(if self.options.no_search {
+                "Internet search is off.".to_string()
+            } else if Provider::Perplexity.is_configured() {
+                "Internet search is on.".to_string()
+            } else {
+                "Internet search is on, but Perplexity is not configured.".to_string()
+            })
+        }
+
+        fn set_system(&mut self, prompt: &str) -> String {
+            match prompt {
+                "" => {}
+                "default" => self.options.system = None,
+                prompt => self.options.system = Some(prompt.to_string()),
+            }
+            format!(
+                "System prompt: {}",
+                self.options
+                    .system
+                    .as_deref()
+                    .unwrap_or("You are a helpful assistant.")
+            )
+        }
+    }
+
+    fn help() -> String {
+        let width = COMMANDS
+            .iter()
+            .map(|command| command.usage.len())
+            .max()
+            .unwrap_or_default();
+        COMMANDS
+            .iter()
+            .map(|command| format!("{:width$}  {}", command.usage, command.about))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Handles `/memory list|add|edit|forget`.
+    async fn memory_command(options: &SaturnOptions, args: &str) -> Result<String> {
+        let Some(memory) = &options.memory else {
+            return Ok("Memory is unavailable in this session.".to_string());
+        };
+        let (command, rest) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+        let rest = rest.trim();
+        let id = || {
+            rest.split_whitespace()
+                .next()
+                .and_then(|id| id.parse::<i64>().ok())
+        };
+
+        match command {
+            "" | "list" => memory.list_memories().map(|memories| {
+                if memories.is_empty() {
+                    "No memories yet.".to_string()
+                } else {
+                    memories
+                        .iter()
+                        .map(|m| format!("[{}] {}", m.id, m.content))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }),
+            "add" if !rest.is_empty() => memory
+                .add_memory(rest)
+                .map(|id| format!("Remembered as [{id}].")),
+            "edit" => match (id(), rest.split_once(' ')) {
+                (Some(id), Some((_, content))) if !content.trim().is_empty() => {
+                    options.edit_memory(id, content).await.map(|found| {
+                        if found {
+                            format!("Updated [{id}].")
+                        } else {
+                            format!("No memory [{id}].")
+                        }
+                    })
+                }
+                _ => Ok("Usage: /memory edit <id> <new text>".to_string()),
+            },
+            "forget" => match id() {
+                Some(id) => options.forget_memory(id).await.map(|found| {
+                    if found {
+                        format!("Forgot [{id}].")
+                    } else {
+                        format!("No memory [{id}].")
+                    }
+                }),
+                None => Ok("Usage: /memory forget <id>".to_string()),
+            },
+            _ => Ok(
+                "Usage: /memory [list | add <text> | edit <id> <text> | forget <id>]".to_string(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::commands::{completions, ChatSession, Command, Outcome};
+    use crate::chat_completions::bots::saturn::saturn::SaturnOptions;
+    use crate::chat_completions::interfaces::openai_compat::openai_compat::{
+        list_models, provider_for_model,
+    };
+    use crate::chat_completions::memory::sessions::sessions::SessionStore;
+    use crate::chat_completions::providers::router::router::Provider;
+    use crate::chat_completions::utils::usage::usage::Usage;
+
+    fn session() -> ChatSession {
+        let options = SaturnOptions::default().with_provider(Provider::Local);
+        ChatSession::new(options, Some(SessionStore::in_memory().unwrap()))
+    }
+
+    async fn run(session: &mut ChatSession, input: &str) -> Outcome {
+        let command = Command::parse(input).unwrap().unwrap();
+        session.run(command).await.unwrap()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert!(Command::parse("hello /new").is_none());
+        assert_eq!(
+            Command::parse("  /search   off ").unwrap().unwrap(),
+            Command::Search("off".to_string())
+        );
+        assert_eq!(
+            Command::parse("/system Answer in French.")
+                .unwrap()
+                .unwrap(),
+            Command::System("Answer in French.".to_string())
+        );
+        assert_eq!(Command::parse("/quit").unwrap().unwrap(), Command::Exit);
+        assert_eq!(Command::parse(" Exit ").unwrap().unwrap(), Command::Exit);
+        assert!(Command::parse("exit the building").is_none());
+        assert_eq!(
+            Command::parse("/model saturn").unwrap().unwrap(),
+            Command::Model("saturn".to_string())
+        );
+        assert!(Command::parse("/nope").unwrap().is_err());
+        assert!(completions().contains(&"/search off".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_commands_change_the_session() {
+        let mut session = session();
+        run(&mut session, "/search off").await;
+        assert!(session.options.no_search);
+        run(&mut session, "/system Answer in French.").await;
+        assert_eq!(session.options.system.as_deref(), Some("Answer in French."));
+        run(&mut session, "/system default").await;
+        assert_eq!(session.options.system, None);
+        assert!(session
+            .run(Command::Search("maybe".to_string()))
+            .await
+            .is_err());
+
+        session.record(
+            "Hi".to_string(),
+            "Hello!".to_string(),
+            false,
+            Usage::of_call(Provider::OpenAI, 12, 3),
+        );
+        assert_eq!(session.usage.total_tokens(), 15);
+        run(&mut session, "/new").await;
+        assert!(session.options.history.is_empty());
+        assert_eq!(session.usage.total_tokens(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_model_lists_picks_and_rejects() {
+        let mut session = session();
+        let Outcome::Reply(listed) = run(&mut session, "/model").await else {
+            panic!("expected a reply");
+        };
+        let models = list_models().data;
+        assert!(listed.starts_with("Models: saturn"));
+        assert!(models.iter().all(|model| listed.contains(&model.id)));
+
+        // Every listed model can be picked, and pins drafting to its provider
+        for model in &models {
+            run(&mut session, &format!("/model {}", model.id)).await;
+            assert_eq!(
+                session.options.provider,
+                provider_for_model(&model.id).unwrap()
+            );
+        }
+        session.options.provider = Some(Provider::Local);
+        run(&mut session, "/model saturn").await;
+        assert_eq!(session.options.provider, None);
+
+        for unknown in ["bard", "local/no-such-model", "openai/gpt-2"] {
+            let command = Command::parse(&format!("/model {unknown}"))
+                .unwrap()
+                .unwrap();
+            assert!(session.run(command).await.is_err(), "{unknown}");
+        }
+        assert_eq!(session.options.provider, None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_replaces_the_last_exchange() {
+        let mut session = session();
+        assert!(session.run(Command::Retry).await.is_err());
+
+        session.record(
+            "First".to_string(),
+            "One".to_string(),
+            false,
+            Usage::default(),
+        );
+        session.record(
+            "Second".to_string(),
+            "Two".to_string(),
+            false,
+            Usage::default(),
+        );
+        let outcome = run(&mut session, "/retry").await;
+        assert_eq!(
+            outcome,
+            Outcome::Ask {
+                query: "Second".to_string(),
+                retry: true
+            }
+        );
+        assert_eq!(session.turn_options(true).history.len(), 2);
+
+        session.record(
+            "Second".to_string(),
+            "Deux".to_string(),
+            true,
+            Usage::default(),
+        );
+        let history = &session.options.history;
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[3].content, "Deux");
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let mut session = session();
+        assert!(session.run(Command::Save(String::new())).await.is_err());
+
+        session.record(
+            "Hi".to_string(),
+            "Hello!".to_string(),
+            false,
+            Usage::default(),
+        );
+        run(&mut session, "/save Greetings").await;
+        session.record(
+            "Bye".to_string(),
+            "Goodbye!".to_string(),
+            false,
+            Usage::default(),
+        );
+        // Saving again updates the same session instead of adding another
+        run(&mut session, "/save").await;
+        let sessions = session.session_store().unwrap();
+        assert_eq!(sessions.list_sessions().unwrap().len(), 1);
+        let id = session.session_id().unwrap();
+        assert_eq!(sessions.history(id).unwrap().len(), 4);
+        run(&mut session, "/new").await;
+        assert!(session.session_id().is_none());
+        assert_eq!(
+            run(&mut session, "/load").await,
+            Outcome::Reply("1. Greetings".to_string())
+        );
+        run(&mut session, "/load greetings").await;
+        assert_eq!(session.options.history.len(), 4);
+        assert_eq!(session.options.history[1].content, "Hello!");
+        assert!(session.session_id().is_some());
+        assert!(session.run(Command::Load("7".to_string())).await.is_err());
+    }
+}
@@ -0,0 +1,214 @@
+pub mod health {
+    use crate::chat_completions::providers::anthropic::anthropic::ANTHROPIC_VERSION;
+    use crate::chat_completions::providers::gemini::gemini::GEMINI_KEY_HEADER;
+    use crate::chat_completions::providers::local::local::list_local_models;
+    use crate::chat_completions::providers::router::router::{
+        classifier_provider, fallback_chain, primary_provider, Provider,
+    };
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use anyhow::{anyhow, Result};
+    use reqwest::{header, Client, StatusCode};
+    use serde::Serialize;
+    use std::env;
+    use std::time::{Duration, Instant};
+    use tokio::sync::Mutex;
+    use tracing::warn;
+
+    /// How long one credential check may take.
+    const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+    /// How long a readiness result is reused, so frequent probes don't turn
+    /// into a stream of provider requests.
+    pub const READINESS_TTL: Duration = Duration::from_secs(30);
+
+    /// The parts a provider plays in Saturn's routing.
+    pub fn provider_roles(
+        primary: Provider,
+        classifier: Provider,
+        fallback: &[Provider],
+        search: bool,
+    ) -> Vec<(Provider, Vec<&'static str>)> {
+        let mut roles: Vec<(Provider, Vec<&'static str>)> = Vec::new();
+        let mut add = |provider: Provider, role: &'static str| match roles
+            .iter_mut()
+            .find(|(p, _)| *p == provider)
+        {
+            Some((_, list)) => list.push(role),
+            None => roles.push((provider, vec![role])),
+        };
+        add(primary, "primary");
+        add(classifier, "classifier");
+        for provider in fallback {
+            add(*provider, "fallback");
+        }
+        if search {
+            add(Provider::Perplexity, "search");
+        }
+        roles
+    }
+
+    /// Sends the cheapest authenticated request a provider offers, to find out
+    /// whether its credentials work.
+    pub async fn verify_credentials(provider: Provider) -> Result<()> {
+        if !provider.is_configured() {
+            return Err(anyhow!("Not configured"));
+        }
+        if provider == Provider::Local {
+            // The local client has no timeout of its own, and a hung server must not stall probes
+            return match tokio::time::timeout(CHECK_TIMEOUT, list_local_models()).await {
+                Ok(models) => models.map(|_| ()),
+                Err(_) => Err(anyhow!("Timed out listing local models")),
+            };
+        }
+        let key = provider
+            .api_key_var()
+            .and_then(|var| env::var(var).ok())
+            .unwrap_or_default();
+        let client = Client::builder().timeout(CHECK_TIMEOUT).build()?;
+        let request = match provider {
+            Provider::OpenAI => client
+                .get("https://api.openai.com/v1/models")
+                .header(header::AUTHORIZATION, format!("Bearer {key}")),
+            Provider::Anthropic => client
+                .get("https://api.anthropic.com/v1/models")
+                .header("x-api-key", key)
+                .header("anthropic-version", ANTHROPIC_VERSION),
+            Provider::Gemini => client
+                .get("https://generativelanguage.googleapis.com/v1beta/models")
+                .header(GEMINI_KEY_HEADER, key),
+            // Perplexity has no free endpoint, so a present key has to do
+            Provider::Perplexity | Provider::Local => return Ok(()),
+        };
+        let response = request.send().await.map_err(reqwest::Error::without_url)?;
+        if response.status() != StatusCode::OK {
+            return Err(UpstreamError::new(provider.name(), response.status()).into());
+        }
+        Ok(())
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct ProviderStatus {
+        pub provider: &'static str,
+        pub roles: Vec<&'static str>,
+        /// Whether Saturn can't answer without this provider.
+        pub required: bool,
+        pub ok: bool,
+    }
+
+    /// The outcome of a readiness check. Saturn is ready when its primary and
+    /// classifier providers work; fallback and search providers are reported
+    /// but optional. Probes are unauthenticated, so why a check failed only
+    /// goes to the log.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct Readiness {
+        pub ready: bool,
+        pub providers: Vec<ProviderStatus>,
+    }
+
+    impl Readiness {
+        pub fn from_results(results: Vec<(Provider, Vec<&'static str>, Result<()>)>) -> Self {
+            let providers: Vec<ProviderStatus> = results
+                .into_iter()
+                .map(|(provider, roles, result)| {
+                    if let Err(e) = &result {
+                        warn!(%provider, error = %format!("{e:#}"), "Readiness check failed");
+                    }
+                    ProviderStatus {
+                        provider: provider.name(),
+                        required: roles.iter().any(|r| *r == "primary" || *r == "classifier"),
+                        roles,
+                        ok: result.is_ok(),
+                    }
+                })
+                .collect();
+            Readiness {
+                ready: providers.iter().all(|status| status.ok || !status.required),
+                providers,
+            }
+        }
+    }
+
+    /// Checks every provider in the configured routing.
+    pub async fn check_readiness() -> Readiness {
+        let roles = provider_roles(
+            primary_provider(),
+            classifier_provider(),
+            &fallback_chain(),
+            Provider::Perplexity.is_configured(),
+        );
+        let checks = roles.into_iter().map(|(provider, roles)| async move {
+            (provider, roles, verify_credentials(provider).await)
+        });
+        Readiness::from_results(futures_util::future::join_all(checks).await)
+    }
+
+    /// `check_readiness`, reusing the last result for `READINESS_TTL`.
+    #[derive(Default)]
+    pub struct ReadinessCache {
+        last: Mutex<Option<(Instant, Readiness)>>,
+    }
+
+    impl ReadinessCache {
+        pub async fn check(&self) -> Readiness {
+            let mut last = self.last.lock().await;
+            if let Some((checked, readiness)) = last.as_ref() {
+                if checked.elapsed() < READINESS_TTL {
+                    return readiness.clone();
+                }
+            }
+            let readiness = check_readiness().await;
+            *last = Some((Instant::now(), readiness.clone()));
+            readiness
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::health::{provider_roles, Readiness};
+    use crate::chat_completions::providers::router::router::Provider;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_provider_roles_merge() {
+        let roles = provider_roles(
+            Provider::OpenAI,
+            Provider::OpenAI,
+            &[Provider::Anthropic, Provider::Gemini],
+            true,
+        );
+        assert_eq!(
+            roles,
+            vec![
+                (Provider::OpenAI, vec!["primary", "classifier"]),
+                (Provider::Anthropic, vec!["fallback"]),
+                (Provider::Gemini, vec!["fallback"]),
+                (Provider::Perplexity, vec!["search"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_required_providers_block_readiness() {
+        let readiness = Readiness::from_results(vec![
+            (Provider::OpenAI, vec!["primary", "classifier"], Ok(())),
+            (
+                Provider::Gemini,
+                vec!["fallback"],
+                Err(anyhow!("Not configured")),
+            ),
+        ]);
+        assert!(readiness.ready);
+        assert!(!readiness.providers[1].ok);
+        // Failure details stay out of the unauthenticated probe's body
+        let body = serde_json::to_string(&readiness).unwrap();
+        assert!(!body.contains("Not configured"));
+
+        let readiness = Readiness::from_results(vec![(
+            Provider::Anthropic,
+            vec!["primary"],
+            Err(anyhow!("401")),
+        )]);
+        assert!(!readiness.ready);
+        assert!(readiness.providers[0].required);
+    }
+}
@@ -0,0 +1,216 @@
+pub mod input {
+    use crate::chat_completions::utils::paths::paths::saturn_file;
+    use anyhow::Result;
+    use rustyline::completion::{Completer, Pair};
+    use rustyline::error::ReadlineError;
+    use rustyline::highlight::Highlighter;
+    use rustyline::hint::Hinter;
+    use rustyline::history::DefaultHistory;
+    use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+    use rustyline::{
+        Cmd, CompletionType, Config, Context, Editor, EventHandler, Helper, KeyCode, KeyEvent,
+        Modifiers,
+    };
+    use std::path::PathBuf;
+    use tracing::warn;
+
+    /// Lines kept in the input history file.
+    const MAX_HISTORY: usize = 1000;
+
+    /// Whether `text` asks for another line: the last line ends with a `\`
+    /// or a ``` fence is still open.
+    pub fn is_incomplete(text: &str) -> bool {
+        let fences = text
+            .lines()
+            .filter(|line| line.trim_start().starts_with("```"))
+            .count();
+        text.ends_with('\\') || fences % 2 == 1
+    }
+
+    /// Drops the `\` that continued each line, keeping the line breaks.
+    pub fn join_continuations(text: &str) -> String {
+        text.replace("\\\n", "\n")
+    }
+
+    /// The commands completing the text before the cursor, which must start
+    /// with `/`. Commands may include a subcommand, as in `/memory add`.
+    pub fn complete_command<'a>(commands: &'a [String], before_cursor: &str) -> Vec<&'a str> {
+        if !before_cursor.starts_with('/') {
+            return Vec::new();
+        }
+        commands
+            .iter()
+            .map(String::as_str)
+            .filter(|command| command.starts_with(before_cursor))
+            .collect()
+    }
+
+    /// Tab completion of slash-commands and the multi-line rules for the
+    /// chat prompt.
+    struct ChatHelper {
+        commands: Vec<String>,
+    }
+
+    impl Completer for ChatHelper {
+        type Candidate = Pair;
+
+        fn complete(
+            &self,
+            line: &str,
+            pos: usize,
+            _ctx: &Context<'_>,
+        ) -> rustyline::Result<(usize, Vec<Pair>)> {
+            let candidates = complete_command(&self.commands, &line[..pos])
+                .into_iter()
+                .map(|command| Pair {
+                    display: command.to_string(),
+                    replacement: format!("{command} "),
+                })
+                .collect();
+            Ok((0, candidates))
+        }
+    }
+
+    impl Validator for ChatHelper {
+        fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+            if is_incomplete(ctx.input()) {
+                Ok(ValidationResult::Incomplete)
+            } else {
+                Ok(ValidationResult::Valid(None))
+            }
+        }
+    }
+
+    impl Hinter for ChatHelper {
+        type Hint = String;
+    }
+
+    impl Highlighter for ChatHelper {}
+
+    impl Helper for ChatHelper {}
+
+    /// What the user did at the prompt.
+    pub enum Input {
+        /// A message or command, possibly spanning several lines.
+        Line(String),
+        /// Ctrl-C.
+        Interrupted,
+        /// Ctrl-D or the end of piped input.
+        Eof,
+    }
+
+    /// The chat prompt: line editing, input history kept across sessions in
+    /// `~/.saturn/chat_history.txt`, multi-line messages and completion of
+    /// slash-commands. Without a terminal it reads plain lines.
+    pub struct LineReader {
+        editor: Option<Editor<ChatHelper, DefaultHistory>>,
+        history: Option<PathBuf>,
+    }
+
+    impl LineReader {
+        pub fn new(commands: Vec<String>) -> Result<Self> {
+            let config = Config::builder()
+                .max_history_size(MAX_HISTORY)?
+                .history_ignore_dups(true)?
+                .completion_type(CompletionType::List)
+                .build();
+            let mut editor = Editor::with_config(config)?;
+            editor.set_helper(Some(ChatHelper { commands }));
+            // Alt-Enter starts a new line instead of sending
+            editor.bind_sequence(
+                KeyEvent(KeyCode::Enter, Modifiers::ALT),
+                EventHandler::Simple(Cmd::Newline),
+            );
+
+            let history = saturn_file("chat_history.txt")
+                .map_err(|e| warn!("Input history unavailable: {}", e))
+                .ok();
+            if let Some(path) = history.as_ref().filter(|path| path.exists()) {
+                if let Err(e) = editor.load_history(path) {
+                    warn!("Failed to load input history: {}", e);
+                }
+            }
+            Ok(LineReader {
+                editor: Some(editor),
+                history,
+            })
+        }
+
+        /// Reads one message. The editor blocks, so it runs off the async runtime.
+        pub async fn read(&mut self, prompt: &str) -> Result<Input> {
+            let Some(mut editor) = self.editor.take() else {
+                return Ok(Input::Eof);
+            };
+            let prompt = prompt.to_string();
+            let (editor, line) = tokio::task::spawn_blocking(move || {
+                let line = editor.readline(&prompt);
+                (editor, line)
+            })
+            .await?;
+            self.editor = Some(editor);
+
+            match line {
+                Ok(line) => {
+                    let line = join_continuations(&line);
+                    self.remember(&line);
+                    Ok(Input::Line(line))
+                }
+                Err(ReadlineError::Interrupted) => Ok(Input::Interrupted),
+                Err(ReadlineError::Eof) => Ok(Input::Eof),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        /// Adds a line to the history and saves it, so it survives a crash.
+        fn remember(&mut self, line: &str) {
+            let Some(editor) = self.editor.as_mut() else {
+                return;
+            };
+            if line.trim().is_empty() || !editor.add_history_entry(line).unwrap_or(false) {
+                return;
+            }
+            if let Some(path) = &self.history {
+                if let Err(e) = editor.save_history(path) {
+                    warn!("Failed to save input history: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::input::{complete_command, is_incomplete, join_continuations};
+
+    #[test]
+    fn test_multi_line_input() {
+        assert!(!is_incomplete("hello"));
+        assert!(is_incomplete("first line \\"));
+        assert!(!is_incomplete("first line \\\nsecond line"));
+        assert!(is_incomplete("```rust\nfn main() {}"));
+        assert!(!is_incomplete("```rust\nfn main() {}\n```"));
+        assert_eq!(
+            join_continuations("first line \\\nsecond line"),
+            "first line \nsecond line"
+        );
+    }
+
+    #[test]
+    fn test_complete_command() {
+        let commands = vec![
+            "/exit".to_string(),
+            "/memory add".to_string(),
+            "/memory list".to_string(),
+        ];
+        assert_eq!(
+            complete_command(&commands, "/mem"),
+            vec!["/memory add", "/memory list"]
+        );
+        assert_eq!(
+            complete_command(&commands, "/memory l"),
+            vec!["/memory list"]
+        );
+        assert!(complete_command(&commands, "mem").is_empty());
+        assert!(complete_command(&commands, "/memory list ").is_empty());
+    }
+}
@@ -0,0 +1,261 @@
+pub mod limits {
+    use crate::chat_completions::utils::errors::errors::{ApiError, ErrorKind};
+    use dotenv::dotenv;
+    use std::collections::HashMap;
+    use std::env;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+    use tokio::time::timeout;
+
+    /// How hard the server may be driven. A limit of `0` disables it.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct LimitsConfig {
+        /// Requests per minute for each client (`SATURN_CLIENT_RPM`, default 30).
+        pub client_per_minute: u32,
+        /// Requests per minute across all clients (`SATURN_GLOBAL_RPM`, default 300).
+        pub global_per_minute: u32,
+        /// Saturn turns running at once (`SATURN_MAX_CONCURRENCY`, default 4).
+        pub max_concurrency: usize,
+        /// How long a request may wait for a free slot (`SATURN_QUEUE_TIMEOUT_SECS`, default 30).
+        pub queue_timeout: Duration,
+    }
+
+    impl Default for LimitsConfig {
+        fn default() -> Self {
+            LimitsConfig {
+                client_per_minute: 30,
+                global_per_minute: 300,
+                max_concurrency: 4,
+                queue_timeout: Duration::from_secs(30),
+            }
+        }
+    }
+
+    fn env_number<T: std::str::FromStr>(var: &str, default: T) -> T {
+        env::var(var)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(default)
+    }
+
+    impl LimitsConfig {
+        pub fn from_env() -> Self {
+            dotenv().ok();
+            let default = LimitsConfig::default();
+            LimitsConfig {
+                client_per_minute: env_number("SATURN_CLIENT_RPM", default.client_per_minute),
+                global_per_minute: env_number("SATURN_GLOBAL_RPM", default.global_per_minute),
+                max_concurrency: env_number("SATURN_MAX_CONCURRENCY", default.max_concurrency),
+                queue_timeout: Duration::from_secs(env_number(
+                    "SATURN_QUEUE_TIMEOUT_SECS",
+                    default.queue_timeout.as_secs(),
+                )),
+            }
+        }
+    }
+
+    /// A bucket holding up to a minute's worth of requests, refilled continuously.
+    #[derive(Clone, Debug)]
+    pub struct TokenBucket {
+        capacity: f64,
+        refill_per_sec: f64,
+        tokens: f64,
+        updated: Instant,
+    }
+
+    impl TokenBucket {
+        pub fn per_minute(requests: u32, now: Instant) -> Self {
+            TokenBucket {
+                capacity: requests as f64,
+                refill_per_sec: requests as f64 / 60.0,
+                tokens: requests as f64,
+                updated: now,
+            }
+        }
+
+        fn refill(&mut self, now: Instant) {
+            let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+            self.updated = now;
+        }
+
+        /// How long until a request fits, or zero if one fits now.
+        pub fn wait_time(&mut self, now: Instant) -> Duration {
+            self.refill(now);
+            if self.tokens >= 1.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec)
+            }
+        }
+
+        pub fn take(&mut self, now: Instant) {
+            self.refill(now);
+            self.tokens -= 1.0;
+        }
+    }
+
+    fn rate_limited(message: String, wait: Duration) -> ApiError {
+        ApiError::new(ErrorKind::RateLimited, message).with_retry_after(wait)
+    }
+
+    /// Permission to run a Saturn turn. The concurrency slot frees when it drops.
+    pub struct Admission {
+        _permit: Option<OwnedSemaphorePermit>,
+    }
+
+    /// Per-client and global rate limits plus a bounded number of concurrent turns.
+    pub struct Limits {
+        config: LimitsConfig,
+        clients: Mutex<HashMap<String, TokenBucket>>,
+        global: Mutex<TokenBucket>,
+        slots: Arc<Semaphore>,
+    }
+
+    impl Limits {
+        pub fn new(config: LimitsConfig) -> Self {
+            let now = Instant::now();
+            Limits {
+                global: Mutex::new(TokenBucket::per_minute(config.global_per_minute, now)),
+                clients: Mutex::new(HashMap::new()),
+                slots: Arc::new(Semaphore::new(config.max_concurrency.max(1))),
+                config,
+            }
+        }
+
+        pub fn from_env() -> Self {
+            Limits::new(LimitsConfig::from_env())
+        }
+
+        pub fn config(&self) -> &LimitsConfig {
+            &self.config
+        }
+
+        /// Counts a request against `client`'s and the global rate limit. Nothing is
+        /// counted when either is exhausted; the error says how long to wait.
+        pub fn check_rate(&self, client: &str) -> Result<(), ApiError> {
+            self.check_rate_at(client, Instant::now())
+        }
+
+        pub fn check_rate_at(&self, client: &str, now: Instant) -> Result<(), ApiError> {
+            let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+            let mut global = self.global.lock().unwrap_or_else(|e| e.into_inner());
+
+            let mut client_bucket = None;
+            if self.config.client_per_minute > 0 {
+                let bucket = clients
+                    .entry(client.to_string())
+                    .or_insert_with(|| TokenBucket::per_minute(self.config.client_per_minute, now));
+                let wait = bucket.wait_time(now);
+                if !wait.is_zero() {
+                    return Err(rate_limited(
+                        format!(
+                            "Rate limit of {} requests per minute exceeded for '{client}'",
+                            self.config.client_per_minute
+                        ),
+                        wait,
+                    ));
+                }
+                client_bucket = Some(bucket);
+            }
+            if self.config.global_per_minute > 0 {
+                let wait = global.wait_time(now);
+                if !wait.is_zero() {
+                    return Err(rate_limited(
+                        "The server's global rate limit is exhausted".to_string(),
+                        wait,
+                    ));
+                }
+                global.take(now);
+            }
+            if let Some(bucket) = client_bucket {
+                bucket.take(now);
+            }
+            Ok(())
+        }
+
+        /// Waits for a concurrency slot, giving up after the queue timeout.
+        pub async fn admit(&self) -> Result<Admission, ApiError> {
+            if self.config.max_concurrency == 0 {
+                return Ok(Admission { _permit: None });
+            }
+            match timeout(
+                self.config.queue_timeout,
+                self.slots.clone().acquire_owned(),
+            )
+            .await
+            {
+                Ok(Ok(permit)) => Ok(Admission {
+                    _permit: Some(permit),
+                }),
+                Ok(Err(_)) => Err(ApiError::new(
+                    ErrorKind::Unavailable,
+                    "The server is shutting down",
+                )),
+                Err(_) => Err(ApiError::new(
+                    ErrorKind::RateLimited,
+                    "Timed out waiting for a free slot; the server is at capacity",
+                )
+                .with_retry_after(Duration::from_secs(5))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::limits::{Limits, LimitsConfig, TokenBucket};
+    use crate::chat_completions::utils::errors::errors::ErrorKind;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::per_minute(2, start);
+        bucket.take(start);
+        bucket.take(start);
+        assert_eq!(bucket.wait_time(start), Duration::from_secs(30));
+        assert_eq!(
+            bucket.wait_time(start + Duration::from_secs(30)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_client_and_global_limits() {
+        let limits = Limits::new(LimitsConfig {
+            client_per_minute: 2,
+            global_per_minute: 3,
+            ..LimitsConfig::default()
+        });
+        let now = Instant::now();
+        assert!(limits.check_rate_at("a", now).is_ok());
+        assert!(limits.check_rate_at("a", now).is_ok());
+
+        let limited = limits.check_rate_at("a", now).unwrap_err();
+        assert_eq!(limited.kind, ErrorKind::RateLimited);
+        assert_eq!(limited.retry_after_secs(), Some(30));
+
+        // "b" has its own budget, but only one global request is left
+        assert!(limits.check_rate_at("b", now).is_ok());
+        let global = limits.check_rate_at("b", now).unwrap_err();
+        assert!(global.message.contains("global"));
+    }
+
+    #[tokio::test]
+    async fn test_admission_times_out_at_capacity() {
+        let limits = Limits::new(LimitsConfig {
+            max_concurrency: 1,
+            queue_timeout: Duration::from_millis(20),
+            ..LimitsConfig::default()
+        });
+        let first = limits.admit().await.unwrap();
+        let queued = limits.admit().await.err().unwrap();
+        assert_eq!(queued.kind, ErrorKind::RateLimited);
+        assert!(queued.retry_after.is_some());
+
+        drop(first);
+        assert!(limits.admit().await.is_ok());
+    }
+}
@@ -0,0 +1,628 @@
+pub mod markdown {
+    use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+    use crossterm::terminal;
+    use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+    use std::sync::OnceLock;
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::{Theme, ThemeSet};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+    use unicode_width::UnicodeWidthStr;
+
+    /// Answers are wrapped to the terminal, but never wider than this, so lines
+    /// stay readable on wide screens.
+    pub const MAX_WIDTH: usize = 100;
+
+    const CODE_COLOR: Color = Color::Rgb {
+        r: 150,
+        g: 200,
+        b: 255,
+    };
+    const LINK_COLOR: Color = Color::Rgb {
+        r: 100,
+        g: 160,
+        b: 255,
+    };
+    const MUTED_COLOR: Color = Color::Rgb {
+        r: 100,
+        g: 100,
+        b: 100,
+    };
+    const CODE_THEME: &str = "base16-ocean.dark";
+
+    /// The width to wrap answers to: the terminal's, capped at `MAX_WIDTH`.
+    pub fn terminal_width() -> usize {
+        terminal::size()
+            .map(|(columns, _)| columns as usize)
+            .unwrap_or(80)
+            .clamp(20, MAX_WIDTH)
+    }
+
+    fn syntaxes() -> &'static SyntaxSet {
+        static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+        SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+    }
+
+    fn theme() -> &'static Theme {
+        static THEME: OnceLock<Theme> = OnceLock::new();
+        THEME.get_or_init(|| {
+            let mut themes = ThemeSet::load_defaults();
+            themes.themes.remove(CODE_THEME).unwrap_or_default()
+        })
+    }
+
+    /// Renders Markdown for the terminal: headings, emphasis, lists, quotes,
+    /// links and tables are styled with ANSI escapes and wrapped to `width`
+    /// columns; fenced code keeps its lines and is highlighted by language.
+    /// Plain text is drawn in `color`.
+    pub fn render_markdown(text: &str, width: usize, color: Color) -> String {
+        let options =
+            Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        let mut renderer = Renderer::new(width, color);
+        for event in Parser::new_ext(text, options) {
+            renderer.event(event);
+        }
+        renderer.finish()
+    }
+
+    /// Highlights `code` as `language` (a fence token such as `rust` or `py`),
+    /// one terminal line per source line.
+    pub fn highlight_code(code: &str, language: &str) -> Vec<String> {
+        let syntaxes = syntaxes();
+        let syntax = syntaxes
+            .find_syntax_by_token(language)
+            .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+        let mut highlighter = HighlightLines::new(syntax, theme());
+        LinesWithEndings::from(code)
+            .map(|line| match highlighter.highlight_line(line, syntaxes) {
+                Ok(ranges) => format!(
+                    "{}\x1b[0m",
+                    as_24_bit_terminal_escaped(&ranges, false).trim_end_matches('\n')
+                ),
+                Err(_) => line.trim_end_matches('\n').to_string(),
+            })
+            .collect()
+    }
+
+    /// The columns `text` takes up once its ANSI escapes are drawn.
+    pub fn visible_width(text: &str) -> usize {
+        strip_ansi(text).width()
+    }
+
+    /// `text` without ANSI escape sequences.
+    pub fn strip_ansi(text: &str) -> String {
+        let mut plain = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                // CSI sequences end with a letter
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            } else {
+                plain.push(c);
+            }
+        }
+        plain
+    }
+
+    /// A line prefix owned by an enclosing block: a list item's marker on its
+    /// first line and matching spaces after, or a quote bar on every line.
+    struct Indent {
+        first: String,
+        rest: String,
+        used: bool,
+    }
+
+    /// A table being collected; it is drawn once all its cells are known.
+    #[derive(Default)]
+    struct Table {
+        rows: Vec<Vec<String>>,
+        cell: String,
+    }
+
+    /// Walks the Markdown events, writing wrapped and styled lines.
+    struct Renderer {
+        width: usize,
+        base: ContentStyle,
+        lines: Vec<String>,
+        line: String,
+        line_width: usize,
+        line_has_text: bool,
+        pending_spaces: usize,
+        styles: Vec<ContentStyle>,
+        indents: Vec<Indent>,
+        lists: Vec<Option<u64>>,
+        code: Option<(String, String)>,
+        table: Option<Table>,
+        link: Option<(String, String)>,
+    }
+
+    impl Renderer {
+        fn new(width: usize, color: Color) -> Self {
+            let base = ContentStyle {
+                foreground_color: Some(color),
+                ..ContentStyle::default()
+            };
+            Renderer {
+                width: width.max(20),
+                base,
+                lines: Vec::new(),
+                line: String::new(),
+                line_width: 0,
+                line_has_text: false,
+                pending_spaces: 0,
+                styles: vec![base],
+                indents: Vec::new(),
+                lists: Vec::new(),
+                code: None,
+                table: None,
+                link: None,
+            }
+        }
+
+        fn style(&self) -> ContentStyle {
+            *self.styles.last().unwrap_or(&self.base)
+        }
+
+        fn push_style(&mut self, change: impl FnOnce(&mut ContentStyle)) {
+            let mut style = self.style();
+            change(&mut style);
+            self.styles.push(style);
+        }
+
+        fn pop_style(&mut self) {
+            if self.styles.len() > 1 {
+                self.styles.pop();
+            }
+        }
+
+        /// Starts the current line with the enclosing blocks' prefixes.
+        fn start_line(&mut self) {
+            if self.line_width > 0 || self.line_has_text {
+                return;
+            }
+            let mut prefix = String::new();
+            for indent in &mut self.indents {
+                prefix.push_str(if indent.used {
+                    &indent.rest
+                } else {
+                    &indent.first
+                });
+                indent.used = true;
+            }
+            self.line_width = visible_width(&prefix);
+            self.line = format!("{}", prefix.with(MUTED_COLOR));
+        }
+
+        fn end_line(&mut self) {
+            self.start_line();
+            let line = std::mem::take(&mut self.line);
+            self.lines.push(line.trim_end().to_string());
+            self.line_width = 0;
+            self.line_has_text = false;
+            self.pending_spaces = 0;
+        }
+
+        /// Ends the current line, if anything is on it.
+        fn flush(&mut self) {
+            if self.line_has_text {
+                self.end_line();
+            }
+        }
+
+        /// Separates blocks with a blank line, except between items of a list.
+        fn block_gap(&mut self) {
+            self.flush();
+            let in_list = !self.lists.is_empty();
+            if !in_list && self.lines.last().is_some_and(|line| !line.is_empty()) {
+                self.lines.push(String::new());
+            }
+        }
+
+        /// Adds `text` in the current style, wrapping at spaces. Spaces are held
+        /// back until the next word, so wrapped lines never end in them.
+        fn text(&mut self, text: &str) {
+            for word in text.split_inclusive(' ') {
+                let body = word.trim_end_matches(' ');
+                let spaces = word.len() - body.len();
+                self.start_line();
+                if !body.is_empty() {
+                    if self.line_has_text
+                        && self.line_width + self.pending_spaces + body.width() > self.width
+                    {
+                        self.end_line();
+                        self.start_line();
+                    } else if self.line_has_text {
+                        self.line.push_str(&" ".repeat(self.pending_spaces));
+                        self.line_width += self.pending_spaces;
+                    }
+                    self.line.push_str(&format!("{}", self.style().apply(body)));
+                    self.line_width += body.width();
+                    self.line_has_text = true;
+                    self.pending_spaces = 0;
+                }
+                if self.line_has_text {
+                    self.pending_spaces += spaces;
+                }
+            }
+        }
+
+        fn event(&mut self, event: Event) {
+            if let Some(table) = &mut self.table {
+                match event {
+                    Event::Text(text) | Event::Code(text) => table.cell.push_str(&text),
+                    Event::End(TagEnd::TableCell) => {
+                        let cell = std::mem::take(&mut table.cell);
+                        match table.rows.last_mut() {
+                            Some(row) => row.push(cell.trim().to_string()),
+                            None => table.rows.push(vec![cell.trim().to_string()]),
+                        }
+                    }
+                    Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                        table.rows.push(Vec::new())
+                    }
+                    Event::End(TagEnd::Table) => self.draw_table(),
+                    _ => {}
+                }
+                return;
+            }
+            if let Some((_, code)) = &mut self.code {
+                match event {
+                    Event::Text(text) => code.push_str(&text),
+                    Event::End(TagEnd::CodeBlock) => self.draw_code(),
+                    _ => {}
+                }
+                return;
+            }
+
+            match event {
+                Event::Start(tag) => self.start(tag),
+                Event::End(tag) => self.end(tag),
+                Event::Text(text) => {
+                    if let Some((_, label)) = &mut self.link {
+                        label.push_str(&text);
+                    }
+                    self.text(&text);
+                }
+                Event::Code(code) => {
+                    self.push_style(|style| style.foreground_color = Some(CODE_COLOR));
+                    self.text(&code);
+                    self.pop_style();
+                }
+                Event::Html(html) | Event::InlineHtml(html) => self.text(&html),
+                Event::SoftBreak => self.text(" "),
+                Event::HardBreak => self.end_line(),
+                Event::Rule => {
+                    self.block_gap();
+                    self.start_line();
+                    let rule = "─".repeat(self.width.saturating_sub(self.line_width));
+                    self.line.push_str(&format!("{}", rule.with(MUTED_COLOR)));
+                    self.line_has_text = true;
+                    self.end_line();
+                }
+                Event::TaskListMarker(done) => self.text(if done { "[x] " } else { "[ ] " }),
+                Event::FootnoteReference(name) => self.text(&format!("[{name}]")),
+                _ => {}
+            }
+        }
+
+        fn start(&mut self, tag: Tag) {
+            match tag {
+                // Tight list items hold their text without a paragraph gap
+                Tag::Paragraph if self.lists.is_empty() || self.line_has_text => self.block_gap(),
+                Tag::Heading { level, .. } => {
+                    self.block_gap();
+                    self.push_style(|style| {
+                        style.attributes.set(Attribute::Bold);
+                        if level == HeadingLevel::H1 {
+                            style.attributes.set(Attribute::Underlined);
+                        }
+                    });
+                }
+                Tag::BlockQuote(_) => {
+                    self.block_gap();
+                    self.indents.push(Indent {
+                        first: "│ ".to_string(),
+                        rest: "│ ".to_string(),
+                        used: false,
+                    });
+                    self.push_style(|style| {
+                        style.foreground_color = Some(MUTED_COLOR);
+                        style.attributes.set(Attribute::Italic);
+                    });
+                }
+                Tag::CodeBlock(kind) => {
+                    self.block_gap();
+                    let language = match kind {
+                        CodeBlockKind::Fenced(info) => {
+                            info.split_whitespace().next().unwrap_or("").to_string()
+                        }
+                        CodeBlockKind::Indented => String::new(),
+                    };
+                    self.code = Some((language, String::new()));
+                }
+                Tag::List(start) => {
+                    if self.lists.is_empty() {
+                        self.block_gap();
+                    } else {
+                        self.flush();
+                    }
+                    self.lists.push(start);
+                }
+                Tag::Item => {
+                    self.flush();
+                    let marker = match self.lists.last_mut() {
+                        Some(Some(number)) => {
+                            *number += 1;
+                            format!("{}. ", *number - 1)
+                        }
+                        _ => "• ".to_string(),
+                    };
+                    self.indents.push(Indent {
+                        rest: " ".repeat(marker.width()),
+                        first: marker,
+                        used: false,
+                    });
+                }
+                Tag::Emphasis => self.push_style(|style| style.attributes.set(Attribute::Italic)),
+                Tag::Strong => self.push_style(|style| style.attributes.set(Attribute::Bold)),
+                Tag::Strikethrough => {
+                    self.push_style(|style| style.attributes.set(Attribute::CrossedOut))
+                }
+                Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                    self.link = Some((dest_url.to_string(), String::new()));
+                    self.push_style(|style| {
+                        style.foreground_color = Some(LINK_COLOR);
+                        style.attributes.set(Attribute::Underlined);
+                    });
+                }
+                Tag::Table(_) => {
+                    self.block_gap();
+                    self.table = Some(Table::default());
+                }
+                _ => {}
+            }
+        }
+
+        fn end(&mut self, tag: TagEnd) {
+            match tag {
+                TagEnd::Paragraph => self.flush(),
+                TagEnd::Heading(_) => {
+                    self.pop_style();
+                    self.flush();
+                }
+                TagEnd::BlockQuote(_) => {
+                    self.flush();
+                    self.indents.pop();
+                    self.pop_style();
+                }
+                TagEnd::List(_) => {
+                    self.flush();
+                    self.lists.pop();
+                }
+                TagEnd::Item => {
+                    self.flush();
+                    self.indents.pop();
+                }
+                TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => self.pop_style(),
+                TagEnd::Link | TagEnd::Image => {
+                    self.pop_style();
+                    if let Some((url, label)) = self.link.take() {
+                        // Show where a link goes unless its text already says so
+                        if !url.is_empty() && url != label {
+                            self.push_style(|style| style.foreground_color = Some(MUTED_COLOR));
+                            self.text(&format!(" ({url})"));
+                            self.pop_style();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn draw_code(&mut self) {
+            let Some((language, code)) = self.code.take() else {
+                return;
+            };
+            if !language.is_empty() {
+                self.start_line();
+                self.line
+                    .push_str(&format!("{}", language.as_str().with(MUTED_COLOR)));
+                self.line_has_text = true;
+                self.end_line();
+            }
+            for line in highlight_code(&code, &language) {
+                self.start_line();
+                self.line.push_str("  ");
+                self.line.push_str(&line);
+                self.line_has_text = true;
+                self.end_line();
+            }
+        }
+
+        fn draw_table(&mut self) {
+            let Some(table) = self.table.take() else {
+                return;
+            };
+            let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+            if columns == 0 {
+                return;
+            }
+            let mut widths: Vec<usize> = (0..columns)
+                .map(|column| {
+                    table
+                        .rows
+                        .iter()
+                        .filter_map(|row| row.get(column))
+                        .map(|cell| cell.width())
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            // Narrow the widest columns until the table fits, truncating their cells
+            let separators = 3 * (columns - 1);
+            while widths.iter().sum::<usize>() + separators > self.width {
+                let Some(widest) = widths.iter_mut().max().filter(|width| **width > 3) else {
+                    break;
+                };
+                *widest -= 1;
+            }
+
+            let separator = format!("{}", " │ ".with(MUTED_COLOR));
+            for (index, row) in table.rows.iter().enumerate() {
+                let cells: Vec<String> = widths
+                    .iter()
+                    .enumerate()
+                    .map(|(column, width)| {
+                        let cell = fit_cell(row.get(column).map_or("", String::as_str), *width);
+                        // The last column needs no padding
+                        let cell = if column + 1 == columns {
+                            cell.trim_end().to_string()
+                        } else {
+                            cell
+                        };
+                        let style = if index == 0 {
+                            let mut style = self.base;
+                            style.attributes.set(Attribute::Bold);
+                            style
+                        } else {
+                            self.base
+                        };
+                        format!("{}", style.apply(cell))
+                    })
+                    .collect();
+                self.start_line();
+                self.line.push_str(&cells.join(&separator));
+                self.line_has_text = true;
+                self.end_line();
+                if index == 0 {
+                    let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                    self.start_line();
+                    self.line
+                        .push_str(&format!("{}", rule.join("─┼─").with(MUTED_COLOR)));
+                    self.line_has_text = true;
+                    self.end_line();
+                }
+            }
+        }
+
+        fn finish(mut self) -> String {
+            self.flush();
+            while self.lines.last().is_some_and(|line| line.is_empty()) {
+                self.lines.pop();
+            }
+            self.lines.join("\n")
+        }
+    }
+
+    /// Pads or truncates `cell` to exactly `width` columns.
+    fn fit_cell(cell: &str, width: usize) -> String {
+        if cell.width() <= width {
+            return format!("{cell}{}", " ".repeat(width - cell.width()));
+        }
+        let mut fitted = String::new();
+        for c in cell.chars() {
+            if fitted.width() + c.to_string().width() + 1 > width {
+                break;
+            }
+            fitted.push(c);
+        }
+        fitted.push('…');
+        format!(
+            "{fitted}{}",
+            " ".repeat(width.saturating_sub(fitted.width()))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::markdown::{highlight_code, render_markdown, strip_ansi, visible_width};
+    use crossterm::style::Color;
+
+    fn plain(markdown: &str, width: usize) -> Vec<String> {
+        strip_ansi(&render_markdown(markdown, width, Color::Yellow))
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_wraps_to_width() {
+        let text = "Saturn is the sixth planet from the Sun and the second largest in the \
+                    Solar System, after Jupiter.";
+        let rendered = render_markdown(text, 30, Color::Yellow);
+        assert!(rendered.lines().count() > 1);
+        assert!(rendered.lines().all(|line| visible_width(line) <= 30));
+        assert_eq!(
+            strip_ansi(&rendered).split_whitespace().collect::<Vec<_>>(),
+            text.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let lines = plain(
+            "# Moons\n\nSaturn has **many** moons:\n\n- Titan\n- Enceladus, with \
+             [geysers](https://example.com)\n\n1. First\n2. Second\n\n> Quoted",
+            80,
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "Moons",
+                "",
+                "Saturn has many moons:",
+                "",
+                "• Titan",
+                "• Enceladus, with geysers (https://example.com)",
+                "",
+                "1. First",
+                "2. Second",
+                "",
+                "│ Quoted",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_items_wrap_under_their_text() {
+        let lines = plain("- one two three four five six", 20);
+        assert_eq!(lines, vec!["• one two three four", "  five six"]);
+    }
+
+    #[test]
+    fn test_tables_align() {
+        let lines = plain(
+            "| Moon | Radius |\n|---|---|\n| Titan | 2575 km |\n| Mimas | 198 km |",
+            80,
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "Moon  │ Radius",
+                "──────┼────────",
+                "Titan │ 2575 km",
+                "Mimas │ 198 km",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_are_highlighted() {
+        let rendered = render_markdown("```rust\nfn main() {}\n```", 80, Color::Yellow);
+        assert!(rendered.contains("\x1b[38;2;"));
+        assert_eq!(
+            strip_ansi(&rendered).lines().collect::<Vec<_>>(),
+            vec!["rust", "  fn main() {}"]
+        );
+        // Unknown languages still render, as plain text
+        assert_eq!(
+            strip_ansi(&highlight_code("x = 1\n", "no-such-language").join("\n")),
+            "x = 1"
+        );
+    }
+}
@@ -1 +1,10 @@
+pub mod auth;
 pub mod chat;
+pub mod commands;
+pub mod health;
+pub mod input;
+pub mod limits;
+pub mod markdown;
+pub mod openai_compat;
+pub mod tui;
+pub mod websocket;
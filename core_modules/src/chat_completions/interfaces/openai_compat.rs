@@ -0,0 +1,390 @@
+pub mod openai_compat {
+    use crate::chat_completions::bots::saturn::saturn::SaturnOptions;
+    use crate::chat_completions::providers::router::router::Provider;
+    use crate::chat_completions::utils::context_window::context_window::{
+        count_message_tokens, count_tokens,
+    };
+    use crate::chat_completions::utils::messages::messages::{ChatMessage, Role};
+    use anyhow::{bail, Result};
+    use chrono::Utc;
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::fmt;
+    use uuid::Uuid;
+
+    /// The model id that runs the full Saturn pipeline with the configured routing.
+    pub const SATURN_MODEL: &str = "saturn";
+
+    /// A message in an OpenAI chat completions request. `content` is either a
+    /// string or an array of content parts, of which only text is used.
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct CompatMessage {
+        pub role: String,
+        #[serde(default)]
+        pub content: Value,
+    }
+
+    impl CompatMessage {
+        pub fn text(&self) -> String {
+            match &self.content {
+                Value::String(text) => text.clone(),
+                Value::Array(parts) => parts
+                    .iter()
+                    .filter_map(|part| part.get("text").and_then(Value::as_str))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => String::new(),
+            }
+        }
+    }
+
+    /// The subset of `POST /v1/chat/completions` Saturn understands. Sampling
+    /// parameters are accepted and ignored.
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct ChatCompletionRequest {
+        #[serde(default)]
+        pub model: Option<String>,
+        pub messages: Vec<CompatMessage>,
+        /// Sends the reply as server-sent events. Saturn only settles on an answer
+        /// once it has been judged, so the events carry the finished answer in
+        /// word-sized chunks rather than tokens as they are generated.
+        #[serde(default)]
+        pub stream: bool,
+        /// A Saturn extension: interleaves named `status` events with a streamed
+        /// reply. Off by default, since OpenAI's SDKs fail on named events.
+        #[serde(default)]
+        pub saturn_status: bool,
+    }
+
+    /// The requested model id is not one `/v1/models` lists.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ModelNotFound(pub String);
+
+    impl fmt::Display for ModelNotFound {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "The model '{}' does not exist", self.0)
+        }
+    }
+
+    impl std::error::Error for ModelNotFound {}
+
+    /// Which provider a model id pins drafting to: `None` for `saturn`,
+    /// otherwise a provider name optionally followed by `/` and the model that
+    /// provider is configured with. Anything else is [`ModelNotFound`].
+    pub fn provider_for_model(model: &str) -> Result<Option<Provider>> {
+        if model.is_empty() || model == SATURN_MODEL {
+            return Ok(None);
+        }
+        let (name, suffix) = match model.split_once('/') {
+            Some((name, suffix)) => (name, Some(suffix)),
+            None => (model, None),
+        };
+        let provider: Provider = name.parse().map_err(|_| ModelNotFound(model.to_string()))?;
+        if suffix.is_some_and(|suffix| suffix != provider.model()) {
+            return Err(ModelNotFound(model.to_string()).into());
+        }
+        Ok(Some(provider))
+    }
+
+    /// A Saturn turn derived from a chat completions request.
+    pub struct CompatTurn {
+        pub model: String,
+        pub query: String,
+        pub options: SaturnOptions,
+        /// The full conversation, for usage accounting.
+        pub prompt: Vec<ChatMessage>,
+    }
+
+    impl ChatCompletionRequest {
+        /// Splits the request into Saturn's shape: system messages become the
+        /// system prompt, the final user message the query and the rest history.
+        /// The client owns the conversation, so the turn is not remembered.
+        pub fn into_turn(self, base: &SaturnOptions) -> Result<CompatTurn> {
+            let model = self.model.unwrap_or_else(|| SATURN_MODEL.to_string());
+            let provider = provider_for_model(&model)?;
+
+            let mut system = Vec::new();
+            let mut conversation = Vec::new();
+            for message in &self.messages {
+                match message.role.as_str() {
+                    "system" | "developer" => system.push(message.text()),
+                    "user" => conversation.push(ChatMessage::user(message.text())),
+                    "assistant" => conversation.push(ChatMessage::assistant(message.text())),
+                    // Tool calls are Saturn's own business; client tool turns are ignored
+                    _ => {}
+                }
+            }
+            let Some(last) = conversation.pop() else {
+                bail!("'messages' must contain at least one user message");
+            };
+            if last.role != Role::User {
+                bail!("The last message must come from the user");
+            }
+
+            let mut prompt: Vec<ChatMessage> = system
+                .iter()
+                .map(|text| ChatMessage::system(text.clone()))
+                .collect();
+            prompt.extend(conversation.iter().cloned());
+            prompt.push(last.clone());
+
+            let mut options = base
+                .clone()
+                .with_history(conversation)
+                .without_remembering();
+            if let Some(provider) = provider {
+                options = options.with_provider(provider);
+            }
+            if !system.is_empty() {
+                options = options.with_system(system.join("\n\n"));
+            }
+            Ok(CompatTurn {
+                model,
+                query: last.content,
+                options,
+                prompt,
+            })
+        }
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct CompatUsage {
+        pub prompt_tokens: usize,
+        pub completion_tokens: usize,
+        pub total_tokens: usize,
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct CompatReplyMessage {
+        pub role: &'static str,
+        pub content: String,
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct CompatChoice {
+        pub index: usize,
+        pub message: CompatReplyMessage,
+        pub finish_reason: &'static str,
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct ChatCompletionResponse {
+        pub id: String,
+        pub object: &'static str,
+        pub created: i64,
+        pub model: String,
+        pub choices: Vec<CompatChoice>,
+        pub usage: CompatUsage,
+    }
+
+    pub fn completion_id() -> String {
+        format!("chatcmpl-{}", Uuid::new_v4().simple())
+    }
+
+    impl ChatCompletionResponse {
+        pub fn new(turn: &CompatTurn, content: String) -> Self {
+            let prompt_tokens = count_message_tokens(&turn.prompt);
+            let completion_tokens = count_tokens(&content);
+            ChatCompletionResponse {
+                id: completion_id(),
+                object: "chat.completion",
+                created: Utc::now().timestamp(),
+                model: turn.model.clone(),
+                choices: vec![CompatChoice {
+                    index: 0,
+                    message: CompatReplyMessage {
+                        role: "assistant",
+                        content,
+                    },
+                    finish_reason: "stop",
+                }],
+                usage: CompatUsage {
+                    prompt_tokens,
+                    completion_tokens,
+                    total_tokens: prompt_tokens + completion_tokens,
+                },
+            }
+        }
+    }
+
+    #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+    pub struct CompatDelta {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub role: Option<&'static str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub content: Option<String>,
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct CompatChunkChoice {
+        pub index: usize,
+        pub delta: CompatDelta,
+        pub finish_reason: Option<&'static str>,
+    }
+
+    /// One `data:` event of a streamed completion.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct ChatCompletionChunk {
+        pub id: String,
+        pub object: &'static str,
+        pub created: i64,
+        pub model: String,
+        pub choices: Vec<CompatChunkChoice>,
+    }
+
+    impl ChatCompletionChunk {
+        fn new(
+            id: &str,
+            model: &str,
+            delta: CompatDelta,
+            finish_reason: Option<&'static str>,
+        ) -> Self {
+            ChatCompletionChunk {
+                id: id.to_string(),
+                object: "chat.completion.chunk",
+                created: Utc::now().timestamp(),
+                model: model.to_string(),
+                choices: vec![CompatChunkChoice {
+                    index: 0,
+                    delta,
+                    finish_reason,
+                }],
+            }
+        }
+
+        /// The opening chunk announcing the assistant role.
+        pub fn role(id: &str, model: &str) -> Self {
+            let delta = CompatDelta {
+                role: Some("assistant"),
+                content: None,
+            };
+            ChatCompletionChunk::new(id, model, delta, None)
+        }
+
+        pub fn content(id: &str, model: &str, content: impl Into<String>) -> Self {
+            let delta = CompatDelta {
+                role: None,
+                content: Some(content.into()),
+            };
+            ChatCompletionChunk::new(id, model, delta, None)
+        }
+
+        pub fn stop(id: &str, model: &str) -> Self {
+            ChatCompletionChunk::new(id, model, CompatDelta::default(), Some("stop"))
+        }
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct CompatModel {
+        pub id: String,
+        pub object: &'static str,
+        pub created: i64,
+        pub owned_by: String,
+    }
+
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct CompatModelList {
+        pub object: &'static str,
+        pub data: Vec<CompatModel>,
+    }
+
+    /// `saturn` followed by `provider/model` for every configured provider.
+    pub fn list_models() -> CompatModelList {
+        let created = Utc::now().timestamp();
+        let mut data = vec![CompatModel {
+            id: SATURN_MODEL.to_string(),
+            object: "model",
+            created,
+            owned_by: "saturn".to_string(),
+        }];
+        data.extend(
+            Provider::ALL
+                .into_iter()
+                .filter(Provider::is_configured)
+                .map(|provider| CompatModel {
+                    id: format!("{}/{}", provider.name(), provider.model()),
+                    object: "model",
+                    created,
+                    owned_by: provider.name().to_string(),
+                }),
+        );
+        CompatModelList {
+            object: "list",
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::openai_compat::{
+        provider_for_model, ChatCompletionChunk, ChatCompletionRequest, ModelNotFound,
+    };
+    use crate::chat_completions::bots::saturn::saturn::SaturnOptions;
+    use crate::chat_completions::providers::router::router::Provider;
+    use crate::chat_completions::utils::messages::messages::Role;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_splits_into_saturn_turn() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "anthropic/claude-3-5-sonnet-latest",
+            "messages": [
+                { "role": "system", "content": "Answer like a pirate." },
+                { "role": "user", "content": "Hi" },
+                { "role": "assistant", "content": "Ahoy!" },
+                { "role": "user", "content": [{ "type": "text", "text": "Where is the treasure?" }] }
+            ]
+        }))
+        .unwrap();
+        assert!(!request.stream && !request.saturn_status);
+        let turn = request.into_turn(&SaturnOptions::default()).unwrap();
+
+        assert_eq!(turn.query, "Where is the treasure?");
+        assert_eq!(turn.options.provider, Some(Provider::Anthropic));
+        assert_eq!(
+            turn.options.system.as_deref(),
+            Some("Answer like a pirate.")
+        );
+        assert_eq!(turn.options.history.len(), 2);
+        assert_eq!(turn.options.history[1].role, Role::Assistant);
+        assert_eq!(turn.prompt.len(), 4);
+        assert!(turn.options.no_remember);
+    }
+
+    #[test]
+    fn test_request_must_end_with_user_message() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "messages": [{ "role": "assistant", "content": "Hello" }]
+        }))
+        .unwrap();
+        assert!(request.into_turn(&SaturnOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_model_ids() {
+        assert_eq!(provider_for_model("saturn").unwrap(), None);
+        assert_eq!(
+            provider_for_model("gemini").unwrap(),
+            Some(Provider::Gemini)
+        );
+        let pinned = format!("openai/{}", Provider::OpenAI.model());
+        assert_eq!(provider_for_model(&pinned).unwrap(), Some(Provider::OpenAI));
+        for unknown in ["gpt-5", "openai/gpt-5"] {
+            let error = provider_for_model(unknown).unwrap_err();
+            assert!(error.downcast_ref::<ModelNotFound>().is_some());
+        }
+    }
+
+    #[test]
+    fn test_chunks_serialize_like_openai() {
+        let role = serde_json::to_value(ChatCompletionChunk::role("id", "saturn")).unwrap();
+        assert_eq!(role["object"], "chat.completion.chunk");
+        assert_eq!(role["choices"][0]["delta"], json!({ "role": "assistant" }));
+        assert_eq!(role["choices"][0]["finish_reason"], json!(null));
+
+        let stop = serde_json::to_value(ChatCompletionChunk::stop("id", "saturn")).unwrap();
+        assert_eq!(stop["choices"][0]["delta"], json!({}));
+        assert_eq!(stop["choices"][0]["finish_reason"], "stop");
+    }
+}
@@ -0,0 +1,763 @@
+pub mod tui {
+    use crate::chat_completions::bots::saturn::saturn::{saturn_with_options, turn_timeout};
+    use crate::chat_completions::interfaces::chat::chat_options;
+    use crate::chat_completions::interfaces::commands::commands::{ChatSession, Command, Outcome};
+    use crate::chat_completions::memory::sessions::sessions::{Session, SessionStore};
+    use crate::chat_completions::utils::errors::errors::Cancelled;
+    use crate::chat_completions::utils::messages::messages::Role;
+    use crate::chat_completions::utils::progress::progress::Progress;
+    use crate::chat_completions::utils::usage::usage::{track_usage, Usage};
+    use anyhow::Result;
+    use crossterm::event::{
+        self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers,
+    };
+    use crossterm::execute;
+    use ratatui::layout::{Constraint, Layout, Position, Rect};
+    use ratatui::style::{Color, Modifier, Style, Stylize};
+    use ratatui::text::{Line, Span};
+    use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+    use ratatui::{DefaultTerminal, Frame};
+    use std::env;
+    use std::io::{self, IsTerminal};
+    use std::time::Duration;
+    use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+    use tokio_util::sync::CancellationToken;
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+    const USER_COLOR: Color = Color::Rgb(23, 184, 144);
+    const AI_COLOR: Color = Color::Rgb(255, 223, 0);
+    const MUTED_COLOR: Color = Color::Rgb(100, 100, 100);
+    const SIDEBAR_WIDTH: u16 = 28;
+    /// The input box grows with its text up to this many lines.
+    const MAX_INPUT_LINES: usize = 6;
+
+    /// Whether the full-screen interface can run: both ends are terminals, the
+    /// terminal isn't `dumb`, and `SATURN_UI` doesn't ask for `simple`.
+    pub fn supported() -> bool {
+        let dumb = env::var("TERM").is_ok_and(|term| term == "dumb");
+        let simple = env::var("SATURN_UI").is_ok_and(|ui| ui.eq_ignore_ascii_case("simple"));
+        !dumb && !simple && io::stdin().is_terminal() && io::stdout().is_terminal()
+    }
+
+    /// Word-wraps `text` to `width` columns, breaking words longer than a line.
+    pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+        let width = width.max(1);
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut line = String::new();
+            for word in paragraph.split_inclusive(' ') {
+                if line.width() + word.trim_end().width() > width && !line.is_empty() {
+                    lines.push(line.trim_end().to_string());
+                    line.clear();
+                }
+                for c in word.chars() {
+                    if line.width() + c.width().unwrap_or(0) > width && c != ' ' {
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    line.push(c);
+                }
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        lines
+    }
+
+    /// A multi-line text editor for the input box. The cursor is a line index
+    /// and a character (not byte) offset into that line.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct InputEditor {
+        lines: Vec<String>,
+        row: usize,
+        column: usize,
+    }
+
+    impl Default for InputEditor {
+        fn default() -> Self {
+            InputEditor {
+                lines: vec![String::new()],
+                row: 0,
+                column: 0,
+            }
+        }
+    }
+
+    impl InputEditor {
+        pub fn text(&self) -> String {
+            self.lines.join("\n")
+        }
+
+        pub fn lines(&self) -> &[String] {
+            &self.lines
+        }
+
+        pub fn is_blank(&self) -> bool {
+            self.lines.iter().all(|line| line.trim().is_empty())
+        }
+
+        /// The cursor as (line, character offset).
+        pub fn cursor(&self) -> (usize, usize) {
+            (self.row, self.column)
+        }
+
+        pub fn clear(&mut self) {
+            *self = InputEditor::default();
+        }
+
+        /// Replaces the contents, leaving the cursor at the end.
+        pub fn set_text(&mut self, text: &str) {
+            self.clear();
+            self.insert_str(text);
+        }
+
+        fn byte_index(&self) -> usize {
+            let line = &self.lines[self.row];
+            line.char_indices()
+                .nth(self.column)
+                .map_or(line.len(), |(index, _)| index)
+        }
+
+        fn line_chars(&self) -> usize {
+            self.lines[self.row].chars().count()
+        }
+
+        pub fn insert_char(&mut self, c: char) {
+            if c == '\n' {
+                return self.insert_newline();
+            }
+            let index = self.byte_index();
+            self.lines[self.row].insert(index, c);
+            self.column += 1;
+        }
+
+        pub fn insert_str(&mut self, text: &str) {
+            for c in text.replace("\r\n", "\n").chars() {
+                self.insert_char(c);
+            }
+        }
+
+        pub fn insert_newline(&mut self) {
+            let index = self.byte_index();
+            let rest = self.lines[self.row].split_off(index);
+            self.lines.insert(self.row + 1, rest);
+            self.row += 1;
+            self.column = 0;
+        }
+
+        pub fn backspace(&mut self) {
+            if self.column > 0 {
+                self.column -= 1;
+                let index = self.byte_index();
+                self.lines[self.row].remove(index);
+            } else if self.row > 0 {
+                let line = self.lines.remove(self.row);
+                self.row -= 1;
+                self.column = self.line_chars();
+                self.lines[self.row].push_str(&line);
+            }
+        }
+
+        pub fn delete(&mut self) {
+            if self.column < self.line_chars() {
+                let index = self.byte_index();
+                self.lines[self.row].remove(index);
+            } else if self.row + 1 < self.lines.len() {
+                let next = self.lines.remove(self.row + 1);
+                self.lines[self.row].push_str(&next);
+            }
+        }
+
+        pub fn left(&mut self) {
+            if self.column > 0 {
+                self.column -= 1;
+            } else if self.row > 0 {
+                self.row -= 1;
+                self.column = self.line_chars();
+            }
+        }
+
+        pub fn right(&mut self) {
+            if self.column < self.line_chars() {
+                self.column += 1;
+            } else if self.row + 1 < self.lines.len() {
+                self.row += 1;
+                self.column = 0;
+            }
+        }
+
+        pub fn up(&mut self) {
+            if self.row > 0 {
+                self.row -= 1;
+                self.column = self.column.min(self.line_chars());
+            }
+        }
+
+        pub fn down(&mut self) {
+            if self.row + 1 < self.lines.len() {
+                self.row += 1;
+                self.column = self.column.min(self.line_chars());
+            }
+        }
+
+        pub fn home(&mut self) {
+            self.column = 0;
+        }
+
+        pub fn end(&mut self) {
+            self.column = self.line_chars();
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Focus {
+        Input,
+        Sidebar,
+    }
+
+    /// A turn that has finished, one way or another.
+    struct TurnOutcome {
+        query: String,
+        retry: bool,
+        result: Result<String>,
+        usage: Usage,
+    }
+
+    /// The turn in flight. A retry hides the exchange it replaces.
+    struct Turn {
+        query: String,
+        retry: bool,
+        cancel: CancellationToken,
+    }
+
+    struct App {
+        /// The conversation, changed by slash-commands just as in the simple chat.
+        chat: ChatSession,
+        session_list: Vec<Session>,
+        sidebar: ListState,
+        show_sidebar: bool,
+        focus: Focus,
+        input: InputEditor,
+        /// Lines scrolled up from the bottom of the transcript.
+        scroll: usize,
+        turn: Option<Turn>,
+        status: String,
+        /// The last slash-command and its reply, shown below the transcript.
+        command_reply: Option<(String, String)>,
+        notice: Option<String>,
+        quit: bool,
+        outcomes: UnboundedSender<TurnOutcome>,
+        progress: UnboundedSender<Progress>,
+    }
+
+    impl App {
+        fn refresh_sessions(&mut self) {
+            let Some(sessions) = self.chat.session_store() else {
+                return;
+            };
+            match sessions.list_sessions() {
+                Ok(list) => self.session_list = list,
+                Err(e) => self.notice = Some(format!("Sessions unavailable: {e}")),
+            }
+            let selected = self
+                .chat
+                .session_id()
+                .and_then(|id| self.session_list.iter().position(|s| s.id == id));
+            self.sidebar
+                .select(selected.or((!self.session_list.is_empty()).then_some(0)));
+        }
+
+        async fn new_session(&mut self) {
+            if let Err(e) = self.chat.run(Command::New).await {
+                self.notice = Some(e.to_string());
+                return;
+            }
+            self.scroll = 0;
+            self.command_reply = None;
+            self.notice = Some("New conversation".to_string());
+            self.refresh_sessions();
+        }
+
+        async fn open_selected_session(&mut self) {
+            let Some(session) = self
+                .sidebar
+                .selected()
+                .and_then(|index| self.session_list.get(index))
+                .cloned()
+            else {
+                return;
+            };
+            match self.chat.run(Command::Load(session.id.clone())).await {
+                Ok(_) => {
+                    self.scroll = 0;
+                    self.focus = Focus::Input;
+                    self.command_reply = None;
+                    self.notice = Some(format!("Opened \"{}\"", title_of(&session)));
+                }
+                Err(e) => self.notice = Some(format!("Could not open the session: {e}")),
+            }
+        }
+
+        async fn submit(&mut self) {
+            if self.turn.is_some() || self.input.is_blank() {
+                return;
+            }
+            let input = self.input.text().trim().to_string();
+            self.input.clear();
+            self.scroll = 0;
+            self.notice = None;
+            self.command_reply = None;
+
+            // Slash-commands change the conversation, or ask again as with `/retry`; `exit` ends it
+            let outcome = match Command::parse(&input) {
+                Some(Ok(command)) => self.chat.run(command).await,
+                Some(Err(e)) => Err(e),
+                None => Ok(Outcome::Ask {
+                    query: input.clone(),
+                    retry: false,
+                }),
+            };
+            match outcome {
+                Ok(Outcome::Ask { query, retry }) => self.start_turn(query, retry),
+                Ok(Outcome::Reply(reply)) => {
+                    self.refresh_sessions();
+                    self.command_reply = Some((input, reply));
+                }
+                Ok(Outcome::Exit) => self.quit = true,
+                Err(e) => self.notice = Some(e.to_string()),
+            }
+        }
+
+        fn start_turn(&mut self, query: String, retry: bool) {
+            self.status = "THINKING...".to_string();
+            let cancel = CancellationToken::new();
+            let mut options = self
+                .chat
+                .turn_options(retry)
+                .with_cancel(cancel.clone())
+                .with_progress(self.progress.clone());
+            if let Some(timeout) = turn_timeout() {
+                options = options.with_timeout(timeout);
+            }
+            let outcomes = self.outcomes.clone();
+            let task_query = query.clone();
+            tokio::spawn(async move {
+                let (result, usage) =
+                    track_usage(saturn_with_options(task_query.clone(), &options)).await;
+                let _ = outcomes.send(TurnOutcome {
+                    query: task_query,
+                    retry,
+                    result,
+                    usage,
+                });
+            });
+            self.turn = Some(Turn {
+                query,
+                retry,
+                cancel,
+            });
+        }
+
+        fn finish_turn(&mut self, outcome: TurnOutcome) {
+            self.turn = None;
+            self.status.clear();
+            let answer = match outcome.result {
+                Ok(answer) => answer,
+                Err(e) => {
+                    self.notice = Some(if e.downcast_ref::<Cancelled>().is_some() {
+                        "Cancelled".to_string()
+                    } else {
+                        format!("Error: {e:#}")
+                    });
+                    // Give the message back so it can be edited or resent
+                    if self.input.is_blank() {
+                        self.input.set_text(&outcome.query);
+                    }
+                    return;
+                }
+            };
+            self.chat
+                .record(outcome.query, answer, outcome.retry, outcome.usage);
+            self.save();
+        }
+
+        /// Saves the conversation after each answer, starting a session if needed.
+        fn save(&mut self) {
+            if self.chat.session_store().is_none() {
+                return;
+            }
+            if let Err(e) = self.chat.save("") {
+                self.notice = Some(format!("Could not save the conversation: {e}"));
+                return;
+            }
+            self.refresh_sessions();
+        }
+
+        fn cancel_turn(&mut self) -> bool {
+            match &self.turn {
+                Some(turn) => {
+                    turn.cancel.cancel();
+                    self.status = "Cancelling...".to_string();
+                    true
+                }
+                None => false,
+            }
+        }
+
+        async fn on_key(&mut self, key: KeyEvent) {
+            if key.kind != KeyEventKind::Press {
+                return;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            let alt = key.modifiers.contains(KeyModifiers::ALT);
+            let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+            // Ctrl-C and Esc cancel the answer in progress before doing anything else
+            let cancel_key = key.code == KeyCode::Esc || (ctrl && key.code == KeyCode::Char('c'));
+            if cancel_key && self.cancel_turn() {
+                return;
+            }
+            match key.code {
+                KeyCode::Char('c' | 'd' | 'q') if ctrl => self.quit = true,
+                KeyCode::Esc => self.focus = Focus::Input,
+                KeyCode::Char('n') if ctrl && self.turn.is_none() => self.new_session().await,
+                KeyCode::Char('b') if ctrl => {
+                    self.show_sidebar = !self.show_sidebar;
+                    if !self.show_sidebar {
+                        self.focus = Focus::Input;
+                    }
+                }
+                KeyCode::Tab if self.show_sidebar => {
+                    self.focus = match self.focus {
+                        Focus::Input => Focus::Sidebar,
+                        Focus::Sidebar => Focus::Input,
+                    };
+                }
+                KeyCode::PageUp => self.scroll += 10,
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::Up if ctrl => self.scroll += 1,
+                KeyCode::Down if ctrl => self.scroll = self.scroll.saturating_sub(1),
+                _ if self.focus == Focus::Sidebar => self.on_sidebar_key(key.code).await,
+                KeyCode::Enter if alt || shift => self.input.insert_newline(),
+                KeyCode::Char('j') if ctrl => self.input.insert_newline(),
+                KeyCode::Enter => self.submit().await,
+                KeyCode::Char(c) if !ctrl => self.input.insert_char(c),
+                KeyCode::Backspace => self.input.backspace(),
+                KeyCode::Delete => self.input.delete(),
+                KeyCode::Left => self.input.left(),
+                KeyCode::Right => self.input.right(),
+                KeyCode::Up => self.input.up(),
+                KeyCode::Down => self.input.down(),
+                KeyCode::Home => self.input.home(),
+                KeyCode::End => self.input.end(),
+                _ => {}
+            }
+        }
+
+        async fn on_sidebar_key(&mut self, code: KeyCode) {
+            match code {
+                KeyCode::Up => self.sidebar.select_previous(),
+                KeyCode::Down => self.sidebar.select_next(),
+                KeyCode::Enter if self.turn.is_none() => self.open_selected_session().await,
+                _ => {}
+            }
+        }
+
+        fn draw(&mut self, frame: &mut Frame) {
+            let input_height = self.input.lines().len().clamp(1, MAX_INPUT_LINES) as u16 + 2;
+            let [main, input, status] = Layout::vertical([
+                Constraint::Min(3),
+                Constraint::Length(input_height),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+            let transcript = if self.show_sidebar {
+                let [sidebar, transcript] =
+                    Layout::horizontal([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(20)])
+                        .areas(main);
+                self.draw_sidebar(frame, sidebar);
+                transcript
+            } else {
+                main
+            };
+            self.draw_transcript(frame, transcript);
+            self.draw_input(frame, input);
+            self.draw_status(frame, status);
+        }
+
+        fn border(&self, focus: Focus) -> Style {
+            if self.focus == focus {
+                Style::new().fg(USER_COLOR)
+            } else {
+                Style::new().fg(MUTED_COLOR)
+            }
+        }
+
+        fn draw_sidebar(&mut self, frame: &mut Frame, area: Rect) {
+            let items: Vec<ListItem> = self
+                .session_list
+                .iter()
+                .map(|session| {
+                    let current = self.chat.session_id() == Some(session.id.as_str());
+                    let style = if current {
+                        Style::new().fg(AI_COLOR)
+                    } else {
+                        Style::new()
+                    };
+                    ListItem::new(Line::styled(title_of(session), style))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::bordered()
+                        .title(" Sessions ")
+                        .border_style(self.border(Focus::Sidebar)),
+                )
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, area, &mut self.sidebar);
+        }
+
+        fn draw_transcript(&mut self, frame: &mut Frame, area: Rect) {
+            let width = area.width.saturating_sub(2) as usize;
+            let mut lines: Vec<Line> = Vec::new();
+            let mut add = |speaker: &str, color: Color, text: &str, muted: bool| {
+                lines.push(Line::from(speaker.to_string().fg(color).bold()));
+                for line in wrap_text(text, width) {
+                    lines.push(if muted {
+                        Line::from(line.fg(MUTED_COLOR))
+                    } else {
+                        Line::from(line)
+                    });
+                }
+                lines.push(Line::default());
+            };
+            let history = &self.chat.options.history;
+            let shown = match &self.turn {
+                Some(turn) if turn.retry => history.len().saturating_sub(2),
+                _ => history.len(),
+            };
+            for message in &history[..shown] {
+                match message.role {
+                    Role::User => add("You", USER_COLOR, &message.content, false),
+                    Role::Assistant => add("Saturn", AI_COLOR, &message.content, false),
+                    Role::System => {}
+                }
+            }
+            if let Some((input, reply)) = &self.command_reply {
+                add("You", USER_COLOR, input, false);
+                add("Saturn", AI_COLOR, reply, true);
+            }
+            if let Some(turn) = &self.turn {
+                add("You", USER_COLOR, &turn.query, false);
+                add("Saturn", AI_COLOR, &self.status, true);
+            }
+
+            let height = area.height.saturating_sub(2) as usize;
+            let bottom = lines.len().saturating_sub(height);
+            self.scroll = self.scroll.min(bottom);
+            let title = match self
+                .chat
+                .session_id()
+                .and_then(|id| self.session_list.iter().find(|session| session.id == id))
+            {
+                Some(session) => format!(" {} ", title_of(session)),
+                None => " New conversation ".to_string(),
+            };
+            let mut block = Block::bordered()
+                .title(title)
+                .border_style(Style::new().fg(MUTED_COLOR));
+            if self.scroll > 0 {
+                block = block.title_bottom(Line::from(" ↓ PgDn for newer ").right_aligned());
+            }
+            let transcript = Paragraph::new(lines)
+                .block(block)
+                .scroll(((bottom - self.scroll) as u16, 0));
+            frame.render_widget(transcript, area);
+        }
+
+        fn draw_input(&self, frame: &mut Frame, area: Rect) {
+            let visible = area.height.saturating_sub(2) as usize;
+            let (row, column) = self.input.cursor();
+            let first = (row + 1).saturating_sub(visible);
+            let title = if self.turn.is_some() {
+                " Message (Esc cancels the answer) "
+            } else {
+                " Message (Enter sends, Alt+Enter adds a line) "
+            };
+            let text: Vec<Line> = self.input.lines()[first..]
+                .iter()
+                .map(|line| Line::from(line.as_str()))
+                .collect();
+            let input = Paragraph::new(text).block(
+                Block::bordered()
+                    .title(title)
+                    .border_style(self.border(Focus::Input)),
+            );
+            frame.render_widget(input, area);
+            if self.focus == Focus::Input {
+                let before: String = self.input.lines()[row].chars().take(column).collect();
+                let x = (area.x + 1 + before.width() as u16).min(area.right().saturating_sub(2));
+                frame.set_cursor_position(Position::new(x, area.y + 1 + (row - first) as u16));
+            }
+        }
+
+        fn draw_status(&self, frame: &mut Frame, area: Rect) {
+            let mut spans = vec![
+                Span::styled(
+                    format!(" {} ", self.chat.provider()),
+                    Style::new().reversed(),
+                ),
+                Span::raw(format!(" {} ", self.chat.usage)),
+            ];
+            if let Some(notice) = &self.notice {
+                spans.push(Span::styled(
+                    format!(" {notice} "),
+                    Style::new().fg(AI_COLOR),
+                ));
+            }
+            spans.push(Span::styled(
+                " Ctrl-C cancel/quit · Ctrl-N new · Ctrl-B sessions · Tab focus · PgUp/PgDn scroll",
+                Style::new().fg(MUTED_COLOR),
+            ));
+            frame.render_widget(Paragraph::new(Line::from(spans)), area);
+        }
+    }
+
+    fn title_of(session: &Session) -> String {
+        if session.title.is_empty() {
+            "(untitled)".to_string()
+        } else {
+            session.title.clone()
+        }
+    }
+
+    /// Reads terminal events on a thread of its own, since crossterm's reads
+    /// block. Stops once the receiver is dropped.
+    fn spawn_input_reader(events: UnboundedSender<Event>) {
+        std::thread::spawn(move || loop {
+            match event::poll(Duration::from_millis(100)) {
+                Ok(true) => match event::read() {
+                    Ok(event) => {
+                        if events.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                },
+                Ok(false) if events.is_closed() => break,
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        });
+    }
+
+    /// Runs the full-screen chat: a transcript that scrolls, a multi-line
+    /// input box, a sidebar of stored sessions and a status bar with the
+    /// provider and estimated usage. Slash-commands work as in the simple
+    /// chat, and conversations are saved as sessions.
+    pub async fn start_tui() -> Result<()> {
+        let options = chat_options();
+        let sessions = match SessionStore::open_default() {
+            Ok(sessions) => Some(sessions),
+            Err(e) => {
+                eprintln!("Sessions unavailable, continuing without them: {e}");
+                None
+            }
+        };
+
+        let mut terminal = ratatui::try_init()?;
+        let _ = execute!(io::stdout(), EnableBracketedPaste);
+        let result = run(&mut terminal, ChatSession::new(options, sessions)).await;
+        let _ = execute!(io::stdout(), DisableBracketedPaste);
+        ratatui::restore();
+        result
+    }
+
+    async fn run(terminal: &mut DefaultTerminal, chat: ChatSession) -> Result<()> {
+        let (events_sender, mut events) = mpsc::unbounded_channel();
+        spawn_input_reader(events_sender);
+        let (outcomes_sender, mut outcomes): (_, UnboundedReceiver<TurnOutcome>) =
+            mpsc::unbounded_channel();
+        let (progress_sender, mut progress) = mpsc::unbounded_channel();
+
+        let mut app = App {
+            chat,
+            session_list: Vec::new(),
+            sidebar: ListState::default(),
+            show_sidebar: true,
+            focus: Focus::Input,
+            input: InputEditor::default(),
+            scroll: 0,
+            turn: None,
+            status: String::new(),
+            command_reply: None,
+            notice: None,
+            quit: false,
+            outcomes: outcomes_sender,
+            progress: progress_sender,
+        };
+        app.show_sidebar = app.chat.session_store().is_some();
+        app.refresh_sessions();
+
+        while !app.quit {
+            terminal.draw(|frame| app.draw(frame))?;
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(Event::Key(key)) => app.on_key(key).await,
+                    Some(Event::Paste(text)) if app.focus == Focus::Input => {
+                        app.input.insert_str(&text)
+                    }
+                    Some(_) => {}
+                    None => break, // The terminal went away
+                },
+                Some(outcome) = outcomes.recv() => app.finish_turn(outcome),
+                Some(report) = progress.recv() => {
+                    if app.turn.is_some() {
+                        app.status = format!("THINKING... {report}");
+                    }
+                }
+            }
+        }
+        if let Some(turn) = &app.turn {
+            turn.cancel.cancel();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tui::{wrap_text, InputEditor};
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(
+            wrap_text("the rings of Saturn\n\nare ice", 10),
+            vec!["the rings", "of Saturn", "", "are ice"]
+        );
+        assert_eq!(wrap_text("abcdefghij12", 5), vec!["abcde", "fghij", "12"]);
+    }
+
+    #[test]
+    fn test_input_editor() {
+        let mut input = InputEditor::default();
+        input.insert_str("fn main() {\r\n}");
+        assert_eq!(input.lines(), ["fn main() {", "}"]);
+        assert_eq!(input.cursor(), (1, 1));
+
+        input.home();
+        input.backspace();
+        assert_eq!(input.text(), "fn main() {}");
+        input.insert_newline();
+        input.insert_str("    é\n");
+        assert_eq!(input.text(), "fn main() {\n    é\n}");
+        assert_eq!(input.cursor(), (2, 0));
+        input.backspace();
+        input.up();
+        input.end();
+        input.delete();
+        assert_eq!(input.text(), "fn main() {    é}");
+        assert!(!input.is_blank());
+
+        input.set_text("  \n ");
+        assert!(input.is_blank());
+    }
+}
@@ -0,0 +1,127 @@
+pub mod websocket {
+    use crate::chat_completions::memory::sessions::sessions::SessionMessage;
+    use crate::chat_completions::utils::progress::progress::Progress;
+    use anyhow::{anyhow, Result};
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+
+    /// A frame a client sends over the chat socket.
+    #[derive(Deserialize, Debug, Clone, PartialEq)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum ClientMessage {
+        /// Ask Saturn something. The first message opens a new session unless
+        /// one was resumed.
+        Message { content: String },
+        /// Abandon the turn in progress.
+        Cancel,
+        /// Continue a stored session; its messages are sent back first.
+        Resume { session_id: String },
+    }
+
+    impl ClientMessage {
+        pub fn parse(text: &str) -> Result<Self> {
+            serde_json::from_str(text).map_err(|e| {
+                anyhow!("Expected {{\"type\": \"message\" | \"cancel\" | \"resume\", ...}}: {e}")
+            })
+        }
+    }
+
+    /// A frame the server sends over the chat socket.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum ServerEvent {
+        /// The session the socket is attached to, with what it holds so far.
+        Session {
+            session_id: String,
+            messages: Vec<SessionMessage>,
+        },
+        /// What the turn in progress is doing.
+        Status {
+            message: String,
+            #[serde(flatten)]
+            progress: Progress,
+        },
+        /// The next piece of the answer.
+        Token { content: String },
+        /// The turn finished; `content` is the whole answer.
+        Done { content: String },
+        /// The turn was cancelled before it finished.
+        Cancelled,
+        /// A request failed, in the HTTP error envelope's shape.
+        Error { error: Value },
+    }
+
+    impl ServerEvent {
+        pub fn status(progress: Progress) -> Self {
+            ServerEvent::Status {
+                message: progress.to_string(),
+                progress,
+            }
+        }
+
+        /// An error event carrying `envelope`'s inner `error` object.
+        pub fn error(envelope: Value) -> Self {
+            ServerEvent::Error {
+                error: envelope.get("error").cloned().unwrap_or(envelope),
+            }
+        }
+
+        pub fn to_text(&self) -> String {
+            json!(self).to_string()
+        }
+    }
+
+    /// Splits an answer into the word-sized pieces sent as `token` events.
+    pub fn token_pieces(answer: &str) -> impl Iterator<Item = &str> {
+        answer.split_inclusive(' ')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::websocket::{token_pieces, ClientMessage, ServerEvent};
+    use crate::chat_completions::utils::progress::progress::Progress;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_parse_client_messages() {
+        assert_eq!(
+            ClientMessage::parse(r#"{"type": "message", "content": "Hi"}"#).unwrap(),
+            ClientMessage::Message {
+                content: "Hi".to_string()
+            }
+        );
+        assert_eq!(
+            ClientMessage::parse(r#"{"type": "cancel"}"#).unwrap(),
+            ClientMessage::Cancel
+        );
+        assert_eq!(
+            ClientMessage::parse(r#"{"type": "resume", "session_id": "abc"}"#).unwrap(),
+            ClientMessage::Resume {
+                session_id: "abc".to_string()
+            }
+        );
+        assert!(ClientMessage::parse(r#"{"type": "shout"}"#).is_err());
+        assert!(ClientMessage::parse("hello").is_err());
+    }
+
+    #[test]
+    fn test_server_event_frames() {
+        let status = ServerEvent::status(Progress::Attempt { number: 3, max: 10 });
+        assert_eq!(
+            serde_json::from_str::<Value>(&status.to_text()).unwrap(),
+            json!({ "type": "status", "message": "Retrying 3/10", "kind": "attempt", "number": 3, "max": 10 })
+        );
+        assert_eq!(ServerEvent::Cancelled.to_text(), r#"{"type":"cancelled"}"#);
+
+        let error = ServerEvent::error(json!({ "error": { "code": "rate_limited" } }));
+        assert_eq!(
+            json!(error),
+            json!({ "type": "error", "error": { "code": "rate_limited" } })
+        );
+        assert_eq!(
+            token_pieces("Hello there world").collect::<Vec<_>>(),
+            vec!["Hello ", "there ", "world"]
+        );
+    }
+}
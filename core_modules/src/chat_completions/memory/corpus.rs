@@ -0,0 +1,439 @@
+pub mod corpus {
+    use crate::chat_completions::memory::embeddings::embeddings::{default_embedder, Embedder};
+    use crate::chat_completions::memory::store::store::LOCAL_CLIENT;
+    use crate::chat_completions::memory::vector_index::vector_index::{VectorEntry, VectorIndex};
+    use crate::chat_completions::utils::paths::paths::saturn_file;
+    use anyhow::{bail, Result};
+    use serde_json::json;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::{self, Path, PathBuf};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    /// Chunks scoring below this similarity are left out of the prompt.
+    pub const MIN_RELEVANCE: f32 = 0.25;
+    /// Lines per chunk, and how many lines consecutive chunks share.
+    pub const CHUNK_LINES: usize = 40;
+    pub const CHUNK_OVERLAP: usize = 5;
+    /// Chunks are cut early once they reach this many characters.
+    pub const CHUNK_CHARS: usize = 2000;
+    /// Files larger than this are skipped as unlikely to be hand-written text.
+    pub const MAX_INGEST_BYTES: u64 = 1024 * 1024;
+    const EMBED_BATCH: usize = 64;
+
+    /// Markdown, plain text and common source code.
+    const INGESTED_EXTENSIONS: &[&str] = &[
+        "md", "markdown", "txt", "rst", "adoc", "org", "rs", "py", "js", "jsx", "ts", "tsx", "go",
+        "java", "kt", "c", "h", "cc", "cpp", "hpp", "cs", "rb", "php", "swift", "scala", "sh",
+        "sql", "toml", "yaml", "yml", "json", "html", "css",
+    ];
+    const SKIPPED_DIRECTORIES: &[&str] = &["target", "node_modules", "dist", "build", "vendor"];
+
+    /// A span of lines from an ingested file.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Chunk {
+        pub path: String,
+        /// First and last line of the chunk, 1-based and inclusive.
+        pub start_line: usize,
+        pub end_line: usize,
+        pub text: String,
+    }
+
+    impl Chunk {
+        /// `path:start-end`, the form answers cite chunks in.
+        pub fn citation(&self) -> String {
+            format!("{}:{}-{}", self.path, self.start_line, self.end_line)
+        }
+    }
+
+    /// A retrieved chunk with its similarity to the query.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RetrievedChunk {
+        pub score: f32,
+        pub chunk: Chunk,
+    }
+
+    /// What an ingestion run did.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct IngestReport {
+        pub files: usize,
+        pub chunks: usize,
+        pub skipped: Vec<String>,
+        /// Files whose chunks were dropped because they were deleted or were
+        /// stored under another spelling of their path.
+        pub removed: usize,
+    }
+
+    /// Splits `text` into overlapping chunks of whole lines.
+    pub fn chunk_text(path: &str, text: &str) -> Vec<Chunk> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < lines.len() {
+            let mut end = start;
+            let mut chars = 0;
+            while end < lines.len() && end - start < CHUNK_LINES {
+                chars += lines[end].len() + 1;
+                end += 1;
+                if chars >= CHUNK_CHARS {
+                    break;
+                }
+            }
+            let body = lines[start..end].join("\n");
+            if !body.trim().is_empty() {
+                chunks.push(Chunk {
+                    path: path.to_string(),
+                    start_line: start + 1,
+                    end_line: end,
+                    text: body,
+                });
+            }
+            if end == lines.len() {
+                break;
+            }
+            start = end.saturating_sub(CHUNK_OVERLAP).max(start + 1);
+        }
+        chunks
+    }
+
+    /// Whether `path` looks like a document or source file worth indexing.
+    pub fn is_ingestible(path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                INGESTED_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            })
+    }
+
+    /// Every ingestible file under `root`, skipping hidden and build directories.
+    /// Symlinked directories are not followed, so a link cycle cannot recurse forever.
+    fn collect_files(root: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        if root.is_file() {
+            files.push(root.to_path_buf());
+            return Ok(());
+        }
+        for entry in fs::read_dir(root)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if file_type.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_str()) {
+                    collect_files(&path, files)?;
+                }
+            } else if (file_type.is_file() || path.is_file()) && is_ingestible(&path) {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the chunks stored under `path` should go after ingesting `files`
+    /// from `root`: the file is gone, or it is one of `files` under another spelling.
+    fn is_stale(path: &str, root: &Path, files: &BTreeSet<PathBuf>) -> bool {
+        match fs::canonicalize(path) {
+            Ok(canonical) => files.contains(&canonical) && canonical != Path::new(path),
+            Err(_) => path::absolute(path).is_ok_and(|absolute| absolute.starts_with(root)),
+        }
+    }
+
+    /// A local document corpus: files are chunked, embedded and stored in a
+    /// vector index so `saturn()` can ground answers in them. Each handle
+    /// ingests and searches the documents of one client.
+    pub struct DocumentCorpus {
+        embedder: Arc<dyn Embedder>,
+        index: Arc<Mutex<VectorIndex>>,
+        client: String,
+    }
+
+    impl DocumentCorpus {
+        pub fn new(embedder: Arc<dyn Embedder>, index: VectorIndex) -> Self {
+            DocumentCorpus {
+                embedder,
+                index: Arc::new(Mutex::new(index)),
+                client: LOCAL_CLIENT.to_string(),
+            }
+        }
+
+        /// A handle on the same index that only sees `client`'s documents.
+        pub fn for_client(&self, client: &str) -> DocumentCorpus {
+            DocumentCorpus {
+                embedder: self.embedder.clone(),
+                index: self.index.clone(),
+                client: client.to_string(),
+            }
+        }
+
+        /// Opens `corpus.json` inside the Saturn home directory with the default embedder.
+        pub fn open_default() -> Result<Self> {
+            let embedder = default_embedder();
+            let index = VectorIndex::open(saturn_file("corpus.json")?, &embedder.name())?;
+            Ok(DocumentCorpus::new(embedder, index))
+        }
+
+        /// How many chunks this client's documents were split into.
+        pub async fn len(&self) -> usize {
+            let index = self.index.lock().await;
+            index
+                .entries()
+                .iter()
+                .filter(|entry| self.owns(entry))
+                .count()
+        }
+
+        pub async fn is_empty(&self) -> bool {
+            self.len().await == 0
+        }
+
+        fn owns(&self, entry: &VectorEntry) -> bool {
+            entry.client() == self.client
+        }
+
+        /// Indexes the file or directory at `path`. Files are keyed by their
+        /// canonical path, so re-ingesting a file replaces its chunks, and
+        /// chunks of files deleted from under `path` are dropped.
+        pub async fn ingest_path(&self, path: impl AsRef<Path>) -> Result<IngestReport> {
+            let path = path.as_ref();
+            if !path.exists() {
+                bail!("{} does not exist", path.display());
+            }
+            let root = fs::canonicalize(path)?;
+            let mut found = Vec::new();
+            collect_files(&root, &mut found)?;
+            let files = found
+                .into_iter()
+                .map(fs::canonicalize)
+                .collect::<std::io::Result<BTreeSet<_>>>()?;
+
+            let mut report = IngestReport::default();
+            for file in &files {
+                let display = file.display().to_string();
+                if fs::metadata(file)?.len() > MAX_INGEST_BYTES {
+                    report.skipped.push(display);
+                    continue;
+                }
+                // Binary or non-UTF-8 files are skipped rather than failing the run
+                let Ok(text) = fs::read_to_string(file) else {
+                    report.skipped.push(display);
+                    continue;
+                };
+                report.chunks += self.ingest_text(&display, &text).await?;
+                report.files += 1;
+            }
+
+            let mut index = self.index.lock().await;
+            let stale: BTreeSet<String> = index
+                .entries()
+                .iter()
+                .filter(|entry| self.owns(entry))
+                .filter_map(|entry| entry.metadata["path"].as_str())
+                .filter(|stored| is_stale(stored, &root, &files))
+                .map(String::from)
+                .collect();
+            index.remove_where(|entry| {
+                self.owns(entry)
+                    && entry.metadata["path"]
+                        .as_str()
+                        .is_some_and(|stored| stale.contains(stored))
+            });
+            report.removed = stale.len();
+            index.persist().await?;
+            Ok(report)
+        }
+
+        /// Indexes `text` as the contents of `path`, returning the number of chunks.
+        /// The index is not saved; `ingest_path` does that once per run.
+        pub async fn ingest_text(&self, path: &str, text: &str) -> Result<usize> {
+            let chunks = chunk_text(path, text);
+            let mut vectors = Vec::with_capacity(chunks.len());
+            for batch in chunks.chunks(EMBED_BATCH) {
+                let texts: Vec<String> = batch
+                    .iter()
+                    .map(|chunk| format!("{}\n{}", chunk.path, chunk.text))
+                    .collect();
+                vectors.extend(self.embedder.embed(&texts).await?);
+            }
+
+            let mut index = self.index.lock().await;
+            index.remove_where(|entry| self.owns(entry) && entry.metadata["path"] == path);
+            for (chunk, vector) in chunks.iter().zip(vectors) {
+                index.upsert(VectorEntry {
+                    id: format!("{}/{}", self.client, chunk.citation()),
+                    text: chunk.text.clone(),
+                    metadata: json!({
+                        "path": chunk.path,
+                        "start_line": chunk.start_line,
+                        "end_line": chunk.end_line,
+                        "client": self.client,
+                    }),
+                    vector,
+                })?;
+            }
+            Ok(chunks.len())
+        }
+
+        /// The chunks most similar to `query`, best first.
+        pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<RetrievedChunk>> {
+            if self.is_empty().await {
+                return Ok(Vec::new());
+            }
+            let vector = self
+                .embedder
+                .embed(&[query.to_string()])
+                .await?
+                .pop()
+                .unwrap_or_default();
+
+            let index = self.index.lock().await;
+            Ok(index
+                .search_where(&vector, limit, |entry| self.owns(entry))
+                .into_iter()
+                .filter(|(score, _)| *score >= MIN_RELEVANCE)
+                .map(|(score, entry)| RetrievedChunk {
+                    score,
+                    chunk: Chunk {
+                        path: entry.metadata["path"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        start_line: entry.metadata["start_line"].as_u64().unwrap_or_default()
+                            as usize,
+                        end_line: entry.metadata["end_line"].as_u64().unwrap_or_default() as usize,
+                        text: entry.text.clone(),
+                    },
+                })
+                .collect())
+        }
+    }
+
+    /// A prompt section quoting `chunks` under their citations, or `None` without chunks.
+    pub fn corpus_prompt(chunks: &[RetrievedChunk]) -> Option<String> {
+        if chunks.is_empty() {
+            return None;
+        }
+        let mut prompt = String::from(
+            "Excerpts from the user's documents. Ground your answer in them when they are \
+             relevant and cite each one you use as [path:start-end].\n",
+        );
+        for retrieved in chunks {
+            prompt.push_str(&format!(
+                "\n[{}]\n{}\n",
+                retrieved.chunk.citation(),
+                retrieved.chunk.text
+            ));
+        }
+        Some(prompt)
+    }
+
+    /// A "Sources" footer listing the chunks an answer was grounded in.
+    pub fn sources_footer(chunks: &[RetrievedChunk]) -> String {
+        let mut footer = String::from("Sources:");
+        for retrieved in chunks {
+            footer.push_str(&format!("\n- {}", retrieved.chunk.citation()));
+        }
+        footer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::corpus::{chunk_text, corpus_prompt, DocumentCorpus, CHUNK_LINES, CHUNK_OVERLAP};
+    use crate::chat_completions::memory::embeddings::embeddings::HashingEmbedder;
+    use crate::chat_completions::memory::vector_index::vector_index::VectorIndex;
+    use std::fs;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_chunks_overlap_and_track_line_ranges() {
+        let text: String = (1..=100).map(|n| format!("line {n}\n")).collect();
+        let chunks = chunk_text("notes.md", &text);
+
+        assert_eq!(chunks[0].start_line, 1);
+        assert_eq!(chunks[0].end_line, CHUNK_LINES);
+        assert_eq!(chunks[1].start_line, CHUNK_LINES - CHUNK_OVERLAP + 1);
+        assert_eq!(chunks.last().unwrap().end_line, 100);
+        assert_eq!(chunks[0].citation(), "notes.md:1-40");
+        assert!(chunks[1].text.starts_with("line 36"));
+    }
+
+    #[tokio::test]
+    async fn test_search_finds_and_cites_relevant_chunk() {
+        let corpus = DocumentCorpus::new(
+            Arc::new(HashingEmbedder::default()),
+            VectorIndex::in_memory("hashing/512"),
+        );
+        corpus
+            .ingest_text(
+                "docs/deploy.md",
+                "# Deploying\nRun the Saturn server behind nginx on port 2223.",
+            )
+            .await
+            .unwrap();
+        corpus
+            .ingest_text("docs/recipes.md", "Cacio e pepe needs pecorino and pepper.")
+            .await
+            .unwrap();
+        // Re-ingesting replaces the file's chunks instead of duplicating them
+        corpus
+            .ingest_text("docs/recipes.md", "Cacio e pepe needs pecorino and pepper.")
+            .await
+            .unwrap();
+        assert_eq!(corpus.len().await, 2);
+
+        let retrieved = corpus
+            .search("Which port does the Saturn server run on?", 3)
+            .await
+            .unwrap();
+        assert_eq!(retrieved.len(), 1);
+        assert_eq!(retrieved[0].chunk.citation(), "docs/deploy.md:1-2");
+        assert!(corpus_prompt(&retrieved)
+            .unwrap()
+            .contains("[docs/deploy.md:1-2]\n# Deploying"));
+
+        // Other clients neither find nor replace this client's documents
+        let other = corpus.for_client("grace");
+        assert!(other.is_empty().await);
+        assert!(other
+            .search("Which port does the Saturn server run on?", 3)
+            .await
+            .unwrap()
+            .is_empty());
+        other
+            .ingest_text("docs/deploy.md", "Grace deploys with Docker.")
+            .await
+            .unwrap();
+        assert_eq!(corpus.len().await, 2);
+        assert_eq!(other.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_uses_canonical_paths_and_drops_deleted_files() {
+        let root = std::env::temp_dir().join(format!("saturn-corpus-{}", std::process::id()));
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/a.md"), "Saturn listens on port 2223.").unwrap();
+        fs::write(root.join("docs/b.md"), "Cacio e pepe needs pecorino.").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("docs/loop")).unwrap();
+
+        let corpus = DocumentCorpus::new(
+            Arc::new(HashingEmbedder::default()),
+            VectorIndex::in_memory("hashing/512"),
+        );
+        let report = corpus.ingest_path(&root).await.unwrap();
+        assert_eq!(report.files, 2);
+
+        // The same file under another spelling is not stored twice
+        corpus
+            .ingest_path(root.join("docs/./../docs/a.md"))
+            .await
+            .unwrap();
+        assert_eq!(corpus.len().await, 2);
+
+        fs::remove_file(root.join("docs/b.md")).unwrap();
+        let report = corpus.ingest_path(&root).await.unwrap();
+        assert_eq!((report.files, report.removed), (1, 1));
+        assert_eq!(corpus.len().await, 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+pub mod embeddings {
+    use crate::chat_completions::providers::local::local::LocalConfig;
+    use crate::chat_completions::providers::router::router::Provider;
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use dotenv::dotenv;
+    use reqwest::{header, Client, StatusCode};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use std::env;
+    use std::sync::Arc;
+
+    /// Turns text into vectors whose cosine similarity tracks semantic similarity.
+    #[async_trait]
+    pub trait Embedder: Send + Sync {
+        /// Identifies the model; vectors from different embedders must not be mixed.
+        fn name(&self) -> String;
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+    }
+
+    /// The OpenAI embeddings API.
+    pub struct OpenAIEmbedder {
+        model: String,
+    }
+
+    impl Default for OpenAIEmbedder {
+        fn default() -> Self {
+            OpenAIEmbedder {
+                model: "text-embedding-3-small".to_string(),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct OpenAIEmbeddingData {
+        embedding: Vec<f32>,
+    }
+
+    #[derive(Deserialize)]
+    struct OpenAIEmbeddingResponse {
+        data: Vec<OpenAIEmbeddingData>,
+    }
+
+    #[async_trait]
+    impl Embedder for OpenAIEmbedder {
+        fn name(&self) -> String {
+            format!("openai/{}", self.model)
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            dotenv().ok();
+            let openai_api_key =
+                env::var("OPENAI_API_KEY").expect("Failed to extract OPENAI_API_KEY");
+            let response = Client::new()
+                .post("https://api.openai.com/v1/embeddings")
+                .header(header::CONTENT_TYPE, "application/json; charset=utf-8")
+                .header(header::AUTHORIZATION, format!("Bearer {}", openai_api_key))
+                .json(&json!({ "model": self.model, "input": texts }))
+                .send()
+                .await?;
+
+            if response.status() != StatusCode::OK {
+                return Err(UpstreamError::new("openai", response.status()).into());
+            }
+
+            let embeddings: OpenAIEmbeddingResponse = response.json().await?;
+            Ok(embeddings.data.into_iter().map(|d| d.embedding).collect())
+        }
+    }
+
+    /// Embeddings from the local Ollama server's `/api/embed`, model taken from
+    /// `LOCAL_EMBEDDING_MODEL` (default `nomic-embed-text`).
+    pub struct LocalEmbedder {
+        config: LocalConfig,
+        model: String,
+    }
+
+    impl LocalEmbedder {
+        pub fn from_env() -> Self {
+            dotenv().ok();
+            LocalEmbedder {
+                config: LocalConfig::from_env(),
+                model: env::var("LOCAL_EMBEDDING_MODEL")
+                    .unwrap_or_else(|_| "nomic-embed-text".to_string()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Embedder for LocalEmbedder {
+        fn name(&self) -> String {
+            format!("local/{}", self.model)
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            let response = Client::new()
+                .post(format!("{}/api/embed", self.config.base_url))
+                .json(&json!({ "model": self.model, "input": texts }))
+                .send()
+                .await?;
+
+            if response.status() != StatusCode::OK {
+                return Err(UpstreamError::new("local", response.status()).into());
+            }
+
+            let body: Value = response.json().await?;
+            serde_json::from_value(body["embeddings"].clone())
+                .map_err(|_| anyhow!("No embeddings found in the response."))
+        }
+    }
+
+    /// A dependency-free embedder that hashes words and word pairs into a fixed
+    /// number of buckets. Far cruder than a model, but always available.
+    pub struct HashingEmbedder {
+        dimensions: usize,
+    }
+
+    impl Default for HashingEmbedder {
+        fn default() -> Self {
+            HashingEmbedder { dimensions: 512 }
+        }
+    }
+
+    impl HashingEmbedder {
+        pub fn embed_text(&self, text: &str) -> Vec<f32> {
+            let words: Vec<String> = text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect();
+            let mut vector = vec![0.0; self.dimensions];
+            let mut add = |feature: &str, weight: f32| {
+                let hash = fnv1a(feature);
+                let sign = if hash & 1 == 0 { 1.0 } else { -1.0 };
+                vector[(hash >> 1) as usize % self.dimensions] += sign * weight;
+            };
+            for word in &words {
+                add(word, 1.0);
+            }
+            for pair in words.windows(2) {
+                add(&format!("{} {}", pair[0], pair[1]), 0.5);
+            }
+            normalize(&mut vector);
+            vector
+        }
+    }
+
+    #[async_trait]
+    impl Embedder for HashingEmbedder {
+        fn name(&self) -> String {
+            format!("hashing/{}", self.dimensions)
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+        }
+    }
+
+    /// FNV-1a, used instead of `DefaultHasher` so stored vectors stay valid across Rust versions.
+    fn fnv1a(text: &str) -> u64 {
+        text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    fn normalize(vector: &mut [f32]) {
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+    }
+
+    pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+        let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+        let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt()
+            * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norms == 0.0 {
+            0.0
+        } else {
+            dot / norms
+        }
+    }
+
+    /// The embedder chosen by `SATURN_EMBEDDER` (`openai`, `local` or `hashing`).
+    /// Without it, OpenAI is used when its key is set and hashing otherwise.
+    pub fn default_embedder() -> Arc<dyn Embedder> {
+        dotenv().ok();
+        match env::var("SATURN_EMBEDDER").as_deref() {
+            Ok("openai") => Arc::new(OpenAIEmbedder::default()),
+            Ok("local") => Arc::new(LocalEmbedder::from_env()),
+            Ok("hashing") => Arc::new(HashingEmbedder::default()),
+            _ if Provider::OpenAI.is_configured() => Arc::new(OpenAIEmbedder::default()),
+            _ => Arc::new(HashingEmbedder::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::embeddings::{cosine_similarity, Embedder, HashingEmbedder};
+
+    #[tokio::test]
+    async fn test_hashing_embedder_prefers_overlapping_text() {
+        let embedder = HashingEmbedder::default();
+        let vectors = embedder
+            .embed(&[
+                "How do I deploy the server with docker".to_string(),
+                "Deploying the Saturn server using docker compose".to_string(),
+                "My favourite pasta recipe".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        let related = cosine_similarity(&vectors[0], &vectors[1]);
+        let unrelated = cosine_similarity(&vectors[0], &vectors[2]);
+        assert!(related > unrelated);
+        assert!((cosine_similarity(&vectors[0], &vectors[0]) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_cosine_similarity_handles_mismatched_vectors() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
@@ -0,0 +1,48 @@
+pub mod extract {
+    use crate::chat_completions::utils::json_query::json_query::json_query;
+    use anyhow::Result;
+    use serde_json::json;
+
+    /// Pulls durable facts worth remembering (preferences, projects, names) out of
+    /// an exchange, skipping anything only relevant to the question at hand.
+    ///
+    /// # Arguments
+    /// * `query` - A `String` representing the user query.
+    /// * `response` - A `String` representing Saturn's answer.
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>>` - Short standalone facts, possibly none.
+    pub async fn extract_memories(query: String, response: String) -> Result<Vec<String>> {
+        let json_response = json_query(
+            "Which long-term facts about the user does this exchange reveal".to_string(),
+            "extract_memories".to_string(),
+            "Lists durable facts about the user worth remembering in future conversations, such as their name, preferences, tools, projects or constraints. Each fact is a short standalone sentence about the user. Returns an empty list when the exchange reveals nothing lasting.".to_string(),
+            json!({
+                "memories": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Standalone facts about the user, e.g. 'The user's name is Ada.'"
+                }
+            }),
+            vec!["memories".to_string()],
+            json!({
+                "query": &query,
+                "response": &response
+            }),
+        )
+        .await?;
+
+        Ok(json_response
+            .get("memories")
+            .and_then(|v| v.as_array())
+            .map(|memories| {
+                memories
+                    .iter()
+                    .filter_map(|memory| memory.as_str())
+                    .map(|memory| memory.trim().to_string())
+                    .filter(|memory| !memory.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod corpus;
+pub mod embeddings;
+pub mod extract;
+pub mod recall;
+pub mod sessions;
+pub mod store;
+pub mod vector_index;
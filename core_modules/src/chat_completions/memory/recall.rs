@@ -0,0 +1,215 @@
+pub mod recall {
+    use crate::chat_completions::memory::embeddings::embeddings::{default_embedder, Embedder};
+    use crate::chat_completions::memory::store::store::LOCAL_CLIENT;
+    use crate::chat_completions::memory::vector_index::vector_index::{VectorEntry, VectorIndex};
+    use crate::chat_completions::utils::paths::paths::saturn_file;
+    use anyhow::Result;
+    use chrono::Utc;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    /// Exchanges scoring below this similarity are not worth showing the model.
+    pub const MIN_SIMILARITY: f32 = 0.3;
+
+    /// A past exchange recalled for the current query.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RecalledExchange {
+        pub score: f32,
+        pub query: String,
+        pub response: String,
+    }
+
+    /// Semantic recall over past conversations: every answered exchange is
+    /// embedded into a vector index and the closest ones are retrieved per query.
+    /// Each handle recalls only the exchanges of one client.
+    pub struct ConversationRecall {
+        embedder: Arc<dyn Embedder>,
+        index: Arc<Mutex<VectorIndex>>,
+        client: String,
+    }
+
+    impl ConversationRecall {
+        pub fn new(embedder: Arc<dyn Embedder>, index: VectorIndex) -> Self {
+            ConversationRecall {
+                embedder,
+                index: Arc::new(Mutex::new(index)),
+                client: LOCAL_CLIENT.to_string(),
+            }
+        }
+
+        /// A handle on the same index that only sees `client`'s exchanges.
+        pub fn for_client(&self, client: &str) -> ConversationRecall {
+            ConversationRecall {
+                embedder: self.embedder.clone(),
+                index: self.index.clone(),
+                client: client.to_string(),
+            }
+        }
+
+        /// Opens `recall.json` inside the Saturn home directory with the default embedder.
+        pub fn open_default() -> Result<Self> {
+            let embedder = default_embedder();
+            let index = VectorIndex::open(saturn_file("recall.json")?, &embedder.name())?;
+            Ok(ConversationRecall::new(embedder, index))
+        }
+
+        /// Embeds and stores an answered exchange.
+        pub async fn add_exchange(&self, query: &str, response: &str) -> Result<()> {
+            let text = format!("User: {query}\nSaturn: {response}");
+            let vector = self
+                .embedder
+                .embed(std::slice::from_ref(&text))
+                .await?
+                .pop()
+                .unwrap_or_default();
+
+            let mut index = self.index.lock().await;
+            let id = format!("exchange-{}-{}", Utc::now().timestamp_millis(), index.len());
+            index.upsert(VectorEntry {
+                id,
+                text,
+                metadata: json!({ "query": query, "response": response, "client": self.client }),
+                vector,
+            })?;
+            index.persist().await
+        }
+
+        /// Removes a stored exchange, so a forgotten fact is not recalled.
+        /// Returns whether anything was removed.
+        pub async fn forget_exchange(&self, query: &str, response: &str) -> Result<bool> {
+            let mut index = self.index.lock().await;
+            let before = index.len();
+            index.remove_where(|entry| {
+                entry.client() == self.client
+                    && entry.metadata["query"] == query
+                    && entry.metadata["response"] == response
+            });
+            if index.len() == before {
+                return Ok(false);
+            }
+            index.persist().await?;
+            Ok(true)
+        }
+
+        /// The past exchanges most similar to `query`, best first.
+        pub async fn relevant(&self, query: &str, limit: usize) -> Result<Vec<RecalledExchange>> {
+            if self.index.lock().await.is_empty() {
+                return Ok(Vec::new());
+            }
+            let vector = self
+                .embedder
+                .embed(&[query.to_string()])
+                .await?
+                .pop()
+                .unwrap_or_default();
+
+            let index = self.index.lock().await;
+            Ok(index
+                .search_where(&vector, limit, |entry| entry.client() == self.client)
+                .into_iter()
+                .filter(|(score, _)| *score >= MIN_SIMILARITY)
+                .map(|(score, entry)| RecalledExchange {
+                    score,
+                    query: entry.metadata["query"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    response: entry.metadata["response"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                })
+                .collect())
+        }
+
+        /// A prompt section listing the recalled exchanges, or `None` if nothing is relevant.
+        pub async fn context_for(&self, query: &str) -> Result<Option<String>> {
+            let recalled = self.relevant(query, 3).await?;
+            if recalled.is_empty() {
+                return Ok(None);
+            }
+            let mut prompt = String::from("Related past exchanges:\n");
+            for exchange in recalled {
+                prompt.push_str(&format!(
+                    "User: {}\nSaturn: {}\n",
+                    exchange.query, exchange.response
+                ));
+            }
+            Ok(Some(prompt))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::recall::ConversationRecall;
+    use crate::chat_completions::memory::embeddings::embeddings::HashingEmbedder;
+    use crate::chat_completions::memory::vector_index::vector_index::VectorIndex;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_recall_returns_related_exchanges() {
+        let recall = ConversationRecall::new(
+            Arc::new(HashingEmbedder::default()),
+            VectorIndex::in_memory("hashing/512"),
+        );
+        assert!(recall.context_for("anything").await.unwrap().is_none());
+
+        recall
+            .add_exchange(
+                "How do I configure the Saturn server port?",
+                "The server listens on port 2223.",
+            )
+            .await
+            .unwrap();
+        recall
+            .add_exchange("Suggest a pasta recipe", "Try cacio e pepe.")
+            .await
+            .unwrap();
+
+        let recalled = recall
+            .relevant("Which port does the Saturn server use?", 3)
+            .await
+            .unwrap();
+        assert_eq!(recalled.len(), 1);
+        assert_eq!(recalled[0].response, "The server listens on port 2223.");
+
+        assert!(recall
+            .forget_exchange(
+                "How do I configure the Saturn server port?",
+                "The server listens on port 2223.",
+            )
+            .await
+            .unwrap());
+        assert!(recall
+            .relevant("Which port does the Saturn server use?", 3)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recall_is_scoped_to_the_client() {
+        let recall = ConversationRecall::new(
+            Arc::new(HashingEmbedder::default()),
+            VectorIndex::in_memory("hashing/512"),
+        );
+        let ada = recall.for_client("ada");
+        ada.add_exchange("Where do I live?", "You live in Oslo.")
+            .await
+            .unwrap();
+
+        let grace = recall.for_client("grace");
+        assert!(grace
+            .context_for("Where do I live?")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!grace
+            .forget_exchange("Where do I live?", "You live in Oslo.")
+            .await
+            .unwrap());
+        assert_eq!(ada.relevant("Where do I live?", 3).await.unwrap().len(), 1);
+    }
+}
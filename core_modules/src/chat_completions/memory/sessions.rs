@@ -0,0 +1,368 @@
+pub mod sessions {
+    use crate::chat_completions::memory::store::store::{ensure_column, LOCAL_CLIENT};
+    use crate::chat_completions::utils::messages::messages::{ChatMessage, Role};
+    use crate::chat_completions::utils::paths::paths::saturn_file;
+    use anyhow::{anyhow, Result};
+    use chrono::Utc;
+    use rusqlite::{params, Connection, OptionalExtension};
+    use serde::Serialize;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use uuid::Uuid;
+
+    /// Titles derived from the first message are cut to this many characters.
+    const TITLE_CHARS: usize = 60;
+
+    /// A server-side conversation.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct Session {
+        pub id: String,
+        pub title: String,
+        pub created_at: String,
+        pub updated_at: String,
+    }
+
+    /// A stored turn of a session.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct SessionMessage {
+        pub id: i64,
+        pub role: Role,
+        pub content: String,
+        pub created_at: String,
+    }
+
+    /// SQLite-backed conversations, so clients only send the newest message.
+    /// Each handle sees only the sessions one client owns.
+    pub struct SessionStore {
+        connection: Arc<Mutex<Connection>>,
+        client: String,
+    }
+
+    const SCHEMA: &str = "
+        PRAGMA foreign_keys = ON;
+        CREATE TABLE IF NOT EXISTS sessions (
+            id TEXT PRIMARY KEY,
+            title TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            client TEXT NOT NULL DEFAULT 'local'
+        );
+        CREATE TABLE IF NOT EXISTS session_messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+            role TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+    ";
+
+    fn parse_role(role: &str) -> Role {
+        match role {
+            "system" => Role::System,
+            "assistant" => Role::Assistant,
+            _ => Role::User,
+        }
+    }
+
+    /// A title for an untitled session, taken from its first message.
+    fn title_from(message: &str) -> String {
+        let line = message.lines().next().unwrap_or_default().trim();
+        if line.chars().count() <= TITLE_CHARS {
+            line.to_string()
+        } else {
+            let cut: String = line.chars().take(TITLE_CHARS).collect();
+            format!("{}…", cut.trim_end())
+        }
+    }
+
+    impl SessionStore {
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            SessionStore::from_connection(Connection::open(path)?)
+        }
+
+        /// Opens `sessions.db` inside the Saturn home directory.
+        pub fn open_default() -> Result<Self> {
+            SessionStore::open(saturn_file("sessions.db")?)
+        }
+
+        pub fn in_memory() -> Result<Self> {
+            SessionStore::from_connection(Connection::open_in_memory()?)
+        }
+
+        fn from_connection(connection: Connection) -> Result<Self> {
+            connection.execute_batch(SCHEMA)?;
+            ensure_column(
+                &connection,
+                "sessions",
+                "client",
+                "TEXT NOT NULL DEFAULT 'local'",
+            )?;
+            Ok(SessionStore {
+                connection: Arc::new(Mutex::new(connection)),
+                client: LOCAL_CLIENT.to_string(),
+            })
+        }
+
+        /// A handle on the same database that only sees `client`'s sessions.
+        pub fn for_client(&self, client: &str) -> SessionStore {
+            SessionStore {
+                connection: self.connection.clone(),
+                client: client.to_string(),
+            }
+        }
+
+        fn connection(&self) -> Result<MutexGuard<'_, Connection>> {
+            self.connection
+                .lock()
+                .map_err(|_| anyhow!("Session store lock was poisoned"))
+        }
+
+        /// Starts a session. Without a title, the first message names it.
+        pub fn create_session(&self, title: Option<&str>) -> Result<Session> {
+            let now = Utc::now().to_rfc3339();
+            let session = Session {
+                id: Uuid::new_v4().to_string(),
+                title: title.map(str::trim).unwrap_or_default().to_string(),
+                created_at: now.clone(),
+                updated_at: now,
+            };
+            self.connection()?.execute(
+                "INSERT INTO sessions (id, title, created_at, updated_at, client)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    session.id,
+                    session.title,
+                    session.created_at,
+                    session.updated_at,
+                    self.client
+                ],
+            )?;
+            Ok(session)
+        }
+
+        /// All sessions, most recently active first.
+        pub fn list_sessions(&self) -> Result<Vec<Session>> {
+            let connection = self.connection()?;
+            let mut statement = connection.prepare(
+                "SELECT id, title, created_at, updated_at FROM sessions WHERE client = ?1
+                 ORDER BY updated_at DESC",
+            )?;
+            let sessions = statement
+                .query_map(params![self.client], |row| {
+                    Ok(Session {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        created_at: row.get(2)?,
+                        updated_at: row.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(sessions)
+        }
+
+        pub fn get_session(&self, id: &str) -> Result<Option<Session>> {
+            let session = self
+                .connection()?
+                .query_row(
+                    "SELECT id, title, created_at, updated_at FROM sessions
+                     WHERE id = ?1 AND client = ?2",
+                    params![id, self.client],
+                    |row| {
+                        Ok(Session {
+                            id: row.get(0)?,
+                            title: row.get(1)?,
+                            created_at: row.get(2)?,
+                            updated_at: row.get(3)?,
+                        })
+                    },
+                )
+                .optional()?;
+            Ok(session)
+        }
+
+        /// A session's turns, oldest first.
+        pub fn messages(&self, id: &str) -> Result<Vec<SessionMessage>> {
+            let connection = self.connection()?;
+            let mut statement = connection.prepare(
+                "SELECT m.id, m.role, m.content, m.created_at FROM session_messages m
+                 JOIN sessions s ON s.id = m.session_id
+                 WHERE m.session_id = ?1 AND s.client = ?2 ORDER BY m.id",
+            )?;
+            let messages = statement
+                .query_map(params![id, self.client], |row| {
+                    Ok(SessionMessage {
+                        id: row.get(0)?,
+                        role: parse_role(&row.get::<_, String>(1)?),
+                        content: row.get(2)?,
+                        created_at: row.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(messages)
+        }
+
+        /// A session's turns as provider-ready history.
+        pub fn history(&self, id: &str) -> Result<Vec<ChatMessage>> {
+            Ok(self
+                .messages(id)?
+                .into_iter()
+                .map(|message| ChatMessage::new(message.role, message.content))
+                .collect())
+        }
+
+        /// Appends a turn, naming the session after it if it has no title yet.
+        /// Returns `false` if no session has that id.
+        pub fn append_message(&self, id: &str, message: &ChatMessage) -> Result<bool> {
+            self.append_messages(id, std::slice::from_ref(message))
+        }
+
+        /// Appends turns in a single transaction, so a question is never stored
+        /// without its answer. Returns `false` if no session has that id.
+        pub fn append_messages(&self, id: &str, messages: &[ChatMessage]) -> Result<bool> {
+            if messages.is_empty() {
+                return Ok(self.get_session(id)?.is_some());
+            }
+            self.write_messages(id, messages, false)
+        }
+
+        /// Replaces a session's turns with `messages` in a single transaction,
+        /// for a conversation edited since it was saved. Returns `false` if no
+        /// session has that id.
+        pub fn replace_messages(&self, id: &str, messages: &[ChatMessage]) -> Result<bool> {
+            self.write_messages(id, messages, true)
+        }
+
+        fn write_messages(
+            &self,
+            id: &str,
+            messages: &[ChatMessage],
+            replace: bool,
+        ) -> Result<bool> {
+            let title = messages
+                .first()
+                .map(|first| title_from(&first.content))
+                .unwrap_or_default();
+            let mut connection = self.connection()?;
+            let transaction = connection.transaction()?;
+            let now = Utc::now().to_rfc3339();
+            let changed = transaction.execute(
+                "UPDATE sessions SET updated_at = ?1,
+                 title = CASE WHEN title = '' THEN ?2 ELSE title END
+                 WHERE id = ?3 AND client = ?4",
+                params![now, title, id, self.client],
+            )?;
+            if changed == 0 {
+                return Ok(false);
+            }
+            if replace {
+                transaction.execute(
+                    "DELETE FROM session_messages WHERE session_id = ?1",
+                    params![id],
+                )?;
+            }
+            for message in messages {
+                transaction.execute(
+                    "INSERT INTO session_messages (session_id, role, content, created_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![id, message.role.as_str(), message.content, now],
+                )?;
+            }
+            transaction.commit()?;
+            Ok(true)
+        }
+
+        /// Returns `false` if no session has that id.
+        pub fn rename_session(&self, id: &str, title: &str) -> Result<bool> {
+            let changed = self.connection()?.execute(
+                "UPDATE sessions SET title = ?1, updated_at = ?2 WHERE id = ?3 AND client = ?4",
+                params![title.trim(), Utc::now().to_rfc3339(), id, self.client],
+            )?;
+            Ok(changed > 0)
+        }
+
+        /// Deletes a session and its turns. Returns `false` if no session has that id.
+        pub fn delete_session(&self, id: &str) -> Result<bool> {
+            let changed = self.connection()?.execute(
+                "DELETE FROM sessions WHERE id = ?1 AND client = ?2",
+                params![id, self.client],
+            )?;
+            Ok(changed > 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sessions::SessionStore;
+    use crate::chat_completions::utils::messages::messages::{ChatMessage, Role};
+
+    #[test]
+    fn test_session_lifecycle() {
+        let store = SessionStore::in_memory().unwrap();
+        let session = store.create_session(None).unwrap();
+        assert!(store
+            .append_messages(
+                &session.id,
+                &[
+                    ChatMessage::user("How tall is Olympus Mons?"),
+                    ChatMessage::assistant("About 22 km."),
+                ],
+            )
+            .unwrap());
+
+        let stored = store.get_session(&session.id).unwrap().unwrap();
+        assert_eq!(stored.title, "How tall is Olympus Mons?");
+        let history = store.history(&session.id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].role, Role::Assistant);
+
+        assert!(store
+            .replace_messages(
+                &session.id,
+                &[
+                    ChatMessage::user("How tall is Olympus Mons?"),
+                    ChatMessage::assistant("About 21.9 km above the datum."),
+                ],
+            )
+            .unwrap());
+        let history = store.history(&session.id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].content, "About 21.9 km above the datum.");
+
+        assert!(store.rename_session(&session.id, "Mars").unwrap());
+        assert_eq!(store.list_sessions().unwrap()[0].title, "Mars");
+
+        assert!(store.delete_session(&session.id).unwrap());
+        assert!(store.get_session(&session.id).unwrap().is_none());
+        assert!(store.messages(&session.id).unwrap().is_empty());
+        assert!(!store
+            .append_message(&session.id, &ChatMessage::user("Hello?"))
+            .unwrap());
+        assert!(!store
+            .replace_messages(&session.id, &[ChatMessage::user("Hello?")])
+            .unwrap());
+    }
+
+    #[test]
+    fn test_sessions_belong_to_their_client() {
+        let store = SessionStore::in_memory().unwrap();
+        let ada = store.for_client("ada");
+        let session = ada.create_session(Some("Mars")).unwrap();
+        assert!(ada
+            .append_message(&session.id, &ChatMessage::user("How far is Mars?"))
+            .unwrap());
+
+        let grace = store.for_client("grace");
+        assert!(grace.list_sessions().unwrap().is_empty());
+        assert!(grace.get_session(&session.id).unwrap().is_none());
+        assert!(grace.messages(&session.id).unwrap().is_empty());
+        assert!(!grace
+            .append_message(&session.id, &ChatMessage::user("Hello?"))
+            .unwrap());
+        assert!(!grace.rename_session(&session.id, "Venus").unwrap());
+        assert!(!grace.delete_session(&session.id).unwrap());
+
+        assert_eq!(ada.history(&session.id).unwrap().len(), 1);
+        assert_eq!(ada.list_sessions().unwrap()[0].title, "Mars");
+    }
+}
@@ -0,0 +1,453 @@
+pub mod store {
+    use crate::chat_completions::utils::paths::paths::saturn_file;
+    use anyhow::{anyhow, Result};
+    use chrono::Utc;
+    use rusqlite::{params, Connection, OptionalExtension};
+    use serde::Serialize;
+    use std::collections::HashSet;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    /// The client the chat, the TUI and a server without keys act as. Stores
+    /// keep each client's data apart, and data from before clients were
+    /// tracked belongs to this one.
+    pub const LOCAL_CLIENT: &str = "local";
+
+    /// A fact Saturn remembers about the user or their work.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct Memory {
+        pub id: i64,
+        pub content: String,
+        pub created_at: String,
+        pub updated_at: String,
+    }
+
+    /// A past query and the answer Saturn gave to it.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    pub struct Exchange {
+        pub id: i64,
+        pub query: String,
+        pub response: String,
+        pub created_at: String,
+    }
+
+    /// SQLite-backed long-term memory shared by the chat and the server.
+    /// Each handle sees only the memories and exchanges of one client.
+    pub struct MemoryStore {
+        connection: Arc<Mutex<Connection>>,
+        client: String,
+    }
+
+    const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS exchanges (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            query TEXT NOT NULL,
+            response TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            client TEXT NOT NULL DEFAULT 'local'
+        );
+        CREATE TABLE IF NOT EXISTS memories (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            exchange_id INTEGER,
+            client TEXT NOT NULL DEFAULT 'local'
+        );
+    ";
+
+    /// Adds a column that tables created by an older version lack.
+    pub(crate) fn ensure_column(
+        connection: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<()> {
+        let mut statement = connection.prepare(&format!("PRAGMA table_info({table})"))?;
+        let columns = statement
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if !columns.iter().any(|name| name == column) {
+            connection.execute_batch(&format!(
+                "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Words too common to say anything about relevance.
+    const STOPWORDS: &[&str] = &[
+        "the", "and", "for", "are", "but", "not", "you", "your", "with", "what", "when", "where",
+        "who", "why", "how", "this", "that", "was", "were", "have", "has", "had", "can", "does",
+        "did", "about", "from", "they", "them", "their", "there", "which", "will", "would",
+        "should", "could", "into", "than", "then", "some", "any", "all", "our", "out",
+    ];
+
+    impl MemoryStore {
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            MemoryStore::from_connection(Connection::open(path)?)
+        }
+
+        /// Opens `memory.db` inside the Saturn home directory.
+        pub fn open_default() -> Result<Self> {
+            MemoryStore::open(saturn_file("memory.db")?)
+        }
+
+        /// A throwaway store, used by tests and when no disk is available.
+        pub fn in_memory() -> Result<Self> {
+            MemoryStore::from_connection(Connection::open_in_memory()?)
+        }
+
+        fn from_connection(connection: Connection) -> Result<Self> {
+            connection.execute_batch(SCHEMA)?;
+            ensure_column(&connection, "memories", "exchange_id", "INTEGER")?;
+            for table in ["exchanges", "memories"] {
+                ensure_column(
+                    &connection,
+                    table,
+                    "client",
+                    "TEXT NOT NULL DEFAULT 'local'",
+                )?;
+            }
+            Ok(MemoryStore {
+                connection: Arc::new(Mutex::new(connection)),
+                client: LOCAL_CLIENT.to_string(),
+            })
+        }
+
+        /// A handle on the same database that only sees `client`'s data.
+        pub fn for_client(&self, client: &str) -> MemoryStore {
+            MemoryStore {
+                connection: self.connection.clone(),
+                client: client.to_string(),
+            }
+        }
+
+        pub fn client(&self) -> &str {
+            &self.client
+        }
+
+        pub(crate) fn connection(&self) -> Result<MutexGuard<'_, Connection>> {
+            self.connection
+                .lock()
+                .map_err(|_| anyhow!("Memory store lock was poisoned"))
+        }
+
+        pub fn record_exchange(&self, query: &str, response: &str) -> Result<i64> {
+            let connection = self.connection()?;
+            connection.execute(
+                "INSERT INTO exchanges (query, response, created_at, client)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![query, response, Utc::now().to_rfc3339(), self.client],
+            )?;
+            Ok(connection.last_insert_rowid())
+        }
+
+        /// The latest `limit` exchanges, oldest first.
+        pub fn recent_exchanges(&self, limit: usize) -> Result<Vec<Exchange>> {
+            let connection = self.connection()?;
+            let mut statement = connection.prepare(
+                "SELECT id, query, response, created_at FROM exchanges WHERE client = ?1
+                 ORDER BY id DESC LIMIT ?2",
+            )?;
+            let mut exchanges = statement
+                .query_map(params![self.client, limit as i64], |row| {
+                    Ok(Exchange {
+                        id: row.get(0)?,
+                        query: row.get(1)?,
+                        response: row.get(2)?,
+                        created_at: row.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            exchanges.reverse();
+            Ok(exchanges)
+        }
+
+        /// Stores a fact unless an identical one is already remembered.
+        pub fn add_memory(&self, content: &str) -> Result<i64> {
+            self.insert_memory(content, None)
+        }
+
+        /// Stores a fact extracted from an exchange, which is deleted along with
+        /// the fact if it is later forgotten or corrected.
+        pub fn add_extracted_memory(&self, content: &str, exchange_id: i64) -> Result<i64> {
+            self.insert_memory(content, Some(exchange_id))
+        }
+
+        fn insert_memory(&self, content: &str, exchange_id: Option<i64>) -> Result<i64> {
+            let content = content.trim();
+            let connection = self.connection()?;
+            let existing: Option<i64> = connection
+                .query_row(
+                    "SELECT id FROM memories WHERE content = ?1 COLLATE NOCASE AND client = ?2",
+                    params![content, self.client],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(id) = existing {
+                return Ok(id);
+            }
+            let now = Utc::now().to_rfc3339();
+            connection.execute(
+                "INSERT INTO memories (content, created_at, updated_at, exchange_id, client)
+                 VALUES (?1, ?2, ?2, ?3, ?4)",
+                params![content, now, exchange_id, self.client],
+            )?;
+            Ok(connection.last_insert_rowid())
+        }
+
+        pub fn list_memories(&self) -> Result<Vec<Memory>> {
+            let connection = self.connection()?;
+            let mut statement = connection.prepare(
+                "SELECT id, content, created_at, updated_at FROM memories WHERE client = ?1
+                 ORDER BY id",
+            )?;
+            let memories = statement
+                .query_map(params![self.client], |row| {
+                    Ok(Memory {
+                        id: row.get(0)?,
+                        content: row.get(1)?,
+                        created_at: row.get(2)?,
+                        updated_at: row.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(memories)
+        }
+
+        /// The exchange a memory was extracted from, if it is still stored.
+        pub fn source_exchange(&self, id: i64) -> Result<Option<Exchange>> {
+            Ok(self
+                .connection()?
+                .query_row(
+                    "SELECT e.id, e.query, e.response, e.created_at FROM memories m
+                     JOIN exchanges e ON e.id = m.exchange_id WHERE m.id = ?1 AND m.client = ?2",
+                    params![id, self.client],
+                    |row| {
+                        Ok(Exchange {
+                            id: row.get(0)?,
+                            query: row.get(1)?,
+                            response: row.get(2)?,
+                            created_at: row.get(3)?,
+                        })
+                    },
+                )
+                .optional()?)
+        }
+
+        /// Replaces a memory's content. The exchange it came from still states
+        /// the old fact, so it is deleted. Returns `false` if no memory has that id.
+        pub fn edit_memory(&self, id: i64, content: &str) -> Result<bool> {
+            let mut connection = self.connection()?;
+            let transaction = connection.transaction()?;
+            delete_source_exchange(&transaction, &self.client, id)?;
+            let changed = transaction.execute(
+                "UPDATE memories SET content = ?1, updated_at = ?2, exchange_id = NULL
+                 WHERE id = ?3 AND client = ?4",
+                params![content.trim(), Utc::now().to_rfc3339(), id, self.client],
+            )?;
+            transaction.commit()?;
+            Ok(changed > 0)
+        }
+
+        /// Deletes a memory and the exchange it came from, so the fact does not
+        /// return through recent exchanges. Returns `false` if no memory has that id.
+        pub fn forget_memory(&self, id: i64) -> Result<bool> {
+            let mut connection = self.connection()?;
+            let transaction = connection.transaction()?;
+            delete_source_exchange(&transaction, &self.client, id)?;
+            let changed = transaction.execute(
+                "DELETE FROM memories WHERE id = ?1 AND client = ?2",
+                params![id, self.client],
+            )?;
+            transaction.commit()?;
+            Ok(changed > 0)
+        }
+
+        /// The memories sharing the most keywords with `query`, best first.
+        pub fn relevant_memories(&self, query: &str, limit: usize) -> Result<Vec<Memory>> {
+            let query_keywords = keywords(query);
+            let mut scored: Vec<(usize, Memory)> = self
+                .list_memories()?
+                .into_iter()
+                .map(|memory| {
+                    (
+                        query_keywords
+                            .intersection(&keywords(&memory.content))
+                            .count(),
+                        memory,
+                    )
+                })
+                .filter(|(score, _)| *score > 0)
+                .collect();
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.id.cmp(&a.1.id)));
+            Ok(scored
+                .into_iter()
+                .take(limit)
+                .map(|(_, memory)| memory)
+                .collect())
+        }
+
+        /// Builds the context block injected into the prompt for `query`: the
+        /// relevant memories plus the last few exchanges. `None` when there is nothing to add.
+        pub fn context_for(&self, query: &str) -> Result<Option<String>> {
+            let memories = self.relevant_memories(query, 5)?;
+            let exchanges = self.recent_exchanges(3)?;
+            Ok(memory_prompt(&memories, &exchanges))
+        }
+    }
+
+    /// Deletes the exchange `client`'s memory `id` was extracted from. Other
+    /// memories from the same exchange keep their content but lose the link.
+    fn delete_source_exchange(connection: &Connection, client: &str, id: i64) -> Result<()> {
+        let exchange_id: Option<i64> = connection
+            .query_row(
+                "SELECT exchange_id FROM memories WHERE id = ?1 AND client = ?2",
+                params![id, client],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        if let Some(exchange_id) = exchange_id {
+            connection.execute(
+                "UPDATE memories SET exchange_id = NULL WHERE exchange_id = ?1",
+                params![exchange_id],
+            )?;
+            connection.execute("DELETE FROM exchanges WHERE id = ?1", params![exchange_id])?;
+        }
+        Ok(())
+    }
+
+    fn keywords(text: &str) -> HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase)
+            .filter(|word| word.len() > 2 && !STOPWORDS.contains(&word.as_str()))
+            .collect()
+    }
+
+    /// Formats remembered facts and exchanges as a system prompt section.
+    pub fn memory_prompt(memories: &[Memory], exchanges: &[Exchange]) -> Option<String> {
+        if memories.is_empty() && exchanges.is_empty() {
+            return None;
+        }
+        let mut prompt = String::new();
+        if !memories.is_empty() {
+            prompt.push_str("Things you remember about the user:\n");
+            for memory in memories {
+                prompt.push_str(&format!("- {}\n", memory.content));
+            }
+        }
+        if !exchanges.is_empty() {
+            if !prompt.is_empty() {
+                prompt.push('\n');
+            }
+            prompt.push_str("Earlier in your conversations:\n");
+            for exchange in exchanges {
+                prompt.push_str(&format!(
+                    "User: {}\nSaturn: {}\n",
+                    exchange.query, exchange.response
+                ));
+            }
+        }
+        Some(prompt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::store::MemoryStore;
+
+    #[test]
+    fn test_list_edit_and_forget_memories() {
+        let store = MemoryStore::in_memory().unwrap();
+        let id = store.add_memory("The user's name is Ada.").unwrap();
+        assert_eq!(store.add_memory("the user's name is ada.").unwrap(), id);
+
+        assert!(store.edit_memory(id, "The user's name is Grace.").unwrap());
+        assert_eq!(
+            store.list_memories().unwrap()[0].content,
+            "The user's name is Grace."
+        );
+
+        assert!(store.forget_memory(id).unwrap());
+        assert!(!store.forget_memory(id).unwrap());
+        assert!(store.list_memories().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_relevant_memories_rank_by_keyword_overlap() {
+        let store = MemoryStore::in_memory().unwrap();
+        store
+            .add_memory("The user deploys services with Kubernetes.")
+            .unwrap();
+        store
+            .add_memory("The user prefers Rust for backend services.")
+            .unwrap();
+        store.add_memory("The user's cat is called Miso.").unwrap();
+
+        let relevant = store
+            .relevant_memories(
+                "Which language should I write these backend services in?",
+                5,
+            )
+            .unwrap();
+        assert_eq!(relevant.len(), 2);
+        assert!(relevant[0].content.contains("Rust"));
+    }
+
+    #[test]
+    fn test_context_includes_recent_exchanges() {
+        let store = MemoryStore::in_memory().unwrap();
+        assert!(store.context_for("hello").unwrap().is_none());
+
+        store.record_exchange("What is 2 + 2?", "4").unwrap();
+        let context = store.context_for("And times 3?").unwrap().unwrap();
+        assert!(context.contains("User: What is 2 + 2?\nSaturn: 4"));
+    }
+
+    #[test]
+    fn test_forgetting_a_memory_deletes_its_exchange() {
+        let store = MemoryStore::in_memory().unwrap();
+        let exchange = store
+            .record_exchange("My name is Ada.", "Nice to meet you, Ada.")
+            .unwrap();
+        let id = store
+            .add_extracted_memory("The user's name is Ada.", exchange)
+            .unwrap();
+        assert_eq!(store.source_exchange(id).unwrap().unwrap().id, exchange);
+
+        assert!(store.forget_memory(id).unwrap());
+        assert!(store.recent_exchanges(10).unwrap().is_empty());
+
+        let exchange = store.record_exchange("I live in Oslo.", "Noted.").unwrap();
+        let id = store
+            .add_extracted_memory("The user lives in Oslo.", exchange)
+            .unwrap();
+        assert!(store.edit_memory(id, "The user lives in Bergen.").unwrap());
+        assert!(store.recent_exchanges(10).unwrap().is_empty());
+        assert!(store.source_exchange(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_clients_only_see_their_own_memories() {
+        let store = MemoryStore::in_memory().unwrap();
+        let ada = store.for_client("ada");
+        let grace = store.for_client("grace");
+        let exchange = ada.record_exchange("I live in Oslo.", "Noted.").unwrap();
+        let id = ada
+            .add_extracted_memory("The user lives in Oslo.", exchange)
+            .unwrap();
+
+        assert!(grace.list_memories().unwrap().is_empty());
+        assert!(grace.context_for("Where do I live?").unwrap().is_none());
+        assert!(store.list_memories().unwrap().is_empty());
+        assert!(!grace.edit_memory(id, "The user lives in Rome.").unwrap());
+        assert!(!grace.forget_memory(id).unwrap());
+        assert!(grace.source_exchange(id).unwrap().is_none());
+
+        let context = ada.context_for("Where do I live?").unwrap().unwrap();
+        assert!(context.contains("Oslo"));
+        assert_eq!(ada.recent_exchanges(10).unwrap().len(), 1);
+    }
+}
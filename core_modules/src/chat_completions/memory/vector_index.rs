@@ -0,0 +1,385 @@
+pub mod vector_index {
+    use crate::chat_completions::memory::embeddings::embeddings::cosine_similarity;
+    use crate::chat_completions::memory::store::store::LOCAL_CLIENT;
+    use anyhow::{bail, Result};
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::collections::HashSet;
+    use std::fs::{self, OpenOptions};
+    use std::io::{ErrorKind, Write};
+    use std::path::{Path, PathBuf};
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct VectorEntry {
+        pub id: String,
+        pub text: String,
+        #[serde(default)]
+        pub metadata: Value,
+        pub vector: Vec<f32>,
+    }
+
+    impl VectorEntry {
+        /// The client the entry belongs to, from `metadata["client"]`. Entries
+        /// written before clients were tracked belong to the local client.
+        pub fn client(&self) -> &str {
+            self.metadata["client"].as_str().unwrap_or(LOCAL_CLIENT)
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Default)]
+    struct VectorIndexFile {
+        embedder: String,
+        entries: Vec<VectorEntry>,
+    }
+
+    /// A change made since the snapshot, one JSON line each in the journal.
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[serde(tag = "op", rename_all = "snake_case")]
+    enum JournalRecord {
+        Upsert { entry: VectorEntry },
+        Remove { ids: Vec<String> },
+    }
+
+    /// Below this many journal records the snapshot is never rewritten.
+    const MIN_JOURNAL_RECORDS: usize = 1024;
+
+    /// A flat, brute-force vector index persisted as a JSON snapshot plus a
+    /// journal of the changes made since, so saving one new entry appends one
+    /// line instead of rewriting every entry. The snapshot is only rewritten
+    /// once the journal outgrows it.
+    ///
+    /// Good for the tens of thousands of entries a personal assistant accumulates;
+    /// every search scores every entry.
+    pub struct VectorIndex {
+        path: Option<PathBuf>,
+        embedder: String,
+        entries: Vec<VectorEntry>,
+        /// Whether the snapshot file exists, recording the embedder.
+        has_snapshot: bool,
+        /// Records already in the journal.
+        journal_records: usize,
+        /// Changes not yet saved.
+        pending: Vec<JournalRecord>,
+    }
+
+    /// Saving work taken out of an index, so it can run without holding it.
+    pub struct IndexWrite(WriteKind);
+
+    enum WriteKind {
+        Append {
+            journal: PathBuf,
+            records: Vec<JournalRecord>,
+        },
+        Snapshot {
+            path: PathBuf,
+            journal: PathBuf,
+            file: VectorIndexFile,
+        },
+    }
+
+    impl IndexWrite {
+        /// Performs the write. Blocking file I/O.
+        pub fn run(self) -> Result<()> {
+            match self.0 {
+                WriteKind::Append { journal, records } => {
+                    let mut lines = Vec::new();
+                    for record in &records {
+                        serde_json::to_writer(&mut lines, record)?;
+                        lines.push(b'\n');
+                    }
+                    let mut file = OpenOptions::new().create(true).append(true).open(journal)?;
+                    file.write_all(&lines)?;
+                    file.sync_data()?;
+                }
+                WriteKind::Snapshot {
+                    path,
+                    journal,
+                    file,
+                } => {
+                    // Write then rename so a crash mid-write never leaves a truncated
+                    // index. Replaying a journal already folded in changes nothing.
+                    let temporary = path.with_extension("tmp");
+                    fs::write(&temporary, serde_json::to_vec(&file)?)?;
+                    fs::rename(temporary, path)?;
+                    match fs::remove_file(journal) {
+                        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn journal_path(path: &Path) -> PathBuf {
+        path.with_extension("journal")
+    }
+
+    impl VectorIndex {
+        /// An index that lives only in memory.
+        pub fn in_memory(embedder: &str) -> Self {
+            VectorIndex {
+                path: None,
+                embedder: embedder.to_string(),
+                entries: Vec::new(),
+                has_snapshot: false,
+                journal_records: 0,
+                pending: Vec::new(),
+            }
+        }
+
+        /// Loads the index at `path` and replays its journal, or starts an empty
+        /// one. An index built with a different embedder has incomparable
+        /// vectors, so it is refused rather than discarded and later overwritten.
+        pub fn open(path: impl AsRef<Path>, embedder: &str) -> Result<Self> {
+            let path = path.as_ref().to_path_buf();
+            let (entries, has_snapshot) = match fs::read_to_string(&path) {
+                Ok(contents) => {
+                    let file: VectorIndexFile = serde_json::from_str(&contents)?;
+                    if file.embedder != embedder {
+                        bail!(
+                            "{} was built with the {} embedder, but {embedder} is configured. \
+                             Set SATURN_EMBEDDER to match, or move the file aside to start a new index.",
+                            path.display(),
+                            file.embedder
+                        );
+                    }
+                    (file.entries, true)
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => (Vec::new(), false),
+                Err(e) => return Err(e.into()),
+            };
+            let mut index = VectorIndex {
+                path: Some(path.clone()),
+                embedder: embedder.to_string(),
+                entries,
+                has_snapshot,
+                journal_records: 0,
+                pending: Vec::new(),
+            };
+            let journal = match fs::read_to_string(journal_path(&path)) {
+                Ok(journal) => journal,
+                Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e.into()),
+            };
+            for line in journal.lines() {
+                // A crash mid-append leaves at most a torn last line. Appending
+                // after it would hide the new records, so the next save rewrites
+                // the snapshot instead.
+                let Ok(record) = serde_json::from_str::<JournalRecord>(line) else {
+                    index.has_snapshot = false;
+                    break;
+                };
+                index.apply(record);
+                index.journal_records += 1;
+            }
+            Ok(index)
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+
+        pub fn entries(&self) -> &[VectorEntry] {
+            &self.entries
+        }
+
+        fn apply(&mut self, record: JournalRecord) {
+            match record {
+                JournalRecord::Upsert { entry } => {
+                    self.entries.retain(|existing| existing.id != entry.id);
+                    self.entries.push(entry);
+                }
+                JournalRecord::Remove { ids } => {
+                    let ids: HashSet<String> = ids.into_iter().collect();
+                    self.entries.retain(|entry| !ids.contains(&entry.id));
+                }
+            }
+        }
+
+        /// Adds an entry, replacing any existing entry with the same id.
+        pub fn upsert(&mut self, entry: VectorEntry) -> Result<()> {
+            if let Some(first) = self.entries.first() {
+                if first.vector.len() != entry.vector.len() {
+                    bail!(
+                        "Vector has {} dimensions but the index uses {}",
+                        entry.vector.len(),
+                        first.vector.len()
+                    );
+                }
+            }
+            if self.path.is_some() {
+                self.pending.push(JournalRecord::Upsert {
+                    entry: entry.clone(),
+                });
+            }
+            self.apply(JournalRecord::Upsert { entry });
+            Ok(())
+        }
+
+        /// Removes every entry satisfying `predicate`.
+        pub fn remove_where(&mut self, predicate: impl Fn(&VectorEntry) -> bool) {
+            let ids: Vec<String> = self
+                .entries
+                .iter()
+                .filter(|entry| predicate(entry))
+                .map(|entry| entry.id.clone())
+                .collect();
+            if ids.is_empty() {
+                return;
+            }
+            if self.path.is_some() {
+                self.pending
+                    .push(JournalRecord::Remove { ids: ids.clone() });
+            }
+            self.apply(JournalRecord::Remove { ids });
+        }
+
+        /// The `limit` entries most similar to `vector`, best first, with their scores.
+        pub fn search(&self, vector: &[f32], limit: usize) -> Vec<(f32, &VectorEntry)> {
+            self.search_where(vector, limit, |_| true)
+        }
+
+        /// [`VectorIndex::search`] over only the entries satisfying `predicate`.
+        pub fn search_where(
+            &self,
+            vector: &[f32],
+            limit: usize,
+            predicate: impl Fn(&VectorEntry) -> bool,
+        ) -> Vec<(f32, &VectorEntry)> {
+            let mut scored: Vec<(f32, &VectorEntry)> = self
+                .entries
+                .iter()
+                .filter(|entry| predicate(entry))
+                .map(|entry| (cosine_similarity(vector, &entry.vector), entry))
+                .collect();
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+            scored.truncate(limit);
+            scored
+        }
+
+        /// Takes the changes made since the last save as a write to run later,
+        /// or `None` if there is nothing to save. Changes are appended to the
+        /// journal until it outgrows the index, which is then rewritten whole.
+        pub fn take_write(&mut self) -> Option<IndexWrite> {
+            let path = self.path.clone()?;
+            if self.pending.is_empty() {
+                return None;
+            }
+            let records = std::mem::take(&mut self.pending);
+            let journal = journal_path(&path);
+            let journal_records = self.journal_records + records.len();
+            if self.has_snapshot && journal_records <= self.entries.len().max(MIN_JOURNAL_RECORDS) {
+                self.journal_records = journal_records;
+                return Some(IndexWrite(WriteKind::Append { journal, records }));
+            }
+            self.has_snapshot = true;
+            self.journal_records = 0;
+            Some(IndexWrite(WriteKind::Snapshot {
+                path,
+                journal,
+                file: VectorIndexFile {
+                    embedder: self.embedder.clone(),
+                    entries: self.entries.clone(),
+                },
+            }))
+        }
+
+        /// Writes the changes made since the last save. A no-op for in-memory indexes.
+        pub fn save(&mut self) -> Result<()> {
+            match self.take_write() {
+                Some(write) => write.run(),
+                None => Ok(()),
+            }
+        }
+
+        /// [`VectorIndex::save`] on the blocking thread pool, so the file I/O
+        /// does not stall the async runtime.
+        pub async fn persist(&mut self) -> Result<()> {
+            match self.take_write() {
+                Some(write) => tokio::task::spawn_blocking(move || write.run()).await?,
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::vector_index::{VectorEntry, VectorIndex};
+    use serde_json::json;
+
+    fn entry(id: &str, vector: Vec<f32>) -> VectorEntry {
+        VectorEntry {
+            id: id.to_string(),
+            text: format!("text of {id}"),
+            metadata: json!({}),
+            vector,
+        }
+    }
+
+    #[test]
+    fn test_search_orders_by_similarity() {
+        let mut index = VectorIndex::in_memory("test");
+        index.upsert(entry("x", vec![1.0, 0.0])).unwrap();
+        index.upsert(entry("y", vec![0.0, 1.0])).unwrap();
+        index.upsert(entry("xy", vec![1.0, 1.0])).unwrap();
+
+        let results = index.search(&[1.0, 0.1], 2);
+        assert_eq!(results[0].1.id, "x");
+        assert_eq!(results[1].1.id, "xy");
+        let results = index.search_where(&[1.0, 0.1], 2, |entry| entry.id != "x");
+        assert_eq!(results[0].1.id, "xy");
+        assert!(index.upsert(entry("z", vec![1.0])).is_err());
+    }
+
+    #[test]
+    fn test_index_round_trips_through_disk() {
+        let path = std::env::temp_dir().join(format!("saturn-index-{}.json", std::process::id()));
+        let mut index = VectorIndex::open(&path, "test").unwrap();
+        index.upsert(entry("a", vec![0.5, 0.5])).unwrap();
+        index.upsert(entry("a", vec![0.6, 0.4])).unwrap();
+        index.save().unwrap();
+
+        let reopened = VectorIndex::open(&path, "test").unwrap();
+        assert_eq!(reopened.entries(), index.entries());
+        assert_eq!(reopened.len(), 1);
+
+        // An index from another embedder is refused and left untouched
+        assert!(VectorIndex::open(&path, "other").is_err());
+        assert_eq!(VectorIndex::open(&path, "test").unwrap().len(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_later_saves_append_to_the_journal() {
+        let path = std::env::temp_dir().join(format!("saturn-journal-{}.json", std::process::id()));
+        let journal = path.with_extension("journal");
+        let mut index = VectorIndex::open(&path, "test").unwrap();
+        index.upsert(entry("a", vec![0.5, 0.5])).unwrap();
+        index.persist().await.unwrap();
+        let snapshot = std::fs::read(&path).unwrap();
+
+        index.upsert(entry("b", vec![0.1, 0.9])).unwrap();
+        index.persist().await.unwrap();
+        index.upsert(entry("c", vec![0.9, 0.1])).unwrap();
+        index.remove_where(|entry| entry.id == "a");
+        index.persist().await.unwrap();
+        // The snapshot is left alone; the journal holds the three changes
+        assert_eq!(std::fs::read(&path).unwrap(), snapshot);
+        assert_eq!(
+            std::fs::read_to_string(&journal).unwrap().lines().count(),
+            3
+        );
+
+        let reopened = VectorIndex::open(&path, "test").unwrap();
+        assert_eq!(reopened.entries(), index.entries());
+        assert_eq!(reopened.len(), 2);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&journal).unwrap();
+    }
+}
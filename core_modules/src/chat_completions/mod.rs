@@ -1,4 +1,6 @@
 pub mod bots;
 pub mod interfaces;
+pub mod memory;
 pub mod providers;
+pub mod tools;
 pub mod utils;
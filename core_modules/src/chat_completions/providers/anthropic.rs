@@ -0,0 +1,381 @@
+pub mod anthropic {
+    use crate::chat_completions::tools::registry::registry::ToolRegistry;
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use crate::chat_completions::utils::messages::messages::{split_system, ChatMessage};
+    use crate::chat_completions::utils::sse::sse::SseParser;
+    use anyhow::{bail, Context, Error, Result};
+    use dotenv::dotenv;
+    use futures_util::StreamExt;
+    use reqwest::{header, Client, StatusCode};
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::env;
+
+    pub const ANTHROPIC_MODEL: &str = "claude-3-5-sonnet-latest";
+    pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+    const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum AnthropicContentBlock {
+        Text {
+            text: String,
+        },
+        ToolUse {
+            id: String,
+            name: String,
+            input: Value,
+        },
+        ToolResult {
+            tool_use_id: String,
+            content: String,
+        },
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct AnthropicMessage {
+        pub role: String,
+        pub content: Vec<AnthropicContentBlock>,
+    }
+
+    impl AnthropicMessage {
+        pub fn new(role: String, content: Vec<AnthropicContentBlock>) -> Self {
+            AnthropicMessage { role, content }
+        }
+
+        pub fn text(role: String, text: String) -> Self {
+            AnthropicMessage::new(role, vec![AnthropicContentBlock::Text { text }])
+        }
+    }
+
+    #[derive(Serialize, Clone, Debug)]
+    pub struct AnthropicTool {
+        name: String,
+        description: String,
+        input_schema: Value,
+    }
+
+    impl AnthropicTool {
+        /// Exposes every tool in the registry using Anthropic's tool format.
+        pub fn from_registry(registry: &ToolRegistry) -> Vec<AnthropicTool> {
+            registry
+                .names()
+                .into_iter()
+                .filter_map(|name| registry.get(&name))
+                .map(|tool| AnthropicTool {
+                    name: tool.name().to_string(),
+                    description: tool.description().to_string(),
+                    input_schema: tool.parameters(),
+                })
+                .collect()
+        }
+    }
+
+    #[derive(Serialize, Debug)]
+    pub struct AnthropicPayload {
+        model: String,
+        max_tokens: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        system: Option<String>,
+        messages: Vec<AnthropicMessage>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        tools: Vec<AnthropicTool>,
+        stream: bool,
+    }
+
+    impl AnthropicPayload {
+        pub fn new(model: String, system: Option<String>, messages: Vec<AnthropicMessage>) -> Self {
+            AnthropicPayload {
+                model,
+                max_tokens: DEFAULT_MAX_TOKENS,
+                system,
+                messages,
+                tools: Vec::new(),
+                stream: false,
+            }
+        }
+
+        /// Builds a payload from provider-agnostic messages, lifting system turns
+        /// into the top-level `system` field as the Messages API requires.
+        pub fn from_chat(model: String, messages: &[ChatMessage]) -> Self {
+            let (system, turns) = split_system(messages);
+            let messages = turns
+                .into_iter()
+                .map(|m| AnthropicMessage::text(m.role.as_str().to_string(), m.content))
+                .collect();
+            AnthropicPayload::new(model, system, messages)
+        }
+
+        pub fn with_tools(mut self, tools: Vec<AnthropicTool>) -> Self {
+            self.tools = tools;
+            self
+        }
+
+        pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+            self.max_tokens = max_tokens;
+            self
+        }
+    }
+
+    #[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+    pub struct AnthropicUsage {
+        #[serde(default)]
+        pub input_tokens: u32,
+        #[serde(default)]
+        pub output_tokens: u32,
+    }
+
+    #[derive(Deserialize, Clone, Debug, Default)]
+    pub struct AnthropicResponse {
+        #[serde(default)]
+        pub id: String,
+        #[serde(default)]
+        pub content: Vec<AnthropicContentBlock>,
+        pub stop_reason: Option<String>,
+        #[serde(default)]
+        pub usage: AnthropicUsage,
+    }
+
+    impl AnthropicResponse {
+        /// Concatenates every text block of the response.
+        pub fn text(&self) -> String {
+            self.content
+                .iter()
+                .filter_map(|block| match block {
+                    AnthropicContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        /// The tool invocations the model asked for, as `(id, name, input)`.
+        pub fn tool_uses(&self) -> Vec<(String, String, Value)> {
+            self.content
+                .iter()
+                .filter_map(|block| match block {
+                    AnthropicContentBlock::ToolUse { id, name, input } => {
+                        Some((id.clone(), name.clone(), input.clone()))
+                    }
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    /// Rebuilds a full `AnthropicResponse` from the events of a streamed reply.
+    #[derive(Default)]
+    pub struct AnthropicStreamState {
+        response: AnthropicResponse,
+        partial_json: Vec<String>,
+    }
+
+    impl AnthropicStreamState {
+        /// Applies one streamed event and returns any text it added.
+        pub fn apply(&mut self, event: &Value) -> Result<Option<String>> {
+            match event["type"].as_str().unwrap_or_default() {
+                "message_start" => {
+                    self.response = serde_json::from_value(event["message"].clone())?;
+                }
+                "content_block_start" => {
+                    let block: AnthropicContentBlock =
+                        serde_json::from_value(event["content_block"].clone())?;
+                    self.response.content.push(block);
+                    self.partial_json.push(String::new());
+                }
+                "content_block_delta" => {
+                    let index = event["index"].as_u64().unwrap_or_default() as usize;
+                    let delta = &event["delta"];
+                    match delta["type"].as_str().unwrap_or_default() {
+                        "text_delta" => {
+                            let text = delta["text"].as_str().unwrap_or_default().to_string();
+                            if let Some(AnthropicContentBlock::Text { text: existing }) =
+                                self.response.content.get_mut(index)
+                            {
+                                existing.push_str(&text);
+                            }
+                            return Ok(Some(text));
+                        }
+                        "input_json_delta" => {
+                            if let Some(buffer) = self.partial_json.get_mut(index) {
+                                buffer.push_str(delta["partial_json"].as_str().unwrap_or_default());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                "content_block_stop" => {
+                    let index = event["index"].as_u64().unwrap_or_default() as usize;
+                    let buffer = self.partial_json.get(index).cloned().unwrap_or_default();
+                    if let Some(AnthropicContentBlock::ToolUse { input, .. }) =
+                        self.response.content.get_mut(index)
+                    {
+                        if !buffer.is_empty() {
+                            *input = serde_json::from_str(&buffer)?;
+                        }
+                    }
+                }
+                "message_delta" => {
+                    if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                        self.response.stop_reason = Some(reason.to_string());
+                    }
+                    if let Some(output_tokens) = event["usage"]["output_tokens"].as_u64() {
+                        self.response.usage.output_tokens = output_tokens as u32;
+                    }
+                }
+                "error" => bail!("Anthropic stream error: {}", event["error"]),
+                _ => {}
+            }
+            Ok(None)
+        }
+
+        pub fn finish(self) -> AnthropicResponse {
+            self.response
+        }
+    }
+
+    fn api_key() -> Result<String> {
+        dotenv().ok();
+        env::var("ANTHROPIC_API_KEY").context("Failed to extract ANTHROPIC_API_KEY")
+    }
+
+    async fn send(payload: &AnthropicPayload) -> Result<reqwest::Response> {
+        let response = Client::new()
+            .post("https://api.anthropic.com/v1/messages")
+            .header(header::CONTENT_TYPE, "application/json; charset=utf-8")
+            .header("x-api-key", api_key()?)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(payload)
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::OK {
+            return Err(UpstreamError::new("anthropic", response.status()).into());
+        }
+        Ok(response)
+    }
+
+    /// Sends a payload and returns the complete response, including usage.
+    pub async fn anthropic_request(payload: &AnthropicPayload) -> Result<AnthropicResponse> {
+        Ok(send(payload).await?.json().await?)
+    }
+
+    /// Streams a payload, handing each text delta to `on_text` as it arrives,
+    /// and returns the reassembled response once the stream ends.
+    pub async fn anthropic_stream(
+        mut payload: AnthropicPayload,
+        mut on_text: impl FnMut(&str),
+    ) -> Result<AnthropicResponse> {
+        payload.stream = true;
+        let mut body = send(&payload).await?.bytes_stream();
+        let mut parser = SseParser::new();
+        let mut state = AnthropicStreamState::default();
+
+        while let Some(chunk) = body.next().await {
+            for event in parser.push(chunk?) {
+                let event: Value = serde_json::from_str(&event.data)?;
+                if let Some(text) = state.apply(&event)? {
+                    on_text(&text);
+                }
+            }
+        }
+
+        Ok(state.finish())
+    }
+
+    /// Sends a multi-turn conversation and returns the response, which carries
+    /// the token usage Anthropic billed.
+    pub async fn anthropic_chat_response(messages: &[ChatMessage]) -> Result<AnthropicResponse> {
+        let payload = AnthropicPayload::from_chat(ANTHROPIC_MODEL.to_string(), messages);
+        let response = anthropic_request(&payload).await?;
+        if response.content.is_empty() {
+            bail!("No content found in the response.")
+        }
+        Ok(response)
+    }
+
+    /// Sends a multi-turn conversation and returns the reply text.
+    pub async fn anthropic_chat(messages: &[ChatMessage]) -> Result<String, Error> {
+        Ok(anthropic_chat_response(messages).await?.text())
+    }
+
+    pub async fn anthropic(query: String) -> Result<String, Error> {
+        anthropic_chat(&[
+            ChatMessage::system("You are a helpful assistant."),
+            ChatMessage::user(query),
+        ])
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::anthropic::*;
+    use crate::chat_completions::tools::builtin::builtin::builtin_tools;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use serde_json::json;
+
+    #[test]
+    fn test_payload_lifts_system_prompt() {
+        let payload = AnthropicPayload::from_chat(
+            ANTHROPIC_MODEL.to_string(),
+            &[
+                ChatMessage::system("Be brief."),
+                ChatMessage::user("Hi"),
+                ChatMessage::assistant("Hello!"),
+                ChatMessage::user("How are you?"),
+            ],
+        )
+        .with_tools(AnthropicTool::from_registry(&builtin_tools()));
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["system"], "Be brief.");
+        assert_eq!(json["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(json["messages"][0]["content"][0]["type"], "text");
+        assert!(json["tools"][0]["input_schema"].is_object());
+    }
+
+    #[test]
+    fn test_stream_state_reassembles_text_and_tool_use() {
+        let events = [
+            json!({"type": "message_start", "message": {"id": "msg_1", "content": [], "stop_reason": null, "usage": {"input_tokens": 12, "output_tokens": 1}}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Let me "}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "check."}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "tu_1", "name": "calculate", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"expression\": "}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "\"2+2\"}"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 30}}),
+            json!({"type": "message_stop"}),
+        ];
+
+        let mut state = AnthropicStreamState::default();
+        let mut streamed = String::new();
+        for event in &events {
+            if let Some(text) = state.apply(event).unwrap() {
+                streamed.push_str(&text);
+            }
+        }
+        let response = state.finish();
+
+        assert_eq!(streamed, "Let me check.");
+        assert_eq!(response.text(), "Let me check.");
+        assert_eq!(response.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(
+            response.usage,
+            AnthropicUsage {
+                input_tokens: 12,
+                output_tokens: 30
+            }
+        );
+        assert_eq!(
+            response.tool_uses(),
+            vec![(
+                "tu_1".to_string(),
+                "calculate".to_string(),
+                json!({"expression": "2+2"})
+            )]
+        );
+    }
+}
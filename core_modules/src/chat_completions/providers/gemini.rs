@@ -1,92 +1,402 @@
 pub mod gemini {
-    use anyhow::{bail, Error, Result};
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use crate::chat_completions::utils::messages::messages::{split_system, ChatMessage, Role};
+    use crate::chat_completions::utils::metrics::metrics::metrics;
+    use anyhow::{Context, Error, Result};
     use dotenv::dotenv;
     use reqwest::{header, Client, StatusCode};
     use serde::{Deserialize, Serialize};
-    use std::{env, thread, time};
+    use std::{env, fmt};
+    use tokio::time::{sleep, Duration};
 
-    #[derive(Serialize)]
-    struct GeminiRequest {
+    pub const GEMINI_MODEL: &str = "gemini-pro";
+    /// Header carrying the API key, which would otherwise sit in the request URL.
+    pub const GEMINI_KEY_HEADER: &str = "x-goog-api-key";
+
+    /// How many times an empty (but not blocked) reply is retried before giving up.
+    const MAX_EMPTY_RETRIES: usize = 3;
+
+    #[derive(Serialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GeminiRequest {
         contents: Vec<GeminiContent>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        system_instruction: Option<GeminiContent>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        generation_config: Option<GeminiGenerationConfig>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        safety_settings: Vec<GeminiSafetySetting>,
+    }
+
+    impl GeminiRequest {
+        /// Builds a request from provider-agnostic messages. System turns become the
+        /// `systemInstruction` and assistant turns use Gemini's `model` role.
+        pub fn from_chat(messages: &[ChatMessage]) -> Self {
+            let (system, turns) = split_system(messages);
+            GeminiRequest {
+                contents: turns
+                    .into_iter()
+                    .map(|message| GeminiContent {
+                        role: Some(gemini_role(message.role).to_string()),
+                        parts: vec![GeminiPart::text(message.content)],
+                    })
+                    .collect(),
+                system_instruction: system.map(|text| GeminiContent {
+                    role: None,
+                    parts: vec![GeminiPart::text(text)],
+                }),
+                ..GeminiRequest::default()
+            }
+        }
+
+        pub fn with_generation_config(mut self, config: GeminiGenerationConfig) -> Self {
+            self.generation_config = Some(config);
+            self
+        }
+
+        pub fn with_safety_settings(mut self, settings: Vec<GeminiSafetySetting>) -> Self {
+            self.safety_settings = settings;
+            self
+        }
     }
 
-    #[derive(Serialize)]
-    struct GeminiContent {
+    fn gemini_role(role: Role) -> &'static str {
+        match role {
+            Role::Assistant => "model",
+            _ => "user",
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct GeminiContent {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        role: Option<String>,
+        #[serde(default)]
         parts: Vec<GeminiPart>,
     }
 
-    #[derive(Serialize)]
-    struct GeminiPart {
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct GeminiPart {
+        #[serde(default)]
         text: String,
     }
 
-    #[derive(Deserialize)]
-    struct GeminiResponse {
+    impl GeminiPart {
+        pub fn text(text: String) -> Self {
+            GeminiPart { text }
+        }
+    }
+
+    #[derive(Serialize, Debug, Clone, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GeminiGenerationConfig {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub temperature: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub max_output_tokens: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub top_p: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub top_k: Option<u32>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub stop_sequences: Vec<String>,
+    }
+
+    impl GeminiGenerationConfig {
+        /// Read from `GEMINI_TEMPERATURE`, `GEMINI_MAX_OUTPUT_TOKENS`, `GEMINI_TOP_P`
+        /// and `GEMINI_TOP_K`. `None` when none of them is set.
+        pub fn from_env() -> Option<Self> {
+            dotenv().ok();
+            fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+                env::var(name)
+                    .ok()
+                    .and_then(|value| value.trim().parse().ok())
+            }
+            let config = GeminiGenerationConfig {
+                temperature: var("GEMINI_TEMPERATURE"),
+                max_output_tokens: var("GEMINI_MAX_OUTPUT_TOKENS"),
+                top_p: var("GEMINI_TOP_P"),
+                top_k: var("GEMINI_TOP_K"),
+                stop_sequences: Vec::new(),
+            };
+            let unset = config.temperature.is_none()
+                && config.max_output_tokens.is_none()
+                && config.top_p.is_none()
+                && config.top_k.is_none();
+            (!unset).then_some(config)
+        }
+    }
+
+    /// The harm categories `GEMINI_SAFETY_THRESHOLD` applies to.
+    const HARM_CATEGORIES: [&str; 4] = [
+        "HARM_CATEGORY_HARASSMENT",
+        "HARM_CATEGORY_HATE_SPEECH",
+        "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+        "HARM_CATEGORY_DANGEROUS_CONTENT",
+    ];
+
+    #[derive(Serialize, Debug, Clone)]
+    pub struct GeminiSafetySetting {
+        category: String,
+        threshold: String,
+    }
+
+    impl GeminiSafetySetting {
+        /// e.g. `("HARM_CATEGORY_HARASSMENT", "BLOCK_ONLY_HIGH")`
+        pub fn new(category: &str, threshold: &str) -> Self {
+            GeminiSafetySetting {
+                category: category.to_string(),
+                threshold: threshold.to_string(),
+            }
+        }
+
+        /// One setting per harm category at the threshold in `GEMINI_SAFETY_THRESHOLD`
+        /// (e.g. `BLOCK_ONLY_HIGH`), or none to keep Gemini's defaults.
+        pub fn from_env() -> Vec<Self> {
+            dotenv().ok();
+            match env::var("GEMINI_SAFETY_THRESHOLD") {
+                Ok(threshold) if !threshold.trim().is_empty() => HARM_CATEGORIES
+                    .iter()
+                    .map(|category| GeminiSafetySetting::new(category, threshold.trim()))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    #[derive(Deserialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GeminiResponse {
+        #[serde(default)]
         candidates: Vec<GeminiCandidate>,
+        prompt_feedback: Option<GeminiPromptFeedback>,
+        #[serde(default)]
+        usage_metadata: GeminiUsage,
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Debug)]
+    #[serde(rename_all = "camelCase")]
     struct GeminiCandidate {
-        content: GeminiCandidateContent,
+        content: Option<GeminiContent>,
+        finish_reason: Option<String>,
     }
 
-    #[derive(Deserialize)]
-    struct GeminiCandidateContent {
-        parts: Vec<GeminiCandidatePart>,
+    #[derive(Deserialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    struct GeminiPromptFeedback {
+        block_reason: Option<String>,
     }
 
-    #[derive(Deserialize)]
-    struct GeminiCandidatePart {
-        text: String,
+    #[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GeminiUsage {
+        #[serde(default)]
+        pub prompt_token_count: u32,
+        #[serde(default)]
+        pub candidates_token_count: u32,
     }
 
-    pub async fn gemini(query: String) -> Result<String, Error> {
+    /// A successful Gemini reply.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct GeminiReply {
+        pub text: String,
+        pub finish_reason: Option<String>,
+        pub usage: GeminiUsage,
+    }
+
+    /// Why Gemini returned no usable text.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum GeminiError {
+        /// The prompt itself was rejected (`promptFeedback.blockReason`).
+        PromptBlocked(String),
+        /// Generation stopped for a non-recoverable reason such as `SAFETY` or `RECITATION`.
+        ResponseBlocked(String),
+        /// The reply had no candidates or only empty parts.
+        EmptyResponse,
+    }
+
+    impl fmt::Display for GeminiError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                GeminiError::PromptBlocked(reason) => {
+                    write!(f, "Gemini blocked the prompt: {reason}")
+                }
+                GeminiError::ResponseBlocked(reason) => {
+                    write!(f, "Gemini stopped generating: {reason}")
+                }
+                GeminiError::EmptyResponse => write!(f, "Gemini returned an empty response"),
+            }
+        }
+    }
+
+    impl std::error::Error for GeminiError {}
+
+    impl GeminiResponse {
+        /// Turns the raw response into the reply text, joining every part of the
+        /// first candidate, or the reason no text was produced.
+        pub fn into_reply(self) -> Result<GeminiReply, GeminiError> {
+            if let Some(reason) = self.prompt_feedback.and_then(|f| f.block_reason) {
+                return Err(GeminiError::PromptBlocked(reason));
+            }
+            let candidate = self
+                .candidates
+                .into_iter()
+                .next()
+                .ok_or(GeminiError::EmptyResponse)?;
+            let text: String = candidate
+                .content
+                .map(|content| content.parts.into_iter().map(|part| part.text).collect())
+                .unwrap_or_default();
+
+            match candidate.finish_reason.as_deref() {
+                None | Some("STOP") | Some("MAX_TOKENS") | Some("FINISH_REASON_UNSPECIFIED") => {}
+                Some(reason) => return Err(GeminiError::ResponseBlocked(reason.to_string())),
+            }
+            if text.trim().is_empty() || text == "null" {
+                return Err(GeminiError::EmptyResponse);
+            }
+
+            Ok(GeminiReply {
+                text,
+                finish_reason: candidate.finish_reason,
+                usage: self.usage_metadata,
+            })
+        }
+    }
+
+    /// Sends a request, retrying a few times on empty replies. Blocked prompts and
+    /// responses are returned as a `GeminiError` straight away.
+    pub async fn gemini_request(request: &GeminiRequest) -> Result<GeminiReply, Error> {
         dotenv().ok();
-        let api_key = env::var("GEMINI_API_KEY").expect("Failed to extract GEMINI_API_KEY");
-        let url = format!("https://generativelanguage.googleapis.com/v1beta/models/gemini-pro:generateContent?key={}", api_key);
+        let api_key = env::var("GEMINI_API_KEY").context("GEMINI_API_KEY is not set")?;
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{GEMINI_MODEL}:generateContent"
+        );
 
         let client = Client::new();
-        let mut valid_response = false;
-        let delay = time::Duration::from_secs(1);
-
-        while !valid_response {
-            // Build the JSON request payload
-            let payload = GeminiRequest {
-                contents: vec![GeminiContent {
-                    parts: vec![GeminiPart {
-                        text: query.clone(),
-                    }],
-                }],
-            };
+        let delay = Duration::from_secs(1);
 
-            // Send the POST request
+        for _ in 0..MAX_EMPTY_RETRIES {
+            // The key goes in a header so it never appears in a logged URL
             let response = client
                 .post(&url)
                 .header(header::CONTENT_TYPE, "application/json")
-                .json(&payload)
+                .header(GEMINI_KEY_HEADER, &api_key)
+                .json(request)
                 .send()
                 .await?;
 
             if response.status() != StatusCode::OK {
-                bail!("Failed to fetch response: {}", response.status());
+                return Err(UpstreamError::new("gemini", response.status()).into());
             }
 
             // Parse the JSON response
             let result: GeminiResponse = response.json().await?;
 
-            if let Some(candidate) = result.candidates.first() {
-                if let Some(part) = candidate.content.parts.first() {
-                    if !part.text.is_empty() && part.text != "null" {
-                        valid_response = true;
-                        return Ok(part.text.clone());
-                    }
+            match result.into_reply() {
+                Ok(reply) => return Ok(reply),
+                Err(GeminiError::EmptyResponse) => {
+                    metrics().record_retry("empty_response");
+                    sleep(delay).await
                 }
+                Err(e) => return Err(e.into()),
             }
+        }
+
+        Err(GeminiError::EmptyResponse.into())
+    }
+
+    /// Sends a multi-turn conversation and returns the reply text.
+    pub async fn gemini_chat(messages: &[ChatMessage]) -> Result<String, Error> {
+        Ok(gemini_chat_reply(messages).await?.text)
+    }
 
-            // Wait before retrying
-            thread::sleep(delay);
+    /// Sends a multi-turn conversation and returns the reply, which carries
+    /// the token usage Gemini reported. Generation config and safety settings
+    /// come from the environment.
+    pub async fn gemini_chat_reply(messages: &[ChatMessage]) -> Result<GeminiReply, Error> {
+        let mut request = GeminiRequest::from_chat(messages)
+            .with_safety_settings(GeminiSafetySetting::from_env());
+        if let Some(config) = GeminiGenerationConfig::from_env() {
+            request = request.with_generation_config(config);
         }
+        gemini_request(&request).await
+    }
+
+    pub async fn gemini(query: String) -> Result<String, Error> {
+        gemini_chat(&[ChatMessage::user(query)]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gemini::*;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use serde_json::json;
+
+    fn response(body: serde_json::Value) -> GeminiResponse {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_request_uses_gemini_roles_and_config() {
+        let request = GeminiRequest::from_chat(&[
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("Hi"),
+            ChatMessage::assistant("Hello!"),
+        ])
+        .with_generation_config(GeminiGenerationConfig {
+            temperature: Some(0.2),
+            max_output_tokens: Some(256),
+            ..GeminiGenerationConfig::default()
+        })
+        .with_safety_settings(vec![GeminiSafetySetting::new(
+            "HARM_CATEGORY_HARASSMENT",
+            "BLOCK_ONLY_HIGH",
+        )]);
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["systemInstruction"]["parts"][0]["text"], "Be brief.");
+        assert_eq!(json["contents"][0]["role"], "user");
+        assert_eq!(json["contents"][1]["role"], "model");
+        assert_eq!(json["generationConfig"]["maxOutputTokens"], 256);
+        assert!(json["generationConfig"].get("topK").is_none());
+        assert_eq!(json["safetySettings"][0]["threshold"], "BLOCK_ONLY_HIGH");
+    }
+
+    #[test]
+    fn test_reply_joins_all_parts() {
+        let reply = response(json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": "Hello, " }, { "text": "world." }] },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": { "promptTokenCount": 4, "candidatesTokenCount": 3 }
+        }))
+        .into_reply()
+        .unwrap();
+
+        assert_eq!(reply.text, "Hello, world.");
+        assert_eq!(reply.usage.candidates_token_count, 3);
+    }
+
+    #[test]
+    fn test_blocked_prompts_and_responses_are_typed_errors() {
+        let blocked_prompt = response(json!({ "promptFeedback": { "blockReason": "SAFETY" } }));
+        assert_eq!(
+            blocked_prompt.into_reply().unwrap_err(),
+            GeminiError::PromptBlocked("SAFETY".to_string())
+        );
+
+        let blocked_response = response(json!({
+            "candidates": [{ "finishReason": "RECITATION" }]
+        }));
+        assert_eq!(
+            blocked_response.into_reply().unwrap_err(),
+            GeminiError::ResponseBlocked("RECITATION".to_string())
+        );
 
-        bail!("Failed to retrieve a valid response after retries.")
+        let empty = response(json!({ "candidates": [] }));
+        assert_eq!(empty.into_reply().unwrap_err(), GeminiError::EmptyResponse);
     }
 }
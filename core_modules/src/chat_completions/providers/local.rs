@@ -0,0 +1,338 @@
+pub mod local {
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use crate::chat_completions::utils::sse::sse::{LineBuffer, SseParser};
+    use anyhow::{anyhow, Error, Result};
+    use dotenv::dotenv;
+    use futures_util::StreamExt;
+    use reqwest::{header, Client, StatusCode};
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use std::env;
+
+    pub const DEFAULT_LOCAL_URL: &str = "http://localhost:11434";
+    pub const DEFAULT_LOCAL_MODEL: &str = "llama3.1";
+
+    /// The HTTP dialect spoken by the local server.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum LocalApi {
+        /// Ollama's native `/api/chat` and `/api/tags`.
+        Ollama,
+        /// The OpenAI-compatible `/v1/chat/completions` served by llama.cpp's `llama-server`.
+        OpenAICompatible,
+    }
+
+    /// Where and how to reach the local model, read from `LOCAL_MODEL_URL`,
+    /// `LOCAL_MODEL` and `LOCAL_MODEL_API` (`ollama` or `llamacpp`).
+    #[derive(Clone, Debug)]
+    pub struct LocalConfig {
+        pub base_url: String,
+        pub model: String,
+        pub api: LocalApi,
+    }
+
+    impl LocalConfig {
+        pub fn from_env() -> Self {
+            dotenv().ok();
+            let api = match env::var("LOCAL_MODEL_API").as_deref() {
+                Ok("llamacpp") | Ok("llama.cpp") | Ok("openai") => LocalApi::OpenAICompatible,
+                _ => LocalApi::Ollama,
+            };
+            LocalConfig {
+                base_url: env::var("LOCAL_MODEL_URL")
+                    .unwrap_or_else(|_| DEFAULT_LOCAL_URL.to_string())
+                    .trim_end_matches('/')
+                    .to_string(),
+                model: env::var("LOCAL_MODEL").unwrap_or_else(|_| DEFAULT_LOCAL_MODEL.to_string()),
+                api,
+            }
+        }
+
+        /// A local model counts as configured once either its URL or model name is set.
+        pub fn is_configured() -> bool {
+            dotenv().ok();
+            env::var("LOCAL_MODEL_URL").is_ok() || env::var("LOCAL_MODEL").is_ok()
+        }
+
+        fn chat_url(&self) -> String {
+            match self.api {
+                LocalApi::Ollama => format!("{}/api/chat", self.base_url),
+                LocalApi::OpenAICompatible => format!("{}/v1/chat/completions", self.base_url),
+            }
+        }
+
+        fn models_url(&self) -> String {
+            match self.api {
+                LocalApi::Ollama => format!("{}/api/tags", self.base_url),
+                LocalApi::OpenAICompatible => format!("{}/v1/models", self.base_url),
+            }
+        }
+    }
+
+    #[derive(Serialize, Debug)]
+    pub struct LocalPayload<'a> {
+        model: &'a str,
+        messages: &'a [ChatMessage],
+        stream: bool,
+        /// Ollama's structured output: `"json"` or a JSON schema.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        format: Option<Value>,
+        /// llama.cpp's structured output.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        response_format: Option<Value>,
+    }
+
+    impl<'a> LocalPayload<'a> {
+        pub fn new(config: &'a LocalConfig, messages: &'a [ChatMessage], stream: bool) -> Self {
+            LocalPayload {
+                model: &config.model,
+                messages,
+                stream,
+                format: None,
+                response_format: None,
+            }
+        }
+
+        /// Constrains the reply to a JSON object matching `schema`.
+        pub fn with_schema(mut self, api: LocalApi, schema: Value) -> Self {
+            match api {
+                LocalApi::Ollama => self.format = Some(schema),
+                LocalApi::OpenAICompatible => {
+                    self.response_format = Some(json!({ "type": "json_object", "schema": schema }))
+                }
+            }
+            self
+        }
+    }
+
+    /// Pulls the reply text out of a complete (non-streamed) response body.
+    pub fn parse_reply(api: LocalApi, body: &Value) -> Result<String> {
+        let content = match api {
+            LocalApi::Ollama => body["message"]["content"].as_str(),
+            LocalApi::OpenAICompatible => body["choices"][0]["message"]["content"].as_str(),
+        };
+        content
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("No content found in the response."))
+    }
+
+    /// Pulls the text delta out of one streamed chunk, returning whether the stream is done.
+    pub fn parse_stream_chunk(api: LocalApi, chunk: &Value) -> (Option<String>, bool) {
+        match api {
+            LocalApi::Ollama => (
+                chunk["message"]["content"].as_str().map(str::to_string),
+                chunk["done"].as_bool().unwrap_or(false),
+            ),
+            LocalApi::OpenAICompatible => (
+                chunk["choices"][0]["delta"]["content"]
+                    .as_str()
+                    .map(str::to_string),
+                chunk["choices"][0]["finish_reason"].is_string(),
+            ),
+        }
+    }
+
+    /// Reads model names out of `/api/tags` or `/v1/models`.
+    pub fn parse_models(api: LocalApi, body: &Value) -> Vec<String> {
+        let (list, key) = match api {
+            LocalApi::Ollama => (&body["models"], "name"),
+            LocalApi::OpenAICompatible => (&body["data"], "id"),
+        };
+        list.as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model[key].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    async fn send(config: &LocalConfig, payload: &LocalPayload<'_>) -> Result<reqwest::Response> {
+        let response = Client::new()
+            .post(config.chat_url())
+            .header(header::CONTENT_TYPE, "application/json; charset=utf-8")
+            .json(payload)
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::OK {
+            return Err(UpstreamError::new("local", response.status()).into());
+        }
+        Ok(response)
+    }
+
+    /// Sends a multi-turn conversation to the local server and returns the reply text.
+    pub async fn local_chat(messages: &[ChatMessage]) -> Result<String, Error> {
+        let config = LocalConfig::from_env();
+        let payload = LocalPayload::new(&config, messages, false);
+        let body: Value = send(&config, &payload).await?.json().await?;
+        parse_reply(config.api, &body)
+    }
+
+    pub async fn local(query: String) -> Result<String, Error> {
+        local_chat(&[
+            ChatMessage::system("You are a helpful assistant."),
+            ChatMessage::user(query),
+        ])
+        .await
+    }
+
+    /// Streams a conversation, handing each text delta to `on_text`, and returns the full reply.
+    pub async fn local_stream(
+        messages: &[ChatMessage],
+        mut on_text: impl FnMut(&str),
+    ) -> Result<String, Error> {
+        let config = LocalConfig::from_env();
+        let payload = LocalPayload::new(&config, messages, true);
+        let mut body = send(&config, &payload).await?.bytes_stream();
+        let mut reply = String::new();
+        let mut handle = |data: &str| -> Result<bool> {
+            if data == "[DONE]" {
+                return Ok(true);
+            }
+            let (text, done) = parse_stream_chunk(config.api, &serde_json::from_str(data)?);
+            if let Some(text) = text {
+                on_text(&text);
+                reply.push_str(&text);
+            }
+            Ok(done)
+        };
+
+        // Ollama streams newline-delimited JSON, llama.cpp streams server-sent events.
+        let mut lines = LineBuffer::new();
+        let mut parser = SseParser::new();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            let done = match config.api {
+                LocalApi::Ollama => {
+                    let mut done = false;
+                    for line in lines.push(&chunk) {
+                        if !line.trim().is_empty() {
+                            done |= handle(line.trim())?;
+                        }
+                    }
+                    done
+                }
+                LocalApi::OpenAICompatible => {
+                    let mut done = false;
+                    for event in parser.push(&chunk) {
+                        done |= handle(&event.data)?;
+                    }
+                    done
+                }
+            };
+            if done {
+                break;
+            }
+        }
+
+        Ok(reply)
+    }
+
+    /// Asks the local model for a JSON object with the given properties, using the
+    /// server's structured output support. Used for the cheap classifier calls.
+    pub async fn local_json(
+        query: &str,
+        description: &str,
+        properties: &Value,
+        required: &[String],
+        arguments: &Value,
+    ) -> Result<Value> {
+        let config = LocalConfig::from_env();
+        let schema = json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        });
+        let messages = [
+            ChatMessage::system(format!(
+                "{description} Reply only with a JSON object matching this schema: {schema}"
+            )),
+            ChatMessage::user(format!("{query}\n\nInput: {arguments}")),
+        ];
+        let payload = LocalPayload::new(&config, &messages, false).with_schema(config.api, schema);
+        let body: Value = send(&config, &payload).await?.json().await?;
+        Ok(serde_json::from_str(&parse_reply(config.api, &body)?)?)
+    }
+
+    /// Lists the models available on the local server.
+    pub async fn list_local_models() -> Result<Vec<String>> {
+        let config = LocalConfig::from_env();
+        let response = Client::new().get(config.models_url()).send().await?;
+        if response.status() != StatusCode::OK {
+            return Err(UpstreamError::new("local", response.status()).into());
+        }
+        Ok(parse_models(config.api, &response.json().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::local::*;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use serde_json::json;
+
+    fn config(api: LocalApi) -> LocalConfig {
+        LocalConfig {
+            base_url: DEFAULT_LOCAL_URL.to_string(),
+            model: "qwen2.5".to_string(),
+            api,
+        }
+    }
+
+    #[test]
+    fn test_payload_requests_structured_output() {
+        let messages = [ChatMessage::user("Is the sky blue?")];
+        let schema = json!({ "type": "object" });
+
+        let ollama = config(LocalApi::Ollama);
+        let payload = serde_json::to_value(
+            LocalPayload::new(&ollama, &messages, false).with_schema(ollama.api, schema.clone()),
+        )
+        .unwrap();
+        assert_eq!(payload["model"], "qwen2.5");
+        assert_eq!(payload["format"], schema);
+        assert!(payload.get("response_format").is_none());
+
+        let llamacpp = config(LocalApi::OpenAICompatible);
+        let payload = serde_json::to_value(
+            LocalPayload::new(&llamacpp, &messages, false).with_schema(llamacpp.api, schema),
+        )
+        .unwrap();
+        assert_eq!(payload["response_format"]["type"], "json_object");
+    }
+
+    #[test]
+    fn test_parse_replies_and_stream_chunks() {
+        let ollama = json!({ "message": { "role": "assistant", "content": "Yes." }, "done": true });
+        assert_eq!(parse_reply(LocalApi::Ollama, &ollama).unwrap(), "Yes.");
+        assert_eq!(
+            parse_stream_chunk(LocalApi::Ollama, &ollama),
+            (Some("Yes.".to_string()), true)
+        );
+
+        let llamacpp =
+            json!({ "choices": [{ "delta": { "content": "Ye" }, "finish_reason": null }] });
+        assert_eq!(
+            parse_stream_chunk(LocalApi::OpenAICompatible, &llamacpp),
+            (Some("Ye".to_string()), false)
+        );
+        assert!(parse_reply(LocalApi::OpenAICompatible, &json!({ "choices": [] })).is_err());
+    }
+
+    #[test]
+    fn test_parse_models() {
+        let tags = json!({ "models": [{ "name": "llama3.1:latest" }, { "name": "qwen2.5:7b" }] });
+        assert_eq!(
+            parse_models(LocalApi::Ollama, &tags),
+            vec!["llama3.1:latest", "qwen2.5:7b"]
+        );
+
+        let models = json!({ "object": "list", "data": [{ "id": "gguf-model" }] });
+        assert_eq!(
+            parse_models(LocalApi::OpenAICompatible, &models),
+            vec!["gguf-model"]
+        );
+    }
+}
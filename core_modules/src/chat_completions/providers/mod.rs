@@ -1,3 +1,6 @@
+pub mod anthropic;
 pub mod gemini;
+pub mod local;
 pub mod openai;
 pub mod perplexity;
+pub mod router;
@@ -1,10 +1,14 @@
 pub mod openai {
-    use anyhow::{bail, Error, Result};
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use anyhow::{bail, Context, Error, Result};
     use dotenv::dotenv;
     use reqwest::{header, Client, StatusCode};
     use serde::{Deserialize, Serialize};
     use std::env;
 
+    pub const OPENAI_MODEL: &str = "gpt-4o";
+
     #[derive(Serialize)]
     pub struct OpenAIPayloadMessage {
         role: String,
@@ -54,11 +58,30 @@ pub mod openai {
         choices: OpenAIChatCompletionResponseChoices,
     }
 
+    impl From<&ChatMessage> for OpenAIPayloadMessage {
+        fn from(message: &ChatMessage) -> Self {
+            OpenAIPayloadMessage::new(message.role.as_str().to_string(), message.content.clone())
+        }
+    }
+
     pub async fn openai(query: String) -> Result<String, Error> {
+        let payload = OpenAIPayload::new(OPENAI_MODEL.to_string(), query);
+        send(payload).await
+    }
+
+    /// Sends a multi-turn conversation and returns the reply text.
+    pub async fn openai_chat(messages: &[ChatMessage]) -> Result<String, Error> {
+        let payload = OpenAIPayload {
+            model: OPENAI_MODEL.to_string(),
+            messages: messages.iter().map(OpenAIPayloadMessage::from).collect(),
+        };
+        send(payload).await
+    }
+
+    async fn send(payload: OpenAIPayload) -> Result<String, Error> {
         dotenv().ok();
-        let openai_api_key = env::var("OPENAI_API_KEY").expect("Failed to extract OPENAI_API_KEY");
+        let openai_api_key = env::var("OPENAI_API_KEY").context("OPENAI_API_KEY is not set")?;
         let client = Client::new();
-        let payload = OpenAIPayload::new("gpt-4o".to_string(), query);
 
         let response = client
             .post("https://api.openai.com/v1/chat/completions")
@@ -69,7 +92,7 @@ pub mod openai {
             .await?;
 
         if response.status() != StatusCode::OK {
-            bail!("Failed to fetch response: {}", response.status());
+            return Err(UpstreamError::new("openai", response.status()).into());
         }
 
         let completion: OpenAIChatCompletionResponse = response.json().await?;
@@ -84,7 +107,8 @@ pub mod openai {
 }
 
 pub mod openai_json {
-    use anyhow::{bail, Error, Result};
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use anyhow::{bail, Context, Error, Result};
     use dotenv::dotenv;
     use reqwest::{header, Client, StatusCode};
     use serde::{Deserialize, Serialize};
@@ -229,6 +253,7 @@ pub mod openai_json {
         }
     }
 
+    #[allow(dead_code)]
     #[derive(Deserialize, Debug)]
     pub struct OpenAIChatCompletionResponseChoiceMessageToolCall {
         id: String,
@@ -236,6 +261,7 @@ pub mod openai_json {
         function: FunctionCallDetails,
     }
 
+    #[allow(dead_code)]
     #[derive(Deserialize, Debug)]
     pub struct FunctionCallDetails {
         name: String,
@@ -270,7 +296,7 @@ pub mod openai_json {
         function_call_arguments: Value,
     ) -> Result<Value, Error> {
         dotenv().ok();
-        let openai_api_key = env::var("OPENAI_API_KEY").expect("Failed to extract OPENAI_API_KEY");
+        let openai_api_key = env::var("OPENAI_API_KEY").context("OPENAI_API_KEY is not set")?;
         let client = Client::new();
 
         let payload = OpenAIFunctionPayload::new(
@@ -292,7 +318,7 @@ pub mod openai_json {
             .await?;
 
         if response.status() != StatusCode::OK {
-            bail!("Failed to fetch response: {}", response.status());
+            return Err(UpstreamError::new("openai", response.status()).into());
         }
 
         let completion: OpenAIChatCompletionResponse = response.json().await?;
@@ -317,6 +343,154 @@ pub mod openai_json {
     }
 }
 
+pub mod openai_tools {
+    use crate::chat_completions::providers::openai::openai::OPENAI_MODEL;
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use anyhow::{bail, Context, Error, Result};
+    use dotenv::dotenv;
+    use reqwest::{header, Client, StatusCode};
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::env;
+
+    /// A chat message that can carry tool calls (assistant) or tool results (tool).
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct OpenAIToolMessage {
+        pub role: String,
+        pub content: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub tool_calls: Option<Vec<OpenAIToolCall>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub tool_call_id: Option<String>,
+    }
+
+    impl OpenAIToolMessage {
+        pub fn new(role: String, content: String) -> Self {
+            OpenAIToolMessage {
+                role,
+                content: Some(content),
+                tool_calls: None,
+                tool_call_id: None,
+            }
+        }
+
+        /// Builds the `tool` role message that answers the call with the given id.
+        pub fn tool_result(tool_call_id: String, content: String) -> Self {
+            OpenAIToolMessage {
+                role: "tool".to_string(),
+                content: Some(content),
+                tool_calls: None,
+                tool_call_id: Some(tool_call_id),
+            }
+        }
+
+        /// The tool calls requested by the model, if any.
+        pub fn requested_tool_calls(&self) -> &[OpenAIToolCall] {
+            self.tool_calls.as_deref().unwrap_or_default()
+        }
+    }
+
+    impl From<&ChatMessage> for OpenAIToolMessage {
+        fn from(message: &ChatMessage) -> Self {
+            OpenAIToolMessage::new(message.role.as_str().to_string(), message.content.clone())
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct OpenAIToolCall {
+        pub id: String,
+        #[serde(rename = "type")]
+        pub call_type: String,
+        pub function: OpenAIToolCallFunction,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct OpenAIToolCallFunction {
+        pub name: String,
+        pub arguments: String,
+    }
+
+    #[derive(Serialize, Clone, Debug)]
+    pub struct OpenAIToolDefinitionFunction {
+        name: String,
+        description: String,
+        parameters: Value,
+    }
+
+    #[derive(Serialize, Clone, Debug)]
+    pub struct OpenAIToolDefinition {
+        r#type: String,
+        function: OpenAIToolDefinitionFunction,
+    }
+
+    impl OpenAIToolDefinition {
+        pub fn new(name: String, description: String, parameters: Value) -> Self {
+            OpenAIToolDefinition {
+                r#type: "function".to_string(),
+                function: OpenAIToolDefinitionFunction {
+                    name,
+                    description,
+                    parameters,
+                },
+            }
+        }
+    }
+
+    #[derive(Serialize, Debug)]
+    pub struct OpenAIToolPayload<'a> {
+        model: String,
+        messages: &'a [OpenAIToolMessage],
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        tools: &'a [OpenAIToolDefinition],
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct OpenAIToolResponseChoice {
+        message: OpenAIToolMessage,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct OpenAIToolResponse {
+        choices: Vec<OpenAIToolResponseChoice>,
+    }
+
+    /// Sends a full conversation along with the available tools and returns the
+    /// assistant's message, which either holds a final answer or requested tool calls.
+    pub async fn chat_with_tools(
+        messages: &[OpenAIToolMessage],
+        tools: &[OpenAIToolDefinition],
+    ) -> Result<OpenAIToolMessage, Error> {
+        dotenv().ok();
+        let openai_api_key = env::var("OPENAI_API_KEY").context("OPENAI_API_KEY is not set")?;
+        let client = Client::new();
+        let payload = OpenAIToolPayload {
+            model: OPENAI_MODEL.to_string(),
+            messages,
+            tools,
+        };
+
+        let response = client
+            .post("https://api.openai.com/v1/chat/completions")
+            .header(header::CONTENT_TYPE, "application/json; charset=utf-8")
+            .header(header::AUTHORIZATION, format!("Bearer {}", openai_api_key))
+            .json(&payload)
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::OK {
+            return Err(UpstreamError::new("openai", response.status()).into());
+        }
+
+        let completion: OpenAIToolResponse = response.json().await?;
+
+        match completion.choices.into_iter().next() {
+            Some(choice) => Ok(choice.message),
+            None => bail!("No choices found in the response."),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::openai_json::*;
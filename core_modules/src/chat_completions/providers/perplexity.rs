@@ -1,10 +1,14 @@
 pub mod perplexity {
-    use anyhow::{bail, Error, Result};
+    use crate::chat_completions::utils::errors::errors::UpstreamError;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use anyhow::{bail, Context, Error, Result};
     use dotenv::dotenv;
     use reqwest::{header, Client, StatusCode};
     use serde::{Deserialize, Serialize};
     use std::env;
 
+    pub const PERPLEXITY_MODEL: &str = "llama-3.1-sonar-small-128k-online";
+
     #[derive(Serialize)]
     pub struct PerplexityPayloadMessage {
         role: String,
@@ -81,13 +85,35 @@ pub mod perplexity {
         choices: PerplexityResponseChoices,
     }
 
+    impl From<&ChatMessage> for PerplexityPayloadMessage {
+        fn from(message: &ChatMessage) -> Self {
+            PerplexityPayloadMessage::new(
+                message.role.as_str().to_string(),
+                message.content.clone(),
+            )
+        }
+    }
+
     pub async fn perplexity(query: String) -> Result<String, Error> {
+        let payload = PerplexityPayload::new(PERPLEXITY_MODEL.to_string(), query);
+        send(payload).await
+    }
+
+    /// Sends a multi-turn conversation and returns the reply text.
+    pub async fn perplexity_chat(messages: &[ChatMessage]) -> Result<String, Error> {
+        let mut payload = PerplexityPayload::new(PERPLEXITY_MODEL.to_string(), String::new());
+        payload.messages = messages
+            .iter()
+            .map(PerplexityPayloadMessage::from)
+            .collect();
+        send(payload).await
+    }
+
+    async fn send(payload: PerplexityPayload) -> Result<String, Error> {
         dotenv().ok();
         let perplexity_api_key =
-            env::var("PERPLEXITY_API_KEY").expect("Failed to extract PERPLEXITY_API_KEY");
+            env::var("PERPLEXITY_API_KEY").context("PERPLEXITY_API_KEY is not set")?;
         let client = Client::new();
-        let payload =
-            PerplexityPayload::new("llama-3.1-sonar-small-128k-online".to_string(), query);
 
         let response = client
             .post("https://api.perplexity.ai/chat/completions")
@@ -101,7 +127,7 @@ pub mod perplexity {
             .await?;
 
         if response.status() != StatusCode::OK {
-            bail!("Failed to fetch response: {}", response.status());
+            return Err(UpstreamError::new("perplexity", response.status()).into());
         }
 
         let completion: PerplexityResponse = response.json().await?;
@@ -0,0 +1,339 @@
+pub mod router {
+    use crate::chat_completions::providers::{
+        anthropic::anthropic::{anthropic_chat_response, ANTHROPIC_MODEL},
+        gemini::gemini::{gemini_chat_reply, GEMINI_MODEL},
+        local::local::{local_chat, LocalConfig},
+        openai::openai::{openai_chat, OPENAI_MODEL},
+        perplexity::perplexity::{perplexity_chat, PERPLEXITY_MODEL},
+    };
+    use crate::chat_completions::utils::context_window::context_window::{
+        count_message_tokens, count_tokens, fit_messages,
+    };
+    use crate::chat_completions::utils::errors::errors::classify;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use crate::chat_completions::utils::metrics::metrics::metrics;
+    use crate::chat_completions::utils::progress::progress::{Progress, ProgressSender};
+    use crate::chat_completions::utils::usage::usage::{add_to_turn, Usage};
+    use anyhow::{anyhow, bail, Error, Result};
+    use dotenv::dotenv;
+    use std::time::Instant;
+    use std::{env, fmt, str::FromStr};
+    use tracing::{debug, field, info_span, warn, Instrument, Span};
+
+    /// Fallback order used when `SATURN_FALLBACK_PROVIDERS` is not set.
+    pub const DEFAULT_FALLBACK_PROVIDERS: &str = "anthropic,gemini";
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum Provider {
+        OpenAI,
+        Anthropic,
+        Gemini,
+        Perplexity,
+        Local,
+    }
+
+    impl Provider {
+        pub const ALL: [Provider; 5] = [
+            Provider::OpenAI,
+            Provider::Anthropic,
+            Provider::Gemini,
+            Provider::Perplexity,
+            Provider::Local,
+        ];
+
+        pub fn name(&self) -> &'static str {
+            match self {
+                Provider::OpenAI => "openai",
+                Provider::Anthropic => "anthropic",
+                Provider::Gemini => "gemini",
+                Provider::Perplexity => "perplexity",
+                Provider::Local => "local",
+            }
+        }
+
+        /// The model this provider sends requests to.
+        pub fn model(&self) -> String {
+            match self {
+                Provider::OpenAI => OPENAI_MODEL.to_string(),
+                Provider::Anthropic => ANTHROPIC_MODEL.to_string(),
+                Provider::Gemini => GEMINI_MODEL.to_string(),
+                Provider::Perplexity => PERPLEXITY_MODEL.to_string(),
+                Provider::Local => LocalConfig::from_env().model,
+            }
+        }
+
+        /// The environment variable holding this provider's credentials, if it needs any.
+        pub fn api_key_var(&self) -> Option<&'static str> {
+            match self {
+                Provider::OpenAI => Some("OPENAI_API_KEY"),
+                Provider::Anthropic => Some("ANTHROPIC_API_KEY"),
+                Provider::Gemini => Some("GEMINI_API_KEY"),
+                Provider::Perplexity => Some("PERPLEXITY_API_KEY"),
+                Provider::Local => None,
+            }
+        }
+
+        /// Whether credentials for this provider are present, so routing can skip
+        /// providers that would fail before sending anything.
+        pub fn is_configured(&self) -> bool {
+            dotenv().ok();
+            match self.api_key_var() {
+                Some(var) => env::var(var).is_ok_and(|key| !key.is_empty()),
+                None => LocalConfig::is_configured(),
+            }
+        }
+    }
+
+    impl fmt::Display for Provider {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.name())
+        }
+    }
+
+    impl FromStr for Provider {
+        type Err = Error;
+
+        fn from_str(name: &str) -> Result<Self> {
+            Provider::ALL
+                .into_iter()
+                .find(|provider| provider.name().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| anyhow!("Unknown provider '{name}'"))
+        }
+    }
+
+    /// Parses a comma separated provider list such as `"anthropic,gemini"`.
+    pub fn parse_providers(list: &str) -> Result<Vec<Provider>> {
+        list.split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(Provider::from_str)
+            .collect()
+    }
+
+    fn provider_from_env(var: &str, default: Provider) -> Provider {
+        dotenv().ok();
+        match env::var(var) {
+            Ok(name) => name.parse().unwrap_or_else(|e| {
+                warn!("{e}; using {default}");
+                default
+            }),
+            Err(_) => default,
+        }
+    }
+
+    /// The model that drafts answers, read from `SATURN_PRIMARY_PROVIDER`.
+    pub fn primary_provider() -> Provider {
+        provider_from_env("SATURN_PRIMARY_PROVIDER", Provider::OpenAI)
+    }
+
+    /// The cheap model behind the `needs_internet` and `is_satisfactory`
+    /// classifiers, read from `SATURN_CLASSIFIER_PROVIDER`.
+    pub fn classifier_provider() -> Provider {
+        provider_from_env("SATURN_CLASSIFIER_PROVIDER", Provider::OpenAI)
+    }
+
+    /// The providers to try, in order, when the primary model fails.
+    /// Read from `SATURN_FALLBACK_PROVIDERS`.
+    pub fn fallback_chain() -> Vec<Provider> {
+        dotenv().ok();
+        let list = env::var("SATURN_FALLBACK_PROVIDERS")
+            .unwrap_or_else(|_| DEFAULT_FALLBACK_PROVIDERS.to_string());
+        parse_providers(&list).unwrap_or_else(|e| {
+            warn!("{e}; using {DEFAULT_FALLBACK_PROVIDERS}");
+            parse_providers(DEFAULT_FALLBACK_PROVIDERS).unwrap()
+        })
+    }
+
+    /// Sends a conversation to a single provider, first fitting it into the
+    /// provider's context window.
+    pub async fn complete(provider: Provider, messages: &[ChatMessage]) -> Result<String> {
+        let messages = fit_messages(provider, messages).await?;
+        dispatch(provider, &messages).await
+    }
+
+    /// Sends a conversation to a single provider exactly as given.
+    pub(crate) async fn dispatch(provider: Provider, messages: &[ChatMessage]) -> Result<String> {
+        let span = provider_span(provider);
+        let started = Instant::now();
+        // Anthropic and Gemini report the tokens they billed; the rest are estimated
+        let result = async {
+            match provider {
+                Provider::OpenAI => openai_chat(messages).await.map(|text| (text, None)),
+                Provider::Anthropic => anthropic_chat_response(messages).await.map(|response| {
+                    let usage = response.usage;
+                    let tokens = (usage.input_tokens as usize, usage.output_tokens as usize);
+                    (response.text(), Some(tokens))
+                }),
+                Provider::Perplexity => perplexity_chat(messages).await.map(|text| (text, None)),
+                Provider::Local => local_chat(messages).await.map(|text| (text, None)),
+                Provider::Gemini => gemini_chat_reply(messages).await.map(|reply| {
+                    let usage = reply.usage;
+                    let tokens = (
+                        usage.prompt_token_count as usize,
+                        usage.candidates_token_count as usize,
+                    );
+                    (reply.text, Some(tokens))
+                }),
+            }
+        }
+        .instrument(span.clone())
+        .await;
+        let response = result.as_ref().ok();
+        span.in_scope(|| {
+            record_call(
+                provider,
+                messages,
+                response.map(|(text, _)| text.as_str()),
+                response.and_then(|(_, reported)| *reported),
+                started,
+            )
+        });
+        result.map(|(text, _)| text)
+    }
+
+    /// The span around one provider call. `record_call` fills in the outcome,
+    /// token counts and latency.
+    pub(crate) fn provider_span(provider: Provider) -> Span {
+        info_span!(
+            "provider_call",
+            %provider,
+            model = %provider.model(),
+            success = field::Empty,
+            prompt_tokens = field::Empty,
+            completion_tokens = field::Empty,
+            latency_ms = field::Empty,
+        )
+    }
+
+    /// Records a provider call's outcome, latency and token usage, on the
+    /// current span, in the metrics and in the usage of the turn being tracked.
+    /// `response` is `None` when the call failed. `reported` holds the (prompt,
+    /// completion) tokens the provider reported; without it they are estimated.
+    pub(crate) fn record_call(
+        provider: Provider,
+        messages: &[ChatMessage],
+        response: Option<&str>,
+        reported: Option<(usize, usize)>,
+        started: Instant,
+    ) {
+        let metrics = metrics();
+        let elapsed = started.elapsed();
+        let span = Span::current();
+        span.record("success", response.is_some());
+        span.record("latency_ms", elapsed.as_millis() as u64);
+        debug!(
+            %provider,
+            success = response.is_some(),
+            latency_ms = elapsed.as_millis() as u64,
+            "Provider call finished"
+        );
+        metrics.record_provider_call(provider.name(), response.is_some(), elapsed);
+        if let Some(response) = response {
+            // A provider that omits usage reports zero tokens
+            let (prompt, completion) = reported
+                .filter(|&(prompt, completion)| prompt + completion > 0)
+                .unwrap_or_else(|| (count_message_tokens(messages), count_tokens(response)));
+            span.record("prompt_tokens", prompt as u64);
+            span.record("completion_tokens", completion as u64);
+            metrics.record_tokens(provider.name(), prompt, completion);
+            add_to_turn(Usage::of_call(provider, prompt, completion));
+        }
+    }
+
+    /// Logs and counts a provider failure that moves a turn on to the next
+    /// provider, and reports it to `progress` without the error's details.
+    pub(crate) fn report_fallback(
+        provider: Provider,
+        error: &Error,
+        progress: Option<&ProgressSender>,
+    ) {
+        warn!(%provider, error = %error, "Provider failed; falling back");
+        metrics().record_retry("fallback");
+        if let Some(progress) = progress {
+            let _ = progress.send(Progress::FallbackTriggered {
+                provider: provider.to_string(),
+                error: classify(error),
+            });
+        }
+    }
+
+    /// Tries each provider in `chain` until one answers, skipping providers
+    /// without credentials. Returns the answering provider with its reply.
+    /// Each provider tried, and each failure, is reported to `progress`.
+    pub async fn complete_with_fallback(
+        chain: &[Provider],
+        messages: &[ChatMessage],
+        progress: Option<&ProgressSender>,
+    ) -> Result<(Provider, String)> {
+        let report = |event: Progress| {
+            if let Some(progress) = progress {
+                let _ = progress.send(event);
+            }
+        };
+        let mut last_error = None;
+        for provider in chain {
+            if !provider.is_configured() {
+                continue;
+            }
+            report(Progress::ProviderSelected {
+                provider: provider.to_string(),
+            });
+            match complete(*provider, messages).await {
+                Ok(response) => return Ok((*provider, response)),
+                Err(e) => {
+                    report_fallback(*provider, &e, progress);
+                    last_error = Some(e);
+                }
+            }
+        }
+        // Keep the last failure so callers can tell a rate limit from an outage
+        match last_error {
+            Some(e) => Err(e.context("No provider in the fallback chain produced a response.")),
+            None => bail!("No provider in the fallback chain is configured."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::router::{complete, complete_with_fallback, parse_providers, Provider};
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+
+    #[test]
+    fn test_parse_providers() {
+        assert_eq!(
+            parse_providers("Anthropic, gemini,").unwrap(),
+            vec![Provider::Anthropic, Provider::Gemini]
+        );
+        assert!(parse_providers("openai,bard").is_err());
+    }
+
+    #[test]
+    fn test_provider_names_round_trip() {
+        for provider in Provider::ALL {
+            assert_eq!(provider.to_string().parse::<Provider>().unwrap(), provider);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_providers_fail_without_panicking() {
+        let messages = [ChatMessage::user("Hello?")];
+        // Only providers without credentials in this environment can be checked
+        let unconfigured: Vec<Provider> = Provider::ALL
+            .into_iter()
+            .filter(|provider| provider.api_key_var().is_some() && !provider.is_configured())
+            .collect();
+        for provider in &unconfigured {
+            let error = complete(*provider, &messages).await.unwrap_err();
+            assert!(
+                error.to_string().contains("is not set"),
+                "{provider}: {error}"
+            );
+        }
+        if !unconfigured.is_empty() {
+            let error = complete_with_fallback(&unconfigured, &messages, None)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("is configured"));
+        }
+    }
+}
@@ -0,0 +1,55 @@
+pub mod builtin {
+    use crate::chat_completions::tools::{
+        calculator::calculator::Calculator,
+        clock::clock::{ConvertTimezone, CurrentDateTime},
+        file_reader::file_reader::{FileReader, READ_ROOT_VAR},
+        registry::registry::ToolRegistry,
+        units::units::ConvertUnits,
+    };
+    use tracing::warn;
+
+    /// The offline tools Saturn can use to answer questions without the web:
+    /// arithmetic, the current date and time, timezone and unit conversion, and,
+    /// only when `SATURN_READ_ROOT` names a directory, read-only access to the
+    /// files below it.
+    pub fn builtin_tools() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Calculator)
+            .register(CurrentDateTime)
+            .register(ConvertTimezone)
+            .register(ConvertUnits);
+        match FileReader::from_env() {
+            Some(Ok(reader)) => {
+                registry.register(reader);
+            }
+            Some(Err(e)) => warn!(error = %e, "{READ_ROOT_VAR} is unusable; read_file is off"),
+            None => {}
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::builtin::builtin_tools;
+    use crate::chat_completions::tools::file_reader::file_reader::READ_ROOT_VAR;
+
+    #[tokio::test]
+    async fn test_builtin_tools_answer_locally() {
+        let registry = builtin_tools();
+        // Files are only readable where a root was chosen explicitly
+        assert_eq!(
+            registry.names().contains(&"read_file".to_string()),
+            std::env::var_os(READ_ROOT_VAR).is_some()
+        );
+
+        let result: serde_json::Value = serde_json::from_str(
+            &registry
+                .execute("calculate", r#"{"expression":"12 * (3 + 4)"}"#)
+                .await,
+        )
+        .unwrap();
+        assert_eq!(result["result"], 84.0);
+    }
+}
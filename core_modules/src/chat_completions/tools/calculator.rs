@@ -0,0 +1,234 @@
+pub mod calculator {
+    use crate::chat_completions::tools::registry::registry::Tool;
+    use anyhow::{anyhow, bail, Result};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    /// Evaluates arithmetic expressions exactly instead of letting the model guess.
+    pub struct Calculator;
+
+    #[async_trait]
+    impl Tool for Calculator {
+        fn name(&self) -> &str {
+            "calculate"
+        }
+
+        fn description(&self) -> &str {
+            "Evaluates an arithmetic expression. Supports + - * / % ^, parentheses, the constants pi and e, and the functions sqrt, abs, ln, log, exp, sin, cos, tan, floor, ceil and round."
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "expression": {
+                        "type": "string",
+                        "description": "The expression to evaluate, e.g. '(3 + 4) * 2 ^ 3'"
+                    }
+                },
+                "required": ["expression"]
+            })
+        }
+
+        async fn call(&self, arguments: Value) -> Result<Value> {
+            let expression = arguments
+                .get("expression")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("Missing 'expression' argument"))?;
+            let result = evaluate(expression)?;
+            Ok(json!({ "expression": expression, "result": result }))
+        }
+    }
+
+    /// Evaluates an arithmetic expression with the usual precedence rules.
+    pub fn evaluate(expression: &str) -> Result<f64> {
+        let mut parser = Parser {
+            chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+            position: 0,
+        };
+        let value = parser.expression()?;
+        if parser.position < parser.chars.len() {
+            bail!(
+                "Unexpected '{}' at position {}",
+                parser.chars[parser.position],
+                parser.position
+            );
+        }
+        if !value.is_finite() {
+            bail!("Expression does not evaluate to a finite number");
+        }
+        Ok(value)
+    }
+
+    struct Parser {
+        chars: Vec<char>,
+        position: usize,
+    }
+
+    impl Parser {
+        fn peek(&self) -> Option<char> {
+            self.chars.get(self.position).copied()
+        }
+
+        fn eat(&mut self, expected: char) -> bool {
+            if self.peek() == Some(expected) {
+                self.position += 1;
+                true
+            } else {
+                false
+            }
+        }
+
+        // expression := term (('+' | '-') term)*
+        fn expression(&mut self) -> Result<f64> {
+            let mut value = self.term()?;
+            loop {
+                if self.eat('+') {
+                    value += self.term()?;
+                } else if self.eat('-') {
+                    value -= self.term()?;
+                } else {
+                    return Ok(value);
+                }
+            }
+        }
+
+        // term := unary (('*' | '/' | '%') unary)*
+        fn term(&mut self) -> Result<f64> {
+            let mut value = self.unary()?;
+            loop {
+                if self.eat('*') {
+                    value *= self.unary()?;
+                } else if self.eat('/') {
+                    let divisor = self.unary()?;
+                    if divisor == 0.0 {
+                        bail!("Division by zero");
+                    }
+                    value /= divisor;
+                } else if self.eat('%') {
+                    let divisor = self.unary()?;
+                    if divisor == 0.0 {
+                        bail!("Division by zero");
+                    }
+                    value %= divisor;
+                } else {
+                    return Ok(value);
+                }
+            }
+        }
+
+        // unary := ('-' | '+') unary | power
+        fn unary(&mut self) -> Result<f64> {
+            if self.eat('-') {
+                Ok(-self.unary()?)
+            } else if self.eat('+') {
+                self.unary()
+            } else {
+                self.power()
+            }
+        }
+
+        // power := primary ('^' unary)?   (right associative)
+        fn power(&mut self) -> Result<f64> {
+            let base = self.primary()?;
+            if self.eat('^') {
+                Ok(base.powf(self.unary()?))
+            } else {
+                Ok(base)
+            }
+        }
+
+        fn primary(&mut self) -> Result<f64> {
+            match self.peek() {
+                Some('(') => {
+                    self.position += 1;
+                    let value = self.expression()?;
+                    if !self.eat(')') {
+                        bail!("Missing closing parenthesis");
+                    }
+                    Ok(value)
+                }
+                Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+                Some(c) if c.is_ascii_alphabetic() => self.identifier(),
+                Some(c) => bail!("Unexpected '{c}' at position {}", self.position),
+                None => bail!("Unexpected end of expression"),
+            }
+        }
+
+        fn number(&mut self) -> Result<f64> {
+            let start = self.position;
+            while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+                self.position += 1;
+            }
+            let literal: String = self.chars[start..self.position].iter().collect();
+            literal
+                .parse()
+                .map_err(|_| anyhow!("Invalid number '{literal}'"))
+        }
+
+        fn identifier(&mut self) -> Result<f64> {
+            let start = self.position;
+            while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric()) {
+                self.position += 1;
+            }
+            let name: String = self.chars[start..self.position].iter().collect();
+            match name.to_ascii_lowercase().as_str() {
+                "pi" => return Ok(std::f64::consts::PI),
+                "e" => return Ok(std::f64::consts::E),
+                _ => {}
+            }
+
+            if !self.eat('(') {
+                bail!("Unknown constant '{name}'");
+            }
+            let argument = self.expression()?;
+            if !self.eat(')') {
+                bail!("Missing closing parenthesis after {name}(");
+            }
+
+            let value = match name.to_ascii_lowercase().as_str() {
+                "sqrt" => argument.sqrt(),
+                "abs" => argument.abs(),
+                "ln" => argument.ln(),
+                "log" => argument.log10(),
+                "exp" => argument.exp(),
+                "sin" => argument.sin(),
+                "cos" => argument.cos(),
+                "tan" => argument.tan(),
+                "floor" => argument.floor(),
+                "ceil" => argument.ceil(),
+                "round" => argument.round(),
+                _ => bail!("Unknown function '{name}'"),
+            };
+            Ok(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::calculator::evaluate;
+
+    #[test]
+    fn test_evaluate_respects_precedence() {
+        assert_eq!(evaluate("2 + 3 * 4").unwrap(), 14.0);
+        assert_eq!(evaluate("(2 + 3) * 4").unwrap(), 20.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(evaluate("7 / 2").unwrap(), 3.5);
+    }
+
+    #[test]
+    fn test_evaluate_functions_and_constants() {
+        assert_eq!(evaluate("sqrt(16) + abs(-2)").unwrap(), 6.0);
+        assert!((evaluate("cos(pi)").unwrap() + 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_evaluate_rejects_invalid_input() {
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("2 +").is_err());
+        assert!(evaluate("foo(1)").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+    }
+}
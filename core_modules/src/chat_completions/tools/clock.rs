@@ -0,0 +1,158 @@
+pub mod clock {
+    use crate::chat_completions::tools::registry::registry::Tool;
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+    use serde_json::{json, Value};
+
+    /// Reports the current date and time, optionally in a given IANA timezone.
+    pub struct CurrentDateTime;
+
+    /// Converts a wall-clock time from one IANA timezone to another.
+    pub struct ConvertTimezone;
+
+    #[async_trait]
+    impl Tool for CurrentDateTime {
+        fn name(&self) -> &str {
+            "current_datetime"
+        }
+
+        fn description(&self) -> &str {
+            "Returns the current date, time and weekday. Uses the local timezone unless an IANA timezone such as 'America/New_York' is given."
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "timezone": {
+                        "type": "string",
+                        "description": "Optional IANA timezone name, e.g. 'Europe/London'"
+                    }
+                },
+                "required": []
+            })
+        }
+
+        async fn call(&self, arguments: Value) -> Result<Value> {
+            let now = Utc::now();
+            match arguments.get("timezone").and_then(|v| v.as_str()) {
+                Some(name) => Ok(describe(&now.with_timezone(&parse_timezone(name)?), name)),
+                None => Ok(describe(&now.with_timezone(&Local), "local")),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Tool for ConvertTimezone {
+        fn name(&self) -> &str {
+            "convert_timezone"
+        }
+
+        fn description(&self) -> &str {
+            "Converts a date and time from one IANA timezone to another."
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "datetime": {
+                        "type": "string",
+                        "description": "The time to convert, formatted 'YYYY-MM-DD HH:MM'"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "IANA timezone the time is expressed in"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "IANA timezone to convert into"
+                    }
+                },
+                "required": ["datetime", "from", "to"]
+            })
+        }
+
+        async fn call(&self, arguments: Value) -> Result<Value> {
+            let argument = |key: &str| {
+                arguments
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("Missing '{key}' argument"))
+            };
+            let (from, to) = (argument("from")?, argument("to")?);
+            let converted = convert(argument("datetime")?, from, to)?;
+            Ok(describe(&converted, to))
+        }
+    }
+
+    /// Converts a `YYYY-MM-DD HH:MM[:SS]` wall-clock time between two timezones.
+    pub fn convert(datetime: &str, from: &str, to: &str) -> Result<DateTime<Tz>> {
+        let naive = [
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%d %H:%M",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%dT%H:%M",
+        ]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(datetime.trim(), format).ok())
+        .ok_or_else(|| anyhow!("Could not parse '{datetime}', expected 'YYYY-MM-DD HH:MM'"))?;
+        let local = parse_timezone(from)?
+            .from_local_datetime(&naive)
+            .earliest()
+            .ok_or_else(|| anyhow!("'{datetime}' does not exist in {from}"))?;
+        Ok(local.with_timezone(&parse_timezone(to)?))
+    }
+
+    fn parse_timezone(name: &str) -> Result<Tz> {
+        name.parse::<Tz>()
+            .map_err(|_| anyhow!("Unknown timezone '{name}'"))
+    }
+
+    fn describe<T: TimeZone>(datetime: &DateTime<T>, timezone: &str) -> Value
+    where
+        T::Offset: std::fmt::Display,
+    {
+        json!({
+            "timezone": timezone,
+            "iso8601": datetime.to_rfc3339(),
+            "date": datetime.format("%Y-%m-%d").to_string(),
+            "time": datetime.format("%H:%M:%S").to_string(),
+            "weekday": datetime.format("%A").to_string(),
+            "utc_offset": datetime.format("%:z").to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clock::{convert, CurrentDateTime};
+    use crate::chat_completions::tools::registry::registry::Tool;
+    use serde_json::json;
+
+    #[test]
+    fn test_convert_between_timezones() {
+        let converted = convert("2024-07-01 12:00", "America/New_York", "Europe/London").unwrap();
+        assert_eq!(
+            converted.format("%Y-%m-%d %H:%M").to_string(),
+            "2024-07-01 17:00"
+        );
+    }
+
+    #[test]
+    fn test_convert_rejects_unknown_timezone() {
+        assert!(convert("2024-07-01 12:00", "Mars/Olympus", "UTC").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_current_datetime_in_timezone() {
+        let result = CurrentDateTime
+            .call(json!({ "timezone": "UTC" }))
+            .await
+            .unwrap();
+        assert_eq!(result["utc_offset"], "+00:00");
+        assert!(result["weekday"].as_str().unwrap().ends_with("day"));
+    }
+}
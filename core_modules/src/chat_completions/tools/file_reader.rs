@@ -0,0 +1,149 @@
+pub mod file_reader {
+    use crate::chat_completions::tools::registry::registry::Tool;
+    use anyhow::{anyhow, bail, Result};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::env;
+    use std::path::{Path, PathBuf};
+
+    /// Largest number of bytes returned to the model from a single file.
+    pub const MAX_FILE_BYTES: usize = 64 * 1024;
+
+    /// The directory `read_file` may read below. Unset, the tool is not offered.
+    pub const READ_ROOT_VAR: &str = "SATURN_READ_ROOT";
+
+    /// Read-only access to files below a fixed root directory.
+    ///
+    /// Paths are resolved against the root and canonicalized, so `..` segments
+    /// and symlinks cannot escape it. Hidden files and directories, such as
+    /// `.env` with its API keys or `.git`, cannot be read.
+    pub struct FileReader {
+        root: PathBuf,
+    }
+
+    impl FileReader {
+        pub fn new(root: impl AsRef<Path>) -> Result<Self> {
+            Ok(FileReader {
+                root: root.as_ref().canonicalize()?,
+            })
+        }
+
+        /// A reader rooted at `SATURN_READ_ROOT`, or `None` when it is not set.
+        pub fn from_env() -> Option<Result<Self>> {
+            env::var_os(READ_ROOT_VAR).map(FileReader::new)
+        }
+
+        /// Resolves `path` inside the root, refusing anything that points outside
+        /// of it or at a hidden file or directory.
+        pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+            let resolved = self
+                .root
+                .join(path)
+                .canonicalize()
+                .map_err(|e| anyhow!("Cannot open '{path}': {e}"))?;
+            let Ok(relative) = resolved.strip_prefix(&self.root) else {
+                bail!("'{path}' is outside of the readable directory");
+            };
+            // Checked after canonicalizing, so a symlink to `.env` is refused too
+            if relative
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+            {
+                bail!("'{path}' is a hidden file or inside a hidden directory");
+            }
+            Ok(resolved)
+        }
+
+        pub async fn read(&self, path: &str) -> Result<Value> {
+            let resolved = self.resolve(path)?;
+            if !resolved.is_file() {
+                bail!("'{path}' is not a file");
+            }
+            let bytes = tokio::fs::read(&resolved).await?;
+            let truncated = bytes.len() > MAX_FILE_BYTES;
+            let content = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_FILE_BYTES)]);
+            Ok(json!({
+                "path": path,
+                "bytes": bytes.len(),
+                "truncated": truncated,
+                "content": content,
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl Tool for FileReader {
+        fn name(&self) -> &str {
+            "read_file"
+        }
+
+        fn description(&self) -> &str {
+            "Reads a text file relative to the readable directory. Files outside of it and hidden files cannot be read."
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path of the file, relative to the readable directory"
+                    }
+                },
+                "required": ["path"]
+            })
+        }
+
+        async fn call(&self, arguments: Value) -> Result<Value> {
+            let path = arguments
+                .get("path")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("Missing 'path' argument"))?;
+            self.read(path).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::file_reader::FileReader;
+
+    #[tokio::test]
+    async fn test_reads_file_inside_root() {
+        let reader = FileReader::new(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let result = reader.read("Cargo.toml").await.unwrap();
+        assert!(result["content"]
+            .as_str()
+            .unwrap()
+            .contains("name = \"core_modules\""));
+        assert_eq!(result["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_paths_outside_root() {
+        let reader = FileReader::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src")).unwrap();
+        assert!(reader.read("../Cargo.toml").await.is_err());
+        assert!(reader.read("/etc/hostname").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refuses_hidden_files() {
+        let root = std::env::temp_dir().join(format!("saturn-reader-{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".env"), "OPENAI_API_KEY=sk-secret").unwrap();
+        std::fs::write(root.join(".git/config"), "[core]").unwrap();
+        std::fs::write(root.join("notes.txt"), "Saturn has 146 moons.").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join(".env"), root.join("keys.txt")).unwrap();
+
+        let reader = FileReader::new(&root).unwrap();
+        assert!(reader.read("notes.txt").await.is_ok());
+        assert!(reader.read(".env").await.is_err());
+        assert!(reader.read("./.env").await.is_err());
+        assert!(reader.read(".git/config").await.is_err());
+        #[cfg(unix)]
+        assert!(reader.read("keys.txt").await.is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,6 @@
+pub mod builtin;
+pub mod calculator;
+pub mod clock;
+pub mod file_reader;
+pub mod registry;
+pub mod units;
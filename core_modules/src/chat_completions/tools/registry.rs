@@ -0,0 +1,153 @@
+pub mod registry {
+    use crate::chat_completions::providers::openai::openai_tools::OpenAIToolDefinition;
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    /// A Rust function the model is allowed to call.
+    ///
+    /// `parameters` must be a JSON schema object describing the arguments that
+    /// `call` expects; it is sent verbatim to the provider.
+    #[async_trait]
+    pub trait Tool: Send + Sync {
+        fn name(&self) -> &str;
+        fn description(&self) -> &str;
+        fn parameters(&self) -> Value;
+        async fn call(&self, arguments: Value) -> Result<Value>;
+    }
+
+    /// The set of tools exposed to the model during an agent run.
+    #[derive(Clone, Default)]
+    pub struct ToolRegistry {
+        tools: BTreeMap<String, Arc<dyn Tool>>,
+    }
+
+    impl ToolRegistry {
+        pub fn new() -> Self {
+            ToolRegistry::default()
+        }
+
+        /// Adds a tool, replacing any previously registered tool with the same name.
+        pub fn register<T: Tool + 'static>(&mut self, tool: T) -> &mut Self {
+            self.tools.insert(tool.name().to_string(), Arc::new(tool));
+            self
+        }
+
+        pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+            self.tools.get(name).cloned()
+        }
+
+        pub fn names(&self) -> Vec<String> {
+            self.tools.keys().cloned().collect()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.tools.is_empty()
+        }
+
+        /// The tool definitions in the shape expected by the chat completions API.
+        pub fn definitions(&self) -> Vec<OpenAIToolDefinition> {
+            self.tools
+                .values()
+                .map(|tool| {
+                    OpenAIToolDefinition::new(
+                        tool.name().to_string(),
+                        tool.description().to_string(),
+                        tool.parameters(),
+                    )
+                })
+                .collect()
+        }
+
+        /// Runs the named tool with the raw JSON argument string produced by the model.
+        pub async fn call(&self, name: &str, arguments: &str) -> Result<Value> {
+            let tool = self
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown tool: {name}"))?;
+            let arguments: Value = if arguments.trim().is_empty() {
+                json!({})
+            } else {
+                serde_json::from_str(arguments)?
+            };
+            tool.call(arguments).await
+        }
+
+        /// Like `call`, but folds failures into a JSON error object so the model
+        /// can see what went wrong and recover instead of aborting the run.
+        pub async fn execute(&self, name: &str, arguments: &str) -> String {
+            match self.call(name, arguments).await {
+                Ok(result) => result.to_string(),
+                Err(e) => json!({ "error": format!("{e}") }).to_string(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::registry::{Tool, ToolRegistry};
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    struct Echo;
+
+    #[async_trait]
+    impl Tool for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Returns its arguments unchanged."
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"]
+            })
+        }
+
+        async fn call(&self, arguments: Value) -> Result<Value> {
+            Ok(arguments)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_calls_registered_tool() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Echo);
+
+        let result = registry
+            .call("echo", r#"{"text":"hello"}"#)
+            .await
+            .expect("echo tool should succeed");
+        assert_eq!(result, json!({ "text": "hello" }));
+    }
+
+    #[tokio::test]
+    async fn test_registry_reports_unknown_tool_as_json_error() {
+        let registry = ToolRegistry::new();
+
+        let result: Value = serde_json::from_str(&registry.execute("missing", "{}").await).unwrap();
+        assert!(result["error"].as_str().unwrap().contains("missing"));
+    }
+
+    #[test]
+    fn test_registry_definitions_include_schema() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Echo);
+
+        let definitions = serde_json::to_value(registry.definitions()).unwrap();
+        assert_eq!(definitions[0]["type"], "function");
+        assert_eq!(definitions[0]["function"]["name"], "echo");
+        assert_eq!(
+            definitions[0]["function"]["parameters"]["required"][0],
+            "text"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+pub mod units {
+    use crate::chat_completions::tools::registry::registry::Tool;
+    use anyhow::{anyhow, bail, Result};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    /// Converts a value between units of the same physical dimension.
+    pub struct ConvertUnits;
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Dimension {
+        Length,
+        Mass,
+        Volume,
+        Time,
+        Speed,
+        Data,
+        Temperature,
+    }
+
+    /// (names, dimension, factor to the dimension's base unit)
+    const UNITS: &[(&[&str], Dimension, f64)] = &[
+        (
+            &["m", "meter", "meters", "metre", "metres"],
+            Dimension::Length,
+            1.0,
+        ),
+        (
+            &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+            Dimension::Length,
+            1000.0,
+        ),
+        (
+            &["cm", "centimeter", "centimeters"],
+            Dimension::Length,
+            0.01,
+        ),
+        (
+            &["mm", "millimeter", "millimeters"],
+            Dimension::Length,
+            0.001,
+        ),
+        (&["in", "inch", "inches"], Dimension::Length, 0.0254),
+        (&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+        (&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+        (&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+        (
+            &["nmi", "nautical mile", "nautical miles"],
+            Dimension::Length,
+            1852.0,
+        ),
+        (&["kg", "kilogram", "kilograms"], Dimension::Mass, 1.0),
+        (&["g", "gram", "grams"], Dimension::Mass, 0.001),
+        (
+            &["mg", "milligram", "milligrams"],
+            Dimension::Mass,
+            0.000001,
+        ),
+        (
+            &["t", "tonne", "tonnes", "metric ton"],
+            Dimension::Mass,
+            1000.0,
+        ),
+        (
+            &["lb", "lbs", "pound", "pounds"],
+            Dimension::Mass,
+            0.45359237,
+        ),
+        (&["oz", "ounce", "ounces"], Dimension::Mass, 0.028349523125),
+        (&["st", "stone", "stones"], Dimension::Mass, 6.35029318),
+        (
+            &["l", "liter", "liters", "litre", "litres"],
+            Dimension::Volume,
+            1.0,
+        ),
+        (
+            &["ml", "milliliter", "milliliters"],
+            Dimension::Volume,
+            0.001,
+        ),
+        (
+            &["m3", "cubic meter", "cubic meters"],
+            Dimension::Volume,
+            1000.0,
+        ),
+        (
+            &["gal", "gallon", "gallons"],
+            Dimension::Volume,
+            3.785411784,
+        ),
+        (&["qt", "quart", "quarts"], Dimension::Volume, 0.946352946),
+        (&["pt", "pint", "pints"], Dimension::Volume, 0.473176473),
+        (&["cup", "cups"], Dimension::Volume, 0.2365882365),
+        (
+            &["floz", "fl oz", "fluid ounce", "fluid ounces"],
+            Dimension::Volume,
+            0.0295735295625,
+        ),
+        (&["s", "sec", "second", "seconds"], Dimension::Time, 1.0),
+        (
+            &["ms", "millisecond", "milliseconds"],
+            Dimension::Time,
+            0.001,
+        ),
+        (&["min", "minute", "minutes"], Dimension::Time, 60.0),
+        (&["h", "hr", "hour", "hours"], Dimension::Time, 3600.0),
+        (&["d", "day", "days"], Dimension::Time, 86400.0),
+        (&["wk", "week", "weeks"], Dimension::Time, 604800.0),
+        (&["m/s", "meters per second"], Dimension::Speed, 1.0),
+        (
+            &["km/h", "kph", "kilometers per hour"],
+            Dimension::Speed,
+            1000.0 / 3600.0,
+        ),
+        (&["mph", "miles per hour"], Dimension::Speed, 0.44704),
+        (&["kn", "knot", "knots"], Dimension::Speed, 1852.0 / 3600.0),
+        (&["b", "byte", "bytes"], Dimension::Data, 1.0),
+        (&["bit", "bits"], Dimension::Data, 0.125),
+        (&["kb", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+        (&["mb", "megabyte", "megabytes"], Dimension::Data, 1e6),
+        (&["gb", "gigabyte", "gigabytes"], Dimension::Data, 1e9),
+        (&["tb", "terabyte", "terabytes"], Dimension::Data, 1e12),
+        (&["kib", "kibibyte", "kibibytes"], Dimension::Data, 1024.0),
+        (
+            &["mib", "mebibyte", "mebibytes"],
+            Dimension::Data,
+            1048576.0,
+        ),
+        (
+            &["gib", "gibibyte", "gibibytes"],
+            Dimension::Data,
+            1073741824.0,
+        ),
+        (&["c", "celsius", "°c"], Dimension::Temperature, 0.0),
+        (&["f", "fahrenheit", "°f"], Dimension::Temperature, 0.0),
+        (&["k", "kelvin"], Dimension::Temperature, 0.0),
+    ];
+
+    #[async_trait]
+    impl Tool for ConvertUnits {
+        fn name(&self) -> &str {
+            "convert_units"
+        }
+
+        fn description(&self) -> &str {
+            "Converts a value between units of length, mass, volume, time, speed, data size or temperature."
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "value": { "type": "number", "description": "The quantity to convert" },
+                    "from": { "type": "string", "description": "Source unit, e.g. 'mi', 'lb', 'celsius'" },
+                    "to": { "type": "string", "description": "Target unit, e.g. 'km', 'kg', 'fahrenheit'" }
+                },
+                "required": ["value", "from", "to"]
+            })
+        }
+
+        async fn call(&self, arguments: Value) -> Result<Value> {
+            let value = arguments
+                .get("value")
+                .and_then(|v| v.as_f64())
+                .ok_or_else(|| anyhow!("Missing numeric 'value' argument"))?;
+            let unit = |key: &str| {
+                arguments
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("Missing '{key}' argument"))
+            };
+            let (from, to) = (unit("from")?, unit("to")?);
+            let result = convert(value, from, to)?;
+            Ok(json!({ "value": value, "from": from, "to": to, "result": result }))
+        }
+    }
+
+    /// Converts `value` from one unit to another.
+    pub fn convert(value: f64, from: &str, to: &str) -> Result<f64> {
+        let (from_dimension, from_factor) = lookup(from)?;
+        let (to_dimension, to_factor) = lookup(to)?;
+        if from_dimension != to_dimension {
+            bail!("Cannot convert {from} ({from_dimension:?}) to {to} ({to_dimension:?})");
+        }
+        if from_dimension == Dimension::Temperature {
+            return Ok(from_kelvin(to_kelvin(value, from), to));
+        }
+        Ok(value * from_factor / to_factor)
+    }
+
+    fn lookup(unit: &str) -> Result<(Dimension, f64)> {
+        let normalized = unit.trim().to_lowercase();
+        UNITS
+            .iter()
+            .find(|(names, _, _)| names.contains(&normalized.as_str()))
+            .map(|(_, dimension, factor)| (*dimension, *factor))
+            .ok_or_else(|| anyhow!("Unknown unit '{unit}'"))
+    }
+
+    fn to_kelvin(value: f64, unit: &str) -> f64 {
+        match unit.trim().to_lowercase().trim_start_matches('°') {
+            "c" | "celsius" => value + 273.15,
+            "f" | "fahrenheit" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+            _ => value,
+        }
+    }
+
+    fn from_kelvin(value: f64, unit: &str) -> f64 {
+        match unit.trim().to_lowercase().trim_start_matches('°') {
+            "c" | "celsius" => value - 273.15,
+            "f" | "fahrenheit" => (value - 273.15) * 9.0 / 5.0 + 32.0,
+            _ => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::units::convert;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_convert_linear_units() {
+        assert_close(convert(1.0, "mile", "km").unwrap(), 1.609344);
+        assert_close(convert(2.0, "lb", "g").unwrap(), 907.18474);
+        assert_close(convert(1.0, "GiB", "MiB").unwrap(), 1024.0);
+    }
+
+    #[test]
+    fn test_convert_temperature() {
+        assert_close(convert(100.0, "celsius", "fahrenheit").unwrap(), 212.0);
+        assert_close(convert(32.0, "°F", "C").unwrap(), 0.0);
+        assert_close(convert(0.0, "K", "celsius").unwrap(), -273.15);
+    }
+
+    #[test]
+    fn test_convert_rejects_mismatched_dimensions() {
+        assert!(convert(1.0, "kg", "km").is_err());
+        assert!(convert(1.0, "furlong", "km").is_err());
+    }
+}
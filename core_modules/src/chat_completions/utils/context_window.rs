@@ -0,0 +1,391 @@
+pub mod context_window {
+    use crate::chat_completions::providers::router::router::{
+        classifier_provider, dispatch, Provider,
+    };
+    use crate::chat_completions::utils::messages::messages::{ChatMessage, Role};
+    use anyhow::{bail, Result};
+    use dotenv::dotenv;
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use std::env;
+    use std::sync::{Mutex, OnceLock};
+    use tiktoken_rs::o200k_base_singleton;
+
+    /// Context size assumed for models missing from `CONTEXT_LIMITS`, including local ones.
+    pub const DEFAULT_CONTEXT_LIMIT: usize = 8192;
+    /// Tokens kept free for the reply.
+    pub const RESPONSE_RESERVE: usize = 4096;
+    /// Tokens each message costs on top of its content (role markers, separators).
+    const MESSAGE_OVERHEAD: usize = 4;
+    const TRUNCATION_MARKER: &str = " …[truncated]";
+    /// Summaries kept by `cached_summaries` before the cache is cleared.
+    const MAX_CACHED_SUMMARIES: usize = 256;
+
+    /// Context windows by model name prefix. More specific prefixes come first.
+    const CONTEXT_LIMITS: &[(&str, usize)] = &[
+        ("gpt-4o", 128_000),
+        ("gpt-4-turbo", 128_000),
+        ("gpt-4", 8_192),
+        ("gpt-3.5-turbo", 16_385),
+        ("claude", 200_000),
+        ("gemini-1.5-pro", 2_097_152),
+        ("gemini-1.5-flash", 1_048_576),
+        ("gemini-pro", 30_720),
+        ("gemini-1.0-pro", 30_720),
+        ("llama-3.1-sonar", 127_072),
+        ("sonar", 127_072),
+    ];
+
+    /// The context window of `model`, in tokens.
+    pub fn context_limit(model: &str) -> usize {
+        CONTEXT_LIMITS
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, limit)| *limit)
+            .unwrap_or(DEFAULT_CONTEXT_LIMIT)
+    }
+
+    /// How many tokens a request to `provider` may use, leaving room for the reply.
+    /// `LOCAL_CONTEXT_LIMIT` overrides the window of local models.
+    pub fn token_budget(provider: Provider) -> usize {
+        dotenv().ok();
+        let limit = match provider {
+            Provider::Local => env::var("LOCAL_CONTEXT_LIMIT")
+                .ok()
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(DEFAULT_CONTEXT_LIMIT),
+            _ => context_limit(&provider.model()),
+        };
+        limit - RESPONSE_RESERVE.min(limit / 4)
+    }
+
+    /// Estimates tokens with the `o200k_base` tokenizer. Exact for OpenAI's
+    /// current models and close enough for the others.
+    pub fn count_tokens(text: &str) -> usize {
+        o200k_base_singleton()
+            .lock()
+            .encode_with_special_tokens(text)
+            .len()
+    }
+
+    pub fn count_message_tokens(messages: &[ChatMessage]) -> usize {
+        messages
+            .iter()
+            .map(|message| count_tokens(&message.content) + MESSAGE_OVERHEAD)
+            .sum::<usize>()
+            + 3 // every reply is primed with an assistant header
+    }
+
+    /// Cuts `text` down to about `max_tokens` tokens, marking the cut.
+    pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+        let tokenizer = o200k_base_singleton();
+        let tokenizer = tokenizer.lock();
+        let tokens = tokenizer.encode_with_special_tokens(text);
+        if tokens.len() <= max_tokens {
+            return text.to_string();
+        }
+        let kept = tokens[..max_tokens].to_vec();
+        // A cut inside a multi-byte character does not decode; back off until it does
+        (0..kept.len())
+            .rev()
+            .find_map(|end| tokenizer.decode(kept[..=end].to_vec()).ok())
+            .map(|prefix| format!("{prefix}{TRUNCATION_MARKER}"))
+            .unwrap_or_else(|| TRUNCATION_MARKER.trim().to_string())
+    }
+
+    /// What to do with turns that no longer fit, read from `SATURN_HISTORY_POLICY`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum HistoryPolicy {
+        /// Drop the oldest turns.
+        SlidingWindow,
+        /// Replace the oldest turns with a model-written summary.
+        Summarize,
+    }
+
+    impl HistoryPolicy {
+        pub fn from_env() -> Self {
+            dotenv().ok();
+            match env::var("SATURN_HISTORY_POLICY").as_deref() {
+                Ok("summarize") => HistoryPolicy::Summarize,
+                _ => HistoryPolicy::SlidingWindow,
+            }
+        }
+    }
+
+    /// Keeps every system message and the final message, then as many of the most
+    /// recent turns as fit in `budget`. Returns the kept messages and the dropped turns.
+    pub fn sliding_window(
+        messages: &[ChatMessage],
+        budget: usize,
+    ) -> (Vec<ChatMessage>, Vec<ChatMessage>) {
+        let last = messages.len().saturating_sub(1);
+        let mut keep: Vec<bool> = messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| message.role == Role::System || i == last)
+            .collect();
+        let kept: Vec<ChatMessage> = messages
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(message, _)| message.clone())
+            .collect();
+        let mut used = count_message_tokens(&kept);
+
+        // Walk back from the newest turn, stopping at the first one that does not fit
+        for i in (0..last).rev() {
+            if keep[i] {
+                continue;
+            }
+            let cost = count_tokens(&messages[i].content) + MESSAGE_OVERHEAD;
+            if used + cost > budget {
+                break;
+            }
+            used += cost;
+            keep[i] = true;
+        }
+
+        // Never open the remaining history with an orphaned assistant reply
+        if let Some(first_turn) = (0..last).find(|i| messages[*i].role != Role::System && keep[*i])
+        {
+            if messages[first_turn].role == Role::Assistant {
+                keep[first_turn] = false;
+            }
+        }
+
+        let (kept, dropped): (Vec<_>, Vec<_>) = messages
+            .iter()
+            .cloned()
+            .zip(keep)
+            .partition(|(_, keep)| *keep);
+        (
+            kept.into_iter().map(|(message, _)| message).collect(),
+            dropped.into_iter().map(|(message, _)| message).collect(),
+        )
+    }
+
+    /// Shrinks the longest messages until the conversation fits in `budget`.
+    /// The last resort once no whole turns are left to drop. Fails when the
+    /// conversation does not fit even with every message cut to the marker.
+    pub fn truncate_to_budget(
+        mut messages: Vec<ChatMessage>,
+        budget: usize,
+    ) -> Result<Vec<ChatMessage>> {
+        loop {
+            let total = count_message_tokens(&messages);
+            if total <= budget {
+                return Ok(messages);
+            }
+            let Some((longest, tokens)) = messages
+                .iter()
+                .enumerate()
+                .map(|(i, message)| (i, count_tokens(&message.content)))
+                .max_by_key(|(_, tokens)| *tokens)
+            else {
+                bail!("Even an empty conversation needs more than {budget} tokens");
+            };
+            // Cut inside the longest message, all of it if that is still not enough
+            let excess = total - budget + count_tokens(TRUNCATION_MARKER);
+            let truncated =
+                truncate_to_tokens(&messages[longest].content, tokens.saturating_sub(excess));
+            if count_tokens(&truncated) >= tokens {
+                bail!(
+                    "The conversation needs {total} tokens but only {budget} are available, \
+                     even with every message truncated"
+                );
+            }
+            messages[longest].content = truncated;
+        }
+    }
+
+    /// Summaries of dropped turns, keyed by `prefix_keys`, so each turn is
+    /// summarized once rather than again on every request.
+    fn cached_summaries() -> &'static Mutex<HashMap<String, String>> {
+        static SUMMARIES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+        SUMMARIES.get_or_init(Default::default)
+    }
+
+    /// A key for every prefix of `turns`: entry `i` identifies `turns[..=i]`.
+    pub(crate) fn prefix_keys(turns: &[ChatMessage]) -> Vec<String> {
+        let mut key = String::new();
+        turns
+            .iter()
+            .map(|turn| {
+                let mut hasher = Sha256::new();
+                hasher.update(key.as_bytes());
+                hasher.update(turn.role.as_str().as_bytes());
+                hasher.update([0]);
+                hasher.update(turn.content.as_bytes());
+                key = format!("{:x}", hasher.finalize());
+                key.clone()
+            })
+            .collect()
+    }
+
+    /// Summarizes `turns` with the classifier provider, folding in `previous`,
+    /// the summary of the turns before them.
+    async fn summarize(previous: Option<&str>, turns: &[ChatMessage]) -> Result<String> {
+        let provider = classifier_provider();
+        let mut transcript = turns
+            .iter()
+            .map(|turn| format!("{}: {}", turn.role.as_str(), turn.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if let Some(previous) = previous {
+            transcript = format!("Summary so far: {previous}\n{transcript}");
+        }
+        let request = vec![
+            ChatMessage::system(
+                "Summarize this conversation in a few sentences. Keep names, facts and \
+                 decisions the user may refer back to.",
+            ),
+            ChatMessage::user(transcript),
+        ];
+        dispatch(
+            provider,
+            &truncate_to_budget(request, token_budget(provider))?,
+        )
+        .await
+    }
+
+    /// The summary of `dropped`, extending the cached summary of its longest
+    /// already-summarized prefix with only the turns dropped since.
+    async fn summarize_dropped(dropped: &[ChatMessage]) -> Result<String> {
+        let keys = prefix_keys(dropped);
+        let cached = {
+            let summaries = cached_summaries().lock().unwrap();
+            keys.iter()
+                .enumerate()
+                .rev()
+                .find_map(|(i, key)| summaries.get(key).map(|summary| (i + 1, summary.clone())))
+        };
+        let (summarized, previous) = cached.unzip();
+        let summarized = summarized.unwrap_or(0);
+        if summarized == dropped.len() {
+            return Ok(previous.unwrap_or_default());
+        }
+
+        let summary = summarize(previous.as_deref(), &dropped[summarized..]).await?;
+        let mut summaries = cached_summaries().lock().unwrap();
+        if summaries.len() >= MAX_CACHED_SUMMARIES {
+            summaries.clear();
+        }
+        summaries.insert(keys[dropped.len() - 1].clone(), summary.clone());
+        Ok(summary)
+    }
+
+    /// Fits `messages` into `provider`'s context window according to the
+    /// `HistoryPolicy`. Applied before every provider request.
+    pub async fn fit_messages(
+        provider: Provider,
+        messages: &[ChatMessage],
+    ) -> Result<Vec<ChatMessage>> {
+        let budget = token_budget(provider);
+        if count_message_tokens(messages) <= budget {
+            return Ok(messages.to_vec());
+        }
+
+        let (mut kept, dropped) = sliding_window(messages, budget);
+        if HistoryPolicy::from_env() == HistoryPolicy::Summarize && !dropped.is_empty() {
+            match summarize_dropped(&dropped).await {
+                Ok(summary) => {
+                    let summary = ChatMessage::system(format!(
+                        "Summary of the earlier conversation:\n{summary}"
+                    ));
+                    let at = kept
+                        .iter()
+                        .position(|message| message.role != Role::System)
+                        .unwrap_or(kept.len());
+                    kept.insert(at, summary);
+                    // The summary takes room of its own, so window the result again
+                    kept = sliding_window(&kept, budget).0;
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Could not summarize earlier turns; dropping them")
+                }
+            }
+        }
+        truncate_to_budget(kept, budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::context_window::{
+        context_limit, count_message_tokens, count_tokens, prefix_keys, sliding_window,
+        truncate_to_budget, DEFAULT_CONTEXT_LIMIT,
+    };
+    use crate::chat_completions::utils::messages::messages::{ChatMessage, Role};
+
+    fn conversation(turns: usize) -> Vec<ChatMessage> {
+        let mut messages = vec![ChatMessage::system("You are a helpful assistant.")];
+        for turn in 0..turns {
+            messages.push(ChatMessage::user(format!("Question number {turn}?")));
+            messages.push(ChatMessage::assistant(format!("Answer number {turn}.")));
+        }
+        messages.push(ChatMessage::user("And the final question?"));
+        messages
+    }
+
+    #[test]
+    fn test_context_limits_by_model() {
+        assert_eq!(context_limit("gpt-4o-mini"), 128_000);
+        assert_eq!(context_limit("gpt-4-0613"), 8_192);
+        assert_eq!(context_limit("claude-3-5-sonnet-latest"), 200_000);
+        assert_eq!(context_limit("llama-3.1-sonar-small-128k-online"), 127_072);
+        assert_eq!(context_limit("llama3.1"), DEFAULT_CONTEXT_LIMIT);
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        assert_eq!(count_tokens(""), 0);
+        assert!(count_tokens("hello world") <= 3);
+        assert!(count_message_tokens(&conversation(2)) > count_message_tokens(&conversation(1)));
+    }
+
+    #[test]
+    fn test_sliding_window_keeps_system_and_latest_turns() {
+        let messages = conversation(20);
+        let budget = count_message_tokens(&conversation(3));
+        let (kept, dropped) = sliding_window(&messages, budget);
+
+        assert!(count_message_tokens(&kept) <= budget);
+        assert_eq!(kept.len() + dropped.len(), messages.len());
+        assert_eq!(kept[0].role, Role::System);
+        assert_eq!(kept[1].role, Role::User);
+        assert_eq!(kept.last().unwrap().content, "And the final question?");
+        assert!(kept.iter().any(|m| m.content == "Answer number 19."));
+        assert!(dropped.iter().any(|m| m.content == "Question number 0?"));
+    }
+
+    #[test]
+    fn test_truncate_to_budget_shrinks_the_longest_message() {
+        let messages = vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("word ".repeat(500)),
+        ];
+        let fitted = truncate_to_budget(messages, 100).unwrap();
+        assert!(count_message_tokens(&fitted) <= 100);
+        assert_eq!(fitted[0].content, "Be brief.");
+        assert!(fitted[1].content.ends_with("[truncated]"));
+    }
+
+    #[test]
+    fn test_truncate_to_budget_never_exceeds_the_budget() {
+        // A single message larger than the whole budget is cut down inside
+        let fitted = truncate_to_budget(vec![ChatMessage::user("word ".repeat(500))], 20).unwrap();
+        assert!(count_message_tokens(&fitted) <= 20);
+
+        // Too many messages to fit even once emptied is an error, not an overrun
+        assert!(truncate_to_budget(conversation(20), 20).is_err());
+    }
+
+    #[test]
+    fn test_prefix_keys_identify_each_prefix() {
+        let messages = conversation(2);
+        let keys = prefix_keys(&messages);
+        assert_eq!(keys.len(), messages.len());
+        assert_eq!(prefix_keys(&messages[..3]), keys[..3]);
+        assert_ne!(prefix_keys(&conversation(3))[5], keys[5]);
+    }
+}
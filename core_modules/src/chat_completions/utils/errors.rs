@@ -0,0 +1,323 @@
+pub mod errors {
+    use crate::chat_completions::providers::gemini::gemini::GeminiError;
+    use anyhow::Error;
+    use serde::{Serialize, Serializer};
+    use serde_json::{json, Value};
+    use std::fmt;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    /// A provider answered with a non-success HTTP status.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct UpstreamError {
+        pub provider: &'static str,
+        pub status: u16,
+    }
+
+    impl UpstreamError {
+        pub fn new(provider: &'static str, status: reqwest::StatusCode) -> Self {
+            UpstreamError {
+                provider,
+                status: status.as_u16(),
+            }
+        }
+    }
+
+    impl fmt::Display for UpstreamError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "Failed to fetch response from {}: {}",
+                self.provider, self.status
+            )
+        }
+    }
+
+    impl std::error::Error for UpstreamError {}
+
+    /// A Saturn turn was abandoned before it finished.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Cancelled;
+
+    impl fmt::Display for Cancelled {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("The request was cancelled before Saturn finished answering")
+        }
+    }
+
+    impl std::error::Error for Cancelled {}
+
+    /// A Saturn turn ran past its deadline.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DeadlineExceeded;
+
+    impl fmt::Display for DeadlineExceeded {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("Saturn did not finish answering within the time limit")
+        }
+    }
+
+    impl std::error::Error for DeadlineExceeded {}
+
+    /// What went wrong, as far as an API client is concerned.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ErrorKind {
+        BadRequest,
+        Unauthorized,
+        Forbidden,
+        NotFound,
+        MethodNotAllowed,
+        /// Saturn's own limits, or an upstream provider's.
+        RateLimited,
+        /// An upstream provider failed or was unreachable.
+        Upstream,
+        /// An upstream provider, or the whole turn, took too long.
+        Timeout,
+        /// Saturn is shutting down.
+        Unavailable,
+        Internal,
+    }
+
+    impl ErrorKind {
+        pub fn status(&self) -> u16 {
+            match self {
+                ErrorKind::BadRequest => 400,
+                ErrorKind::Unauthorized => 401,
+                ErrorKind::Forbidden => 403,
+                ErrorKind::NotFound => 404,
+                ErrorKind::MethodNotAllowed => 405,
+                ErrorKind::RateLimited => 429,
+                ErrorKind::Upstream => 502,
+                ErrorKind::Timeout => 504,
+                ErrorKind::Unavailable => 503,
+                ErrorKind::Internal => 500,
+            }
+        }
+
+        /// Stable machine-readable code for the error envelope.
+        pub fn code(&self) -> &'static str {
+            match self {
+                ErrorKind::BadRequest => "bad_request",
+                ErrorKind::Unauthorized => "unauthorized",
+                ErrorKind::Forbidden => "forbidden",
+                ErrorKind::NotFound => "not_found",
+                ErrorKind::MethodNotAllowed => "method_not_allowed",
+                ErrorKind::RateLimited => "rate_limited",
+                ErrorKind::Upstream => "upstream_error",
+                ErrorKind::Timeout => "upstream_timeout",
+                ErrorKind::Unavailable => "unavailable",
+                ErrorKind::Internal => "internal_error",
+            }
+        }
+
+        /// The matching OpenAI error `type`, so OpenAI SDKs surface errors properly.
+        pub fn error_type(&self) -> &'static str {
+            match self {
+                ErrorKind::BadRequest | ErrorKind::MethodNotAllowed => "invalid_request_error",
+                ErrorKind::Unauthorized => "authentication_error",
+                ErrorKind::Forbidden => "permission_error",
+                ErrorKind::NotFound => "not_found_error",
+                ErrorKind::RateLimited => "rate_limit_error",
+                ErrorKind::Upstream
+                | ErrorKind::Timeout
+                | ErrorKind::Unavailable
+                | ErrorKind::Internal => "api_error",
+            }
+        }
+
+        /// What clients are told for failures whose details may name hosts,
+        /// paths or credentials. Those details only go to the server log.
+        pub fn public_message(&self) -> Option<&'static str> {
+            match self {
+                ErrorKind::Upstream => Some("upstream provider unavailable"),
+                ErrorKind::Internal => Some("internal server error"),
+                _ => None,
+            }
+        }
+    }
+
+    /// Serialized as its `code`.
+    impl Serialize for ErrorKind {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.code())
+        }
+    }
+
+    /// Maps an error from the Saturn pipeline to what the client should be told.
+    /// Upstream HTTP failures keep their meaning; anything unrecognised is internal.
+    pub fn classify(error: &Error) -> ErrorKind {
+        for cause in error.chain() {
+            if cause.downcast_ref::<Cancelled>().is_some() {
+                return ErrorKind::Unavailable;
+            }
+            if cause.downcast_ref::<DeadlineExceeded>().is_some() {
+                return ErrorKind::Timeout;
+            }
+            if let Some(upstream) = cause.downcast_ref::<UpstreamError>() {
+                return match upstream.status {
+                    429 => ErrorKind::RateLimited,
+                    408 | 504 => ErrorKind::Timeout,
+                    _ => ErrorKind::Upstream,
+                };
+            }
+            if let Some(request) = cause.downcast_ref::<reqwest::Error>() {
+                return if request.is_timeout() {
+                    ErrorKind::Timeout
+                } else {
+                    ErrorKind::Upstream
+                };
+            }
+            if cause.downcast_ref::<GeminiError>().is_some() {
+                return ErrorKind::Upstream;
+            }
+        }
+        ErrorKind::Internal
+    }
+
+    /// An error reported to an API client.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ApiError {
+        pub kind: ErrorKind,
+        pub message: String,
+        /// How long the client should wait before retrying, sent as `Retry-After`.
+        pub retry_after: Option<Duration>,
+        /// Replaces the kind's `code` where OpenAI SDKs expect a more specific one.
+        pub code: Option<&'static str>,
+    }
+
+    impl ApiError {
+        pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+            ApiError {
+                kind,
+                message: message.into(),
+                retry_after: None,
+                code: None,
+            }
+        }
+
+        pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+            self.retry_after = Some(retry_after);
+            self
+        }
+
+        pub fn with_code(mut self, code: &'static str) -> Self {
+            self.code = Some(code);
+            self
+        }
+
+        /// `Retry-After` in whole seconds, rounded up so clients never retry early.
+        pub fn retry_after_secs(&self) -> Option<u64> {
+            self.retry_after
+                .map(|wait| wait.as_secs() + u64::from(wait.subsec_nanos() > 0))
+        }
+
+        pub fn bad_request(message: impl Into<String>) -> Self {
+            ApiError::new(ErrorKind::BadRequest, message)
+        }
+
+        pub fn not_found(message: impl Into<String>) -> Self {
+            ApiError::new(ErrorKind::NotFound, message)
+        }
+
+        /// `{"error": {"code", "message", "type", "request_id"}}`, plus
+        /// `retry_after` in seconds when the client should back off.
+        pub fn envelope(&self, request_id: &str) -> Value {
+            let mut envelope = json!({
+                "error": {
+                    "code": self.code.unwrap_or(self.kind.code()),
+                    "message": self.message,
+                    "type": self.kind.error_type(),
+                    "request_id": request_id,
+                }
+            });
+            if let Some(seconds) = self.retry_after_secs() {
+                envelope["error"]["retry_after"] = json!(seconds);
+            }
+            envelope
+        }
+    }
+
+    impl From<&Error> for ApiError {
+        fn from(error: &Error) -> Self {
+            let kind = classify(error);
+            match kind.public_message() {
+                Some(message) => {
+                    tracing::error!(code = kind.code(), error = %format!("{error:#}"), "Request failed");
+                    ApiError::new(kind, message)
+                }
+                None => ApiError::new(kind, format!("{error:#}")),
+            }
+        }
+    }
+
+    impl From<Error> for ApiError {
+        fn from(error: Error) -> Self {
+            ApiError::from(&error)
+        }
+    }
+
+    /// A fresh id for correlating a request with its logs and error reports.
+    pub fn new_request_id() -> String {
+        Uuid::new_v4().simple().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::errors::{
+        classify, ApiError, Cancelled, DeadlineExceeded, ErrorKind, UpstreamError,
+    };
+    use anyhow::anyhow;
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn test_classify_upstream_statuses() {
+        let rate_limited =
+            anyhow::Error::new(UpstreamError::new("openai", StatusCode::TOO_MANY_REQUESTS))
+                .context("No provider in the fallback chain produced a response.");
+        assert_eq!(classify(&rate_limited), ErrorKind::RateLimited);
+
+        let timeout = UpstreamError::new("gemini", StatusCode::GATEWAY_TIMEOUT).into();
+        assert_eq!(classify(&timeout), ErrorKind::Timeout);
+
+        let failed = UpstreamError::new("anthropic", StatusCode::SERVICE_UNAVAILABLE).into();
+        assert_eq!(classify(&failed), ErrorKind::Upstream);
+
+        assert_eq!(classify(&anyhow!("disk full")), ErrorKind::Internal);
+        assert_eq!(classify(&Cancelled.into()), ErrorKind::Unavailable);
+        assert_eq!(classify(&DeadlineExceeded.into()), ErrorKind::Timeout);
+    }
+
+    #[test]
+    fn test_envelope() {
+        let envelope = ApiError::bad_request("Missing 'query'").envelope("abc123");
+        assert_eq!(envelope["error"]["code"], "bad_request");
+        assert_eq!(envelope["error"]["message"], "Missing 'query'");
+        assert_eq!(envelope["error"]["type"], "invalid_request_error");
+        assert_eq!(envelope["error"]["request_id"], "abc123");
+        assert_eq!(ErrorKind::Timeout.status(), 504);
+        assert!(envelope["error"].get("retry_after").is_none());
+
+        let limited = ApiError::new(ErrorKind::RateLimited, "Slow down")
+            .with_retry_after(Duration::from_millis(1500));
+        assert_eq!(limited.retry_after_secs(), Some(2));
+        assert_eq!(limited.envelope("abc123")["error"]["retry_after"], 2);
+
+        // Internal and upstream details stay in the server log
+        let failed = anyhow::Error::new(UpstreamError::new("openai", StatusCode::BAD_GATEWAY))
+            .context("https://example.com/v1?key=secret");
+        let error = ApiError::from(failed);
+        assert_eq!(error.message, "upstream provider unavailable");
+        assert_eq!(
+            ApiError::from(anyhow!("/home/saturn/memory.db is locked")).message,
+            "internal server error"
+        );
+
+        let unknown = ApiError::bad_request("No such model").with_code("model_not_found");
+        assert_eq!(
+            unknown.envelope("abc123")["error"]["code"],
+            "model_not_found"
+        );
+    }
+}
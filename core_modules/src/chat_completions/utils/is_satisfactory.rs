@@ -1,12 +1,11 @@
 pub mod is_satisfactory {
-    use crate::chat_completions::providers::openai::openai::openai;
     use crate::chat_completions::utils::json_query::json_query::json_query;
     use anyhow::Result;
-    use serde_json::{json, Value};
+    use serde_json::json;
 
     /// Determines if a given response satisfactorily addresses the query.
     ///
-    /// This function calls OpenAI with a query and response and checks if the response is satisfactory
+    /// This function calls the classifier provider with a query and response and checks if the response is satisfactory
     /// by analyzing if it directly and accurately answers the user's question.
     ///
     /// # Arguments
@@ -15,6 +14,7 @@ pub mod is_satisfactory {
     ///
     /// # Returns
     /// * `Result<bool>` - Returns `true` if the response is satisfactory, `false` otherwise.
+    #[tracing::instrument(skip_all)]
     pub async fn is_satisfactory(query: String, response: String) -> Result<bool> {
         // Use json_query to check if the response is satisfactory
         let json_response = json_query(
@@ -1,11 +1,25 @@
 pub mod json_query {
-    use anyhow::{bail, Result};
+    use anyhow::{anyhow, bail, Result};
     use serde_json::{json, Value};
+    use std::future::Future;
+    use std::time::Instant;
     use tokio::time::{sleep, Duration};
 
-    use crate::chat_completions::providers::openai::openai_json::function_call;
+    use crate::chat_completions::providers::{
+        local::local::local_json,
+        openai::openai_json::function_call,
+        router::router::{classifier_provider, complete, provider_span, record_call, Provider},
+    };
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
+    use crate::chat_completions::utils::metrics::metrics::metrics;
+    use tracing::{debug, instrument, warn, Instrument};
 
     /// The main function for handling JSON queries with validation and retries.
+    ///
+    /// Runs on the classifier provider: OpenAI function calling by default, the
+    /// local model's structured output when `SATURN_CLASSIFIER_PROVIDER=local`,
+    /// and a JSON-only prompt for the other providers.
+    #[instrument(skip_all, fields(function = %function_name))]
     pub async fn json_query(
         query: String,
         function_name: String,
@@ -14,30 +28,69 @@ pub mod json_query {
         required: Vec<String>,
         function_call_arguments: Value,
     ) -> Result<Value> {
+        let provider = classifier_provider();
+
         // Retry up to 10 times if response does not contain required keys
         for attempt in 1..=10 {
-            //println!("JSON Query Attempt: #{attempt} for '{query}'");
-
             // Call the underlying function
-            let response = match function_call(
-                query.clone(),
-                function_name.clone(),
-                function_description.clone(),
-                properties.clone(),
-                required.clone(),
-                function_call_arguments.clone(),
-            )
-            .await
-            {
+            let response = match provider {
+                Provider::Local => {
+                    let call = local_json(
+                        &query,
+                        &function_description,
+                        &properties,
+                        &required,
+                        &function_call_arguments,
+                    );
+                    recorded(
+                        provider,
+                        &query,
+                        &function_description,
+                        &properties,
+                        &function_call_arguments,
+                        call,
+                    )
+                    .await
+                }
+                Provider::OpenAI => {
+                    let call = function_call(
+                        query.clone(),
+                        function_name.clone(),
+                        function_description.clone(),
+                        properties.clone(),
+                        required.clone(),
+                        function_call_arguments.clone(),
+                    );
+                    recorded(
+                        provider,
+                        &query,
+                        &function_description,
+                        &properties,
+                        &function_call_arguments,
+                        call,
+                    )
+                    .await
+                }
+                _ => {
+                    prompt_json(
+                        provider,
+                        &query,
+                        &function_description,
+                        &properties,
+                        &required,
+                        &function_call_arguments,
+                    )
+                    .await
+                }
+            };
+            let response = match response {
                 Ok(response) => response,
-                Err(_e) => {
-                    //println!("Function Call error: {e}");
+                Err(e) => {
+                    debug!(attempt, error = %e, "Function call failed");
                     continue;
                 }
             };
 
-            //println!("Response: {:?}", response);
-
             // Parse response as JSON
             let response_json: Value = response;
 
@@ -47,7 +100,8 @@ pub mod json_query {
             }
 
             // Log retry attempt
-            eprintln!("Attempt {}/10: Missing required keys, retrying...", attempt);
+            warn!(attempt, "Missing required keys; retrying");
+            metrics().record_retry("missing_keys");
 
             // Delay between retries (optional, e.g., 500ms)
             sleep(Duration::from_millis(500)).await;
@@ -57,6 +111,64 @@ pub mod json_query {
         bail!("Failed to retrieve a response with all required keys after 10 attempts.")
     }
 
+    /// Makes a structured-output call with the span, metrics and usage of any
+    /// other provider call. Its tokens are estimated from the prompt it sent.
+    async fn recorded(
+        provider: Provider,
+        query: &str,
+        description: &str,
+        properties: &Value,
+        arguments: &Value,
+        call: impl Future<Output = Result<Value>>,
+    ) -> Result<Value> {
+        let span = provider_span(provider);
+        let started = Instant::now();
+        let result = call.instrument(span.clone()).await;
+        let prompt = [
+            ChatMessage::system(format!("{description} {properties}")),
+            ChatMessage::user(format!("{query}\n\nInput: {arguments}")),
+        ];
+        let response = result.as_ref().ok().map(Value::to_string);
+        span.in_scope(|| record_call(provider, &prompt, response.as_deref(), None, started));
+        result
+    }
+
+    /// Asks a provider without structured output for a JSON object by prompt,
+    /// then parses the object out of its reply.
+    async fn prompt_json(
+        provider: Provider,
+        query: &str,
+        description: &str,
+        properties: &Value,
+        required: &[String],
+        arguments: &Value,
+    ) -> Result<Value> {
+        let schema = json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        });
+        let messages = [
+            ChatMessage::system(format!(
+                "{description} Reply only with a JSON object matching this schema, without any other text: {schema}"
+            )),
+            ChatMessage::user(format!("{query}\n\nInput: {arguments}")),
+        ];
+        extract_json(&complete(provider, &messages).await?)
+    }
+
+    /// The JSON object in a model's reply, which may wrap it in a code fence or prose.
+    pub fn extract_json(reply: &str) -> Result<Value> {
+        let start = reply.find('{');
+        let end = reply.rfind('}');
+        match (start, end) {
+            (Some(start), Some(end)) if start < end => {
+                Ok(serde_json::from_str(&reply[start..=end])?)
+            }
+            _ => Err(anyhow!("No JSON object found in the reply.")),
+        }
+    }
+
     /// Check if all required keys are present in the response.
     fn has_required_keys(response_json: &Value, required_keys: &[String]) -> bool {
         required_keys
@@ -68,9 +180,19 @@ pub mod json_query {
 #[cfg(test)]
 mod tests {
     use super::super::super::providers::openai::openai::openai;
-    use super::json_query::json_query;
+    use super::json_query::{extract_json, json_query};
     use serde_json::json;
 
+    #[test]
+    fn test_extract_json_from_a_fenced_reply() {
+        let reply = "Here you go:\n```json\n{\"satisfactory\": true}\n```";
+        assert_eq!(
+            extract_json(reply).unwrap(),
+            json!({ "satisfactory": true })
+        );
+        assert!(extract_json("Yes, it is.").is_err());
+    }
+
     #[tokio::test]
     async fn weather_test() {
         let query: String = "What's the weather like in orange county, CA?".to_string();
@@ -0,0 +1,214 @@
+pub mod logging {
+    use dotenv::dotenv;
+    use std::env;
+    use std::fs::OpenOptions;
+    use std::io;
+    use std::path::Path;
+    use std::sync::Mutex;
+    use tracing_subscriber::fmt::writer::BoxMakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+    use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+    /// How log lines are written, read from `SATURN_LOG_FORMAT`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum LogFormat {
+        /// Human-readable lines (the default).
+        Text,
+        /// One JSON object per line, for log collectors.
+        Json,
+    }
+
+    impl LogFormat {
+        pub fn from_env() -> Self {
+            dotenv().ok();
+            match env::var("SATURN_LOG_FORMAT") {
+                Ok(format) if format.trim().eq_ignore_ascii_case("json") => LogFormat::Json,
+                _ => LogFormat::Text,
+            }
+        }
+    }
+
+    /// The filter from `SATURN_LOG` (e.g. `debug` or `warn,core_modules=debug`),
+    /// or `default_level` when it is unset or invalid.
+    pub fn log_filter(default_level: &str) -> EnvFilter {
+        dotenv().ok();
+        env::var("SATURN_LOG")
+            .ok()
+            .and_then(|directives| EnvFilter::try_new(directives).ok())
+            .unwrap_or_else(|| EnvFilter::new(default_level))
+    }
+
+    type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+    /// Keeps trace export running; dropping it flushes spans still buffered.
+    /// Hold it for the life of the program.
+    #[must_use = "dropping the guard stops trace export"]
+    pub struct LoggingGuard {
+        #[cfg(feature = "otel")]
+        provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+    }
+
+    impl Drop for LoggingGuard {
+        fn drop(&mut self) {
+            #[cfg(feature = "otel")]
+            if let Some(provider) = self.provider.take() {
+                if let Err(e) = provider.shutdown() {
+                    eprintln!("Failed to flush traces: {e}");
+                }
+            }
+        }
+    }
+
+    /// Sends `tracing` output to stderr, so it never mixes with answers on stdout.
+    /// Binaries call this once at startup; later calls are ignored.
+    ///
+    /// Built with the `otel` feature, spans are also exported over OTLP/HTTP as
+    /// `service` once `OTEL_EXPORTER_OTLP_ENDPOINT` (or the traces-specific
+    /// variant) is set. `SATURN_TRACE` filters exported spans, `info` by default,
+    /// independently of the log level.
+    pub fn init_logging(service: &str, default_level: &str) -> LoggingGuard {
+        init_logging_with(service, default_level, BoxMakeWriter::new(io::stderr), true)
+    }
+
+    /// [`init_logging`], but appending to `path`, for full-screen interfaces
+    /// where stderr would draw over the screen. Logs are dropped if the file
+    /// can't be opened.
+    pub fn init_logging_to_file(service: &str, default_level: &str, path: &Path) -> LoggingGuard {
+        let writer = match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => BoxMakeWriter::new(Mutex::new(file)),
+            Err(_) => BoxMakeWriter::new(io::sink),
+        };
+        init_logging_with(service, default_level, writer, false)
+    }
+
+    fn init_logging_with(
+        service: &str,
+        default_level: &str,
+        writer: BoxMakeWriter,
+        ansi: bool,
+    ) -> LoggingGuard {
+        let output = fmt::layer().with_writer(writer).with_ansi(ansi);
+        let fmt_layer: BoxedLayer = match LogFormat::from_env() {
+            LogFormat::Json => output
+                .json()
+                .flatten_event(true)
+                .with_filter(log_filter(default_level))
+                .boxed(),
+            LogFormat::Text => output.with_filter(log_filter(default_level)).boxed(),
+        };
+        #[cfg_attr(not(feature = "otel"), allow(unused_mut))]
+        let mut layers = vec![fmt_layer];
+
+        #[cfg(not(feature = "otel"))]
+        let guard = {
+            let _ = service;
+            LoggingGuard {}
+        };
+        #[cfg(feature = "otel")]
+        let guard = match otel::export_configured().then(|| otel::tracer_provider(service, None)) {
+            Some(Ok(provider)) => {
+                layers.push(otel::layer(&provider, service));
+                LoggingGuard {
+                    provider: Some(provider),
+                }
+            }
+            Some(Err(e)) => {
+                eprintln!("Trace export disabled: {e}");
+                LoggingGuard { provider: None }
+            }
+            None => LoggingGuard { provider: None },
+        };
+
+        let _ = tracing_subscriber::registry().with(layers).try_init();
+        guard
+    }
+
+    #[cfg(feature = "otel")]
+    pub mod otel {
+        use super::BoxedLayer;
+        use anyhow::Result;
+        use dotenv::dotenv;
+        use opentelemetry::trace::TracerProvider as _;
+        use opentelemetry::KeyValue;
+        use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+        use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+        use std::env;
+        use tracing_subscriber::{EnvFilter, Layer};
+
+        /// Whether an OTLP endpoint is configured.
+        pub fn export_configured() -> bool {
+            dotenv().ok();
+            [
+                "OTEL_EXPORTER_OTLP_ENDPOINT",
+                "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            ]
+            .iter()
+            .any(|var| env::var(var).is_ok_and(|value| !value.is_empty()))
+        }
+
+        /// A batching OTLP/HTTP tracer provider. `endpoint` is the full traces URL;
+        /// without it the standard `OTEL_EXPORTER_OTLP_*` variables apply.
+        pub fn tracer_provider(service: &str, endpoint: Option<&str>) -> Result<TracerProvider> {
+            let mut exporter = SpanExporter::builder().with_http();
+            if let Some(endpoint) = endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            let service = env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service.to_string());
+            Ok(TracerProvider::builder()
+                .with_batch_exporter(exporter.build()?, runtime::Tokio)
+                .with_resource(Resource::new([KeyValue::new("service.name", service)]))
+                .build())
+        }
+
+        /// The `tracing` layer exporting spans through `provider`.
+        pub fn layer(provider: &TracerProvider, service: &str) -> BoxedLayer {
+            let filter = env::var("SATURN_TRACE")
+                .ok()
+                .and_then(|directives| EnvFilter::try_new(directives).ok())
+                .unwrap_or_else(|| EnvFilter::new("info"));
+            tracing_opentelemetry::layer()
+                .with_tracer(provider.tracer(service.to_string()))
+                .with_filter(filter)
+                .boxed()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use super::logging::otel::{layer, tracer_provider};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Exports a span to a stand-in collector and checks it arrives as an OTLP request.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_spans_reach_the_collector() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let collector = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 64 * 1024];
+            let read = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+
+        let endpoint = format!("http://{address}/v1/traces");
+        let provider = tracer_provider("saturn-test", Some(&endpoint)).unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer(&provider, "saturn-test"));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("provider_call", provider = "openai");
+            span.in_scope(|| tracing::info!("inside"));
+        });
+        let _ = provider.force_flush();
+
+        let request = collector.await.unwrap();
+        assert!(request.starts_with("POST /v1/traces"));
+        assert!(request.contains("application/x-protobuf"));
+    }
+}
@@ -0,0 +1,90 @@
+pub mod messages {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Role {
+        System,
+        User,
+        Assistant,
+    }
+
+    impl Role {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            }
+        }
+    }
+
+    /// A provider-agnostic conversation turn. Each provider converts these into
+    /// its own payload format.
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct ChatMessage {
+        pub role: Role,
+        pub content: String,
+    }
+
+    impl ChatMessage {
+        pub fn new(role: Role, content: String) -> Self {
+            ChatMessage { role, content }
+        }
+
+        pub fn system(content: impl Into<String>) -> Self {
+            ChatMessage::new(Role::System, content.into())
+        }
+
+        pub fn user(content: impl Into<String>) -> Self {
+            ChatMessage::new(Role::User, content.into())
+        }
+
+        pub fn assistant(content: impl Into<String>) -> Self {
+            ChatMessage::new(Role::Assistant, content.into())
+        }
+    }
+
+    /// Splits a conversation into its combined system prompt and the remaining turns,
+    /// for providers that take the system prompt as a separate field.
+    pub fn split_system(messages: &[ChatMessage]) -> (Option<String>, Vec<ChatMessage>) {
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.role == Role::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        let turns = messages
+            .iter()
+            .filter(|m| m.role != Role::System)
+            .cloned()
+            .collect();
+        let system = (!system.is_empty()).then(|| system.join("\n\n"));
+        (system, turns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::messages::{split_system, ChatMessage, Role};
+
+    #[test]
+    fn test_split_system_joins_system_prompts() {
+        let messages = vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("Hi"),
+            ChatMessage::system("Answer in English."),
+            ChatMessage::assistant("Hello!"),
+        ];
+
+        let (system, turns) = split_system(&messages);
+        assert_eq!(system.as_deref(), Some("Be brief.\n\nAnswer in English."));
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[1].role, Role::Assistant);
+    }
+
+    #[test]
+    fn test_role_serializes_lowercase() {
+        let json = serde_json::to_value(ChatMessage::user("Hi")).unwrap();
+        assert_eq!(json["role"], "user");
+    }
+}
@@ -0,0 +1,196 @@
+pub mod metrics {
+    use prometheus::{
+        Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+    };
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    /// Process-wide Prometheus metrics, exposed by the server on `/metrics`.
+    pub struct Metrics {
+        registry: Registry,
+        http_requests: IntCounterVec,
+        http_duration: HistogramVec,
+        provider_requests: IntCounterVec,
+        provider_duration: HistogramVec,
+        retries: IntCounterVec,
+        tokens: IntCounterVec,
+    }
+
+    fn counter(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+        let counter = IntCounterVec::new(Opts::new(name, help), labels).unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        counter
+    }
+
+    fn histogram(
+        registry: &Registry,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: Vec<f64>,
+    ) -> HistogramVec {
+        let histogram =
+            HistogramVec::new(HistogramOpts::new(name, help).buckets(buckets), labels).unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+        histogram
+    }
+
+    /// Latency buckets in seconds. Saturn turns chain several model calls, so
+    /// the tail reaches well past the usual web defaults.
+    const LATENCY_BUCKETS: [f64; 12] = [
+        0.005, 0.025, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+    ];
+
+    impl Metrics {
+        pub fn new() -> Self {
+            let registry = Registry::new_custom(Some("saturn".to_string()), None).unwrap();
+            Metrics {
+                http_requests: counter(
+                    &registry,
+                    "http_requests_total",
+                    "HTTP requests by route and status.",
+                    &["route", "status"],
+                ),
+                http_duration: histogram(
+                    &registry,
+                    "http_request_duration_seconds",
+                    "HTTP request latency by route.",
+                    &["route"],
+                    LATENCY_BUCKETS.to_vec(),
+                ),
+                provider_requests: counter(
+                    &registry,
+                    "provider_requests_total",
+                    "Model provider calls by outcome.",
+                    &["provider", "outcome"],
+                ),
+                provider_duration: histogram(
+                    &registry,
+                    "provider_request_duration_seconds",
+                    "Model provider call latency.",
+                    &["provider"],
+                    LATENCY_BUCKETS.to_vec(),
+                ),
+                retries: counter(
+                    &registry,
+                    "retries_total",
+                    "Retries by reason: an unsatisfactory answer, a provider falling back, ...",
+                    &["reason"],
+                ),
+                tokens: counter(
+                    &registry,
+                    "tokens_total",
+                    "Tokens sent to (prompt) and received from (completion) providers, as reported or estimated.",
+                    &["provider", "kind"],
+                ),
+                registry,
+            }
+        }
+
+        pub fn record_request(&self, route: &str, status: u16, elapsed: Duration) {
+            self.http_requests
+                .with_label_values(&[route, &status.to_string()])
+                .inc();
+            self.http_duration
+                .with_label_values(&[route])
+                .observe(elapsed.as_secs_f64());
+        }
+
+        pub fn record_provider_call(&self, provider: &str, success: bool, elapsed: Duration) {
+            let outcome = if success { "success" } else { "failure" };
+            self.provider_requests
+                .with_label_values(&[provider, outcome])
+                .inc();
+            self.provider_duration
+                .with_label_values(&[provider])
+                .observe(elapsed.as_secs_f64());
+        }
+
+        pub fn record_retry(&self, reason: &str) {
+            self.retries.with_label_values(&[reason]).inc();
+        }
+
+        pub fn record_tokens(&self, provider: &str, prompt: usize, completion: usize) {
+            self.tokens
+                .with_label_values(&[provider, "prompt"])
+                .inc_by(prompt as u64);
+            self.tokens
+                .with_label_values(&[provider, "completion"])
+                .inc_by(completion as u64);
+        }
+
+        /// Everything recorded so far, in the Prometheus text format.
+        pub fn render(&self) -> String {
+            let mut buffer = Vec::new();
+            TextEncoder::new()
+                .encode(&self.registry.gather(), &mut buffer)
+                .unwrap();
+            String::from_utf8(buffer).unwrap_or_default()
+        }
+    }
+
+    impl Default for Metrics {
+        fn default() -> Self {
+            Metrics::new()
+        }
+    }
+
+    /// The process-wide metrics.
+    pub fn metrics() -> &'static Metrics {
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        METRICS.get_or_init(Metrics::new)
+    }
+
+    /// The route label for a request path. Ids are collapsed so the number of
+    /// series stays bounded, and unknown paths share a single label.
+    pub fn route_label(path: &str) -> String {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["query" | "ws" | "healthz" | "readyz" | "metrics"] | ["sessions" | "memories"] => {
+                format!("/{}", segments[0])
+            }
+            ["sessions", _] => "/sessions/{id}".to_string(),
+            ["sessions", _, "messages"] => "/sessions/{id}/messages".to_string(),
+            ["memories", _] => "/memories/{id}".to_string(),
+            ["v1", "models"] | ["v1", "chat", "completions"] => format!("/{}", segments.join("/")),
+            _ => "unmatched".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::metrics::{route_label, Metrics};
+    use std::time::Duration;
+
+    #[test]
+    fn test_route_labels() {
+        assert_eq!(route_label("/query"), "/query");
+        assert_eq!(route_label("/sessions"), "/sessions");
+        assert_eq!(route_label("/ws"), "/ws");
+        assert_eq!(
+            route_label("/sessions/abc/messages"),
+            "/sessions/{id}/messages"
+        );
+        assert_eq!(route_label("/memories/12"), "/memories/{id}");
+        assert_eq!(route_label("/v1/chat/completions"), "/v1/chat/completions");
+        assert_eq!(route_label("/wp-admin/login.php"), "unmatched");
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.record_request("/query", 200, Duration::from_millis(300));
+        metrics.record_provider_call("openai", false, Duration::from_secs(2));
+        metrics.record_retry("fallback");
+        metrics.record_tokens("openai", 120, 30);
+
+        let text = metrics.render();
+        assert!(text.contains(r#"saturn_http_requests_total{route="/query",status="200"} 1"#));
+        assert!(text.contains("saturn_http_request_duration_seconds_bucket"));
+        assert!(text
+            .contains(r#"saturn_provider_requests_total{outcome="failure",provider="openai"} 1"#));
+        assert!(text.contains(r#"saturn_retries_total{reason="fallback"} 1"#));
+        assert!(text.contains(r#"saturn_tokens_total{kind="prompt",provider="openai"} 120"#));
+    }
+}
@@ -1,3 +1,12 @@
+pub mod context_window;
+pub mod errors;
 pub mod is_satisfactory;
 pub mod json_query;
+pub mod logging;
+pub mod messages;
+pub mod metrics;
 pub mod needs_internet;
+pub mod paths;
+pub mod progress;
+pub mod sse;
+pub mod usage;
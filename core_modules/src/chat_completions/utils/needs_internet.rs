@@ -1,12 +1,14 @@
 pub mod needs_internet {
-    use crate::chat_completions::providers::openai::openai::openai;
+    use crate::chat_completions::providers::router::router::{classifier_provider, complete};
     use crate::chat_completions::utils::json_query::json_query::json_query;
+    use crate::chat_completions::utils::messages::messages::ChatMessage;
     use anyhow::Result;
-    use serde_json::{json, Value};
+    use serde_json::json;
 
     /// Determines if a given query requires internet access.
     ///
-    /// This function calls OpenAI with a provided query and checks if the response indicates
+    /// This function calls the classifier provider (OpenAI unless `SATURN_CLASSIFIER_PROVIDER`
+    /// says otherwise) with a provided query and checks if the response indicates
     /// a need for internet access by analyzing the result.
     ///
     /// # Arguments
@@ -14,9 +16,17 @@ pub mod needs_internet {
     ///
     /// # Returns
     /// * `Result<bool>` - Returns `true` if internet access is required, `false` otherwise.
+    #[tracing::instrument(skip_all)]
     pub async fn needs_internet(query: String) -> Result<bool> {
-        // First, get the response from OpenAI for the query
-        let response = openai(query.clone()).await?;
+        // First, get the response from the classifier model for the query
+        let response = complete(
+            classifier_provider(),
+            &[
+                ChatMessage::system("You are a helpful assistant."),
+                ChatMessage::user(query.clone()),
+            ],
+        )
+        .await?;
 
         // Use json_query to check if the response suggests internet access is needed
         let json_response = json_query(
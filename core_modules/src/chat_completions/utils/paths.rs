@@ -0,0 +1,24 @@
+pub mod paths {
+    use dotenv::dotenv;
+    use std::env;
+    use std::path::PathBuf;
+
+    /// Directory holding Saturn's local state (memory database, indexes).
+    /// `SATURN_HOME` overrides the default of `~/.saturn`.
+    pub fn saturn_home() -> PathBuf {
+        dotenv().ok();
+        if let Ok(home) = env::var("SATURN_HOME") {
+            return PathBuf::from(home);
+        }
+        env::var("HOME")
+            .map(|home| PathBuf::from(home).join(".saturn"))
+            .unwrap_or_else(|_| PathBuf::from(".saturn"))
+    }
+
+    /// A file inside `saturn_home()`, creating the directory if needed.
+    pub fn saturn_file(name: &str) -> std::io::Result<PathBuf> {
+        let home = saturn_home();
+        std::fs::create_dir_all(&home)?;
+        Ok(home.join(name))
+    }
+}
@@ -0,0 +1,107 @@
+pub mod progress {
+    use crate::chat_completions::utils::errors::errors::ErrorKind;
+    use serde::Serialize;
+    use std::fmt;
+    use tokio::sync::mpsc::UnboundedSender;
+
+    /// A step of a Saturn turn, reported while the turn is running so clients
+    /// can show more than a spinner. Serialized with a `kind` tag.
+    #[derive(Serialize, Debug, Clone, PartialEq)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    pub enum Progress {
+        /// An attempt at an answer started. Attempts after the first are retries.
+        Attempt { number: usize, max: usize },
+        /// A provider was asked for an answer.
+        ProviderSelected { provider: String },
+        /// A provider failed, so the next one in the fallback chain is tried.
+        /// Only the kind of failure is sent, as its details may name hosts or keys.
+        FallbackTriggered { provider: String, error: ErrorKind },
+        /// The classifier is deciding whether the answer needs the internet.
+        CheckingInternet,
+        /// The classifier decided.
+        InternetChecked { needed: bool },
+        /// A search provider was asked instead.
+        Searching { provider: String },
+        /// The judge decided whether the answer is good enough to return.
+        Verdict { satisfactory: bool },
+    }
+
+    impl fmt::Display for Progress {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Progress::Attempt { number: 1, .. } => f.write_str("Thinking"),
+                Progress::Attempt { number, max } => write!(f, "Retrying {number}/{max}"),
+                Progress::ProviderSelected { provider } => write!(f, "Asking {provider}"),
+                Progress::FallbackTriggered { provider, .. } => {
+                    write!(f, "{provider} failed; falling back")
+                }
+                Progress::CheckingInternet => {
+                    f.write_str("Checking whether this needs the internet")
+                }
+                Progress::InternetChecked { needed: true } => f.write_str("Needs the internet"),
+                Progress::InternetChecked { needed: false } => {
+                    f.write_str("Answerable without the internet")
+                }
+                Progress::Searching { provider } => write!(f, "Searching with {provider}"),
+                Progress::Verdict { satisfactory: true } => f.write_str("Answer accepted"),
+                Progress::Verdict {
+                    satisfactory: false,
+                } => f.write_str("Answer rejected by the judge"),
+            }
+        }
+    }
+
+    /// Where a turn sends its progress. Reports are dropped once the receiver is gone.
+    pub type ProgressSender = UnboundedSender<Progress>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::progress::Progress;
+    use crate::chat_completions::utils::errors::errors::ErrorKind;
+    use serde_json::json;
+
+    #[test]
+    fn test_progress_labels() {
+        assert_eq!(
+            Progress::Attempt { number: 1, max: 10 }.to_string(),
+            "Thinking"
+        );
+        assert_eq!(
+            Progress::Attempt { number: 3, max: 10 }.to_string(),
+            "Retrying 3/10"
+        );
+        assert_eq!(
+            Progress::FallbackTriggered {
+                provider: "openai".to_string(),
+                error: ErrorKind::RateLimited
+            }
+            .to_string(),
+            "openai failed; falling back"
+        );
+        assert_eq!(
+            json!(Progress::FallbackTriggered {
+                provider: "openai".to_string(),
+                error: ErrorKind::RateLimited
+            }),
+            json!({ "kind": "fallback_triggered", "provider": "openai", "error": "rate_limited" })
+        );
+        assert_eq!(
+            Progress::Verdict {
+                satisfactory: false
+            }
+            .to_string(),
+            "Answer rejected by the judge"
+        );
+        assert_eq!(
+            json!(Progress::InternetChecked { needed: true }),
+            json!({ "kind": "internet_checked", "needed": true })
+        );
+        assert_eq!(
+            json!(Progress::Searching {
+                provider: "perplexity".to_string()
+            }),
+            json!({ "kind": "searching", "provider": "perplexity" })
+        );
+    }
+}
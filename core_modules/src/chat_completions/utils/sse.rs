@@ -0,0 +1,126 @@
+pub mod sse {
+    /// A single server-sent event.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SseEvent {
+        pub event: Option<String>,
+        pub data: String,
+    }
+
+    /// Splits a byte stream into lines. Bytes are buffered until a newline
+    /// arrives, so a UTF-8 character split across network chunks is decoded
+    /// whole. A trailing `\r` is dropped.
+    #[derive(Default)]
+    pub struct LineBuffer {
+        buffer: Vec<u8>,
+    }
+
+    impl LineBuffer {
+        pub fn new() -> Self {
+            LineBuffer::default()
+        }
+
+        /// Feeds a chunk and returns every line it completed.
+        pub fn push(&mut self, chunk: impl AsRef<[u8]>) -> Vec<String> {
+            self.buffer.extend_from_slice(chunk.as_ref());
+            let mut lines = Vec::new();
+            while let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                let line = line.strip_suffix(b"\n").unwrap_or(&line);
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                lines.push(String::from_utf8_lossy(line).into_owned());
+            }
+            lines
+        }
+    }
+
+    /// Incremental parser for `text/event-stream` bodies.
+    ///
+    /// Network chunks can end anywhere, so bytes are buffered until a blank line
+    /// completes an event.
+    #[derive(Default)]
+    pub struct SseParser {
+        lines: LineBuffer,
+        block: Vec<String>,
+    }
+
+    impl SseParser {
+        pub fn new() -> Self {
+            SseParser::default()
+        }
+
+        /// Feeds a chunk of the body and returns every event it completed.
+        pub fn push(&mut self, chunk: impl AsRef<[u8]>) -> Vec<SseEvent> {
+            let mut events = Vec::new();
+            for line in self.lines.push(chunk) {
+                if !line.is_empty() {
+                    self.block.push(line);
+                    continue;
+                }
+                if let Some(event) = parse_block(&self.block) {
+                    events.push(event);
+                }
+                self.block.clear();
+            }
+            events
+        }
+    }
+
+    fn parse_block(block: &[String]) -> Option<SseEvent> {
+        let mut event = None;
+        let mut data = Vec::new();
+        for line in block {
+            if let Some(value) = line.strip_prefix("event:") {
+                event = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("data:") {
+                data.push(value.strip_prefix(' ').unwrap_or(value));
+            }
+        }
+        if event.is_none() && data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            event,
+            data: data.join("\n"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sse::{LineBuffer, SseParser};
+
+    #[test]
+    fn test_parser_handles_events_split_across_chunks() {
+        let mut parser = SseParser::new();
+
+        assert!(parser.push("event: ping\nda").is_empty());
+        let events = parser.push("ta: {\"a\":1}\n\ndata: [DONE]\n\n");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event.as_deref(), Some("ping"));
+        assert_eq!(events[0].data, "{\"a\":1}");
+        assert_eq!(events[1].event, None);
+        assert_eq!(events[1].data, "[DONE]");
+    }
+
+    #[test]
+    fn test_parser_ignores_comments() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(": keep-alive\r\n\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_multi_byte_characters_split_across_chunks() {
+        let text = "data: {\"text\":\"café ✓\"}\n\n".as_bytes();
+        let split = text.iter().position(|&byte| byte == 0xc3).unwrap() + 1;
+        let mut parser = SseParser::new();
+        assert!(parser.push(&text[..split]).is_empty());
+        let events = parser.push(&text[split..]);
+        assert_eq!(events[0].data, "{\"text\":\"café ✓\"}");
+
+        let mut lines = LineBuffer::new();
+        let text = "{\"a\":\"✓\"}\r\n".as_bytes();
+        assert!(lines.push(&text[..8]).is_empty());
+        assert_eq!(lines.push(&text[8..]), vec!["{\"a\":\"✓\"}"]);
+    }
+}
@@ -0,0 +1,118 @@
+pub mod usage {
+    use crate::chat_completions::providers::router::router::Provider;
+    use std::cell::Cell;
+    use std::fmt;
+    use std::future::Future;
+    use std::ops::AddAssign;
+
+    tokio::task_local! {
+        static TURN_USAGE: Cell<Usage>;
+    }
+
+    /// List prices in US dollars per million (prompt, completion) tokens for
+    /// each provider's default model. Local models are free.
+    pub fn price_per_million(provider: Provider) -> (f64, f64) {
+        match provider {
+            Provider::OpenAI => (2.50, 10.00),
+            Provider::Anthropic => (3.00, 15.00),
+            Provider::Gemini => (0.50, 1.50),
+            Provider::Perplexity => (0.20, 0.20),
+            Provider::Local => (0.0, 0.0),
+        }
+    }
+
+    /// Tokens and estimated spend. Token counts are the ones the provider
+    /// reported where it reports them, and the local tokenizer's estimate otherwise.
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct Usage {
+        pub prompt_tokens: usize,
+        pub completion_tokens: usize,
+        /// In US dollars.
+        pub cost: f64,
+    }
+
+    impl Usage {
+        pub fn of_call(provider: Provider, prompt_tokens: usize, completion_tokens: usize) -> Self {
+            let (prompt_price, completion_price) = price_per_million(provider);
+            Usage {
+                prompt_tokens,
+                completion_tokens,
+                cost: (prompt_tokens as f64 * prompt_price
+                    + completion_tokens as f64 * completion_price)
+                    / 1_000_000.0,
+            }
+        }
+
+        pub fn total_tokens(&self) -> usize {
+            self.prompt_tokens + self.completion_tokens
+        }
+    }
+
+    impl AddAssign for Usage {
+        fn add_assign(&mut self, other: Usage) {
+            self.prompt_tokens += other.prompt_tokens;
+            self.completion_tokens += other.completion_tokens;
+            self.cost += other.cost;
+        }
+    }
+
+    /// Runs `future` and returns its output with the usage of every provider
+    /// call it made on this task: drafting, classifier and judge calls alike.
+    pub async fn track_usage<F: Future>(future: F) -> (F::Output, Usage) {
+        TURN_USAGE
+            .scope(Cell::new(Usage::default()), async {
+                let output = future.await;
+                (output, TURN_USAGE.with(Cell::get))
+            })
+            .await
+    }
+
+    /// Adds a provider call to the usage being tracked, if any.
+    pub(crate) fn add_to_turn(usage: Usage) {
+        let _ = TURN_USAGE.try_with(|total| {
+            let mut sum = total.get();
+            sum += usage;
+            total.set(sum);
+        });
+    }
+
+    impl fmt::Display for Usage {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} tokens, ~${:.4}", self.total_tokens(), self.cost)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::usage::{add_to_turn, track_usage, Usage};
+    use crate::chat_completions::providers::router::router::Provider;
+
+    #[test]
+    fn test_usage_adds_up() {
+        let mut usage = Usage::default();
+        usage += Usage::of_call(Provider::OpenAI, 1_000_000, 0);
+        usage += Usage::of_call(Provider::Anthropic, 0, 100_000);
+        usage += Usage::of_call(Provider::Local, 5_000, 5_000);
+        assert_eq!(usage.total_tokens(), 1_110_000);
+        assert!((usage.cost - 4.0).abs() < 1e-9);
+        assert_eq!(usage.to_string(), "1110000 tokens, ~$4.0000");
+    }
+
+    #[tokio::test]
+    async fn test_track_usage_adds_up_the_calls_of_one_turn() {
+        // Calls outside a tracked turn are not counted anywhere
+        add_to_turn(Usage::of_call(Provider::OpenAI, 10, 10));
+
+        let ((), usage) = track_usage(async {
+            add_to_turn(Usage::of_call(Provider::OpenAI, 100, 20));
+            add_to_turn(Usage::of_call(Provider::Local, 30, 5));
+        })
+        .await;
+        assert_eq!(usage.prompt_tokens, 130);
+        assert_eq!(usage.completion_tokens, 25);
+
+        let ((), usage) = track_usage(async {}).await;
+        assert_eq!(usage, Usage::default());
+    }
+}
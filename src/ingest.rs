@@ -0,0 +1,45 @@
+use anyhow::{bail, Result};
+use core_modules::chat_completions::memory::corpus::corpus::DocumentCorpus;
+use core_modules::chat_completions::memory::store::store::LOCAL_CLIENT;
+use core_modules::chat_completions::utils::logging::logging::init_logging;
+use std::env;
+
+const USAGE: &str = "Usage: ingest [--client <name>] <file-or-directory>...";
+
+/// Indexes local documents for Saturn to answer from. Documents belong to the
+/// local client unless `--client` names the server client they are for.
+///
+/// Usage: `cargo run --bin ingest -- [--client <name>] <file-or-directory>...`
+#[tokio::main]
+async fn main() -> Result<()> {
+    let _logging = init_logging("saturn-ingest", "warn");
+    let mut paths: Vec<String> = env::args().skip(1).collect();
+    let mut client = LOCAL_CLIENT.to_string();
+    if paths.first().is_some_and(|arg| arg == "--client") {
+        if paths.len() < 2 {
+            bail!(USAGE);
+        }
+        client = paths.remove(1);
+        paths.remove(0);
+    }
+    if paths.is_empty() {
+        bail!(USAGE);
+    }
+
+    let corpus = DocumentCorpus::open_default()?.for_client(&client);
+    for path in paths {
+        let report = corpus.ingest_path(&path).await?;
+        println!(
+            "{path}: indexed {} chunks from {} files.",
+            report.chunks, report.files
+        );
+        for skipped in report.skipped {
+            println!("  skipped {skipped}");
+        }
+        if report.removed > 0 {
+            println!("  removed {} deleted or duplicate files", report.removed);
+        }
+    }
+    println!("Corpus now holds {} chunks.", corpus.len().await);
+    Ok(())
+}
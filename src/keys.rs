@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use core_modules::chat_completions::interfaces::auth::auth::{parse_scopes, KeyStore};
+use std::env;
+
+const USAGE: &str = "Usage:
+  keys list
+  keys add <name> [scopes]   scopes: comma separated chat,sessions,memory,admin (default chat,sessions)
+  keys revoke <name>";
+
+/// Manages the client keys the server accepts. Restart the server to apply changes.
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut store = KeyStore::open_default()?;
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["list"] | [] => {
+            if store.clients().is_empty() {
+                println!(
+                    "No client keys; the server accepts unauthenticated requests on loopback only."
+                );
+            }
+            for client in store.clients() {
+                let scopes: Vec<String> = client.scopes.iter().map(|s| s.to_string()).collect();
+                println!(
+                    "{}\t{}\t{}",
+                    client.name,
+                    scopes.join(","),
+                    client.created_at
+                );
+            }
+        }
+        ["add", name] => print_key(
+            name,
+            store.add_client(name, parse_scopes("chat,sessions")?)?,
+        ),
+        ["add", name, scopes] => print_key(name, store.add_client(name, parse_scopes(scopes)?)?),
+        ["revoke", name] => {
+            if !store.revoke_client(name)? {
+                bail!("No client named '{name}'");
+            }
+            println!("Revoked {name}.");
+        }
+        _ => bail!("{USAGE}"),
+    }
+    Ok(())
+}
+
+fn print_key(name: &str, key: String) {
+    println!("Created a key for {name}. It will not be shown again:\n\n  {key}\n");
+    println!("Send it as `Authorization: Bearer <key>`.");
+}
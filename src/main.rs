@@ -1,8 +1,28 @@
 use core_modules::chat_completions::interfaces::chat::start_chat;
+use core_modules::chat_completions::interfaces::tui::tui::{start_tui, supported};
+use core_modules::chat_completions::utils::logging::logging::{init_logging, init_logging_to_file};
+use core_modules::chat_completions::utils::paths::paths::saturn_file;
 use tokio::main;
 
 #[main]
 async fn main() {
-    // Start the chat interface
-    start_chat().await;
+    // The full-screen interface unless `--simple` is passed or the terminal can't show it
+    let full_screen = !std::env::args().any(|arg| arg == "--simple") && supported();
+
+    // Only warnings by default, so logs don't crowd the conversation. The
+    // full-screen interface owns the terminal, so its logs go to a file.
+    let _logging = match saturn_file("saturn.log") {
+        Ok(path) if full_screen => init_logging_to_file("saturn-chat", "warn", &path),
+        _ => init_logging("saturn-chat", "warn"),
+    };
+
+    if full_screen {
+        if let Err(e) = start_tui().await {
+            eprintln!("The full-screen interface failed ({e:#}); using the simple one.");
+            start_chat().await;
+        }
+    } else {
+        // Start the chat interface
+        start_chat().await;
+    }
 }
@@ -1,32 +1,1362 @@
+// The composed warp filters and instrumented Saturn futures nest past the default limit
+#![recursion_limit = "256"]
+
 use anyhow::Result;
-use core_modules::chat_completions::bots::saturn::saturn::saturn;
-use serde_json::json;
+use core_modules::chat_completions::bots::saturn::saturn::{
+    saturn_with_options, turn_timeout, SaturnOptions,
+};
+use core_modules::chat_completions::interfaces::auth::auth::{bearer_token, KeyStore, Scope};
+use core_modules::chat_completions::interfaces::health::health::ReadinessCache;
+use core_modules::chat_completions::interfaces::limits::limits::{Admission, Limits};
+use core_modules::chat_completions::interfaces::openai_compat::openai_compat::{
+    completion_id, list_models, ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse,
+    CompatTurn, ModelNotFound,
+};
+use core_modules::chat_completions::interfaces::websocket::websocket::{
+    token_pieces, ClientMessage, ServerEvent,
+};
+use core_modules::chat_completions::memory::{
+    corpus::corpus::DocumentCorpus,
+    recall::recall::ConversationRecall,
+    sessions::sessions::SessionStore,
+    store::store::{MemoryStore, LOCAL_CLIENT},
+};
+use core_modules::chat_completions::utils::errors::errors::{
+    new_request_id, ApiError, Cancelled, ErrorKind,
+};
+use core_modules::chat_completions::utils::logging::logging::init_logging;
+use core_modules::chat_completions::utils::messages::messages::ChatMessage;
+use core_modules::chat_completions::utils::metrics::metrics::{metrics, route_label};
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{error, field, info, info_span, warn};
+use warp::reject::{
+    InvalidHeader, LengthRequired, MethodNotAllowed, MissingHeader, PayloadTooLarge,
+    UnsupportedMediaType,
+};
+use warp::sse::Event;
+use warp::ws::{Message, WebSocket, Ws};
 use warp::{http::StatusCode, reply, serve, Filter, Rejection, Reply};
 
+/// Header carrying the request id, echoed from the client or generated.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Where the server listens unless `SATURN_BIND` says otherwise.
+const DEFAULT_BIND: &str = "127.0.0.1:2223";
+/// How long in-flight requests may run after a shutdown signal unless
+/// `SATURN_SHUTDOWN_GRACE_SECS` says otherwise.
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+/// How long cancelled requests get to send their final response.
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// A request turned away before reaching its handler: it failed authentication,
+/// lacks the route's scope or is over a limit. Carries the request's id so the
+/// error envelope still matches the client's `X-Request-Id`.
+#[derive(Debug)]
+struct ApiRejection {
+    error: ApiError,
+    request_id: String,
+}
+
+impl ApiRejection {
+    fn reject(error: impl Into<ApiError>, request_id: &str) -> Rejection {
+        warp::reject::custom(ApiRejection {
+            error: error.into(),
+            request_id: request_id.to_string(),
+        })
+    }
+}
+
+impl warp::reject::Reject for ApiRejection {}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Server initiating...");
+    let _logging = init_logging("saturn-server", "info");
+    info!("Server initiating");
+    let keys = Arc::new(KeyStore::open_default()?);
+    let address: SocketAddr = env::var("SATURN_BIND")
+        .unwrap_or_else(|_| DEFAULT_BIND.to_string())
+        .parse()?;
+    if !keys.is_enabled() {
+        if !address.ip().is_loopback() {
+            return Err(format!(
+                "Refusing to listen on {address} without authentication. \
+                 Create a client key first: cargo run --bin keys -- add <name>"
+            )
+            .into());
+        }
+        info!("No client keys configured; authentication is disabled on loopback");
+    }
+    let limits = Arc::new(Limits::from_env());
+    let config = limits.config();
+    info!(
+        client_per_minute = config.client_per_minute,
+        global_per_minute = config.global_per_minute,
+        max_concurrency = config.max_concurrency,
+        queue_timeout_secs = config.queue_timeout.as_secs(),
+        "Limits configured"
+    );
+    let memory = Arc::new(MemoryStore::open_default()?);
+    let recall = Arc::new(ConversationRecall::open_default()?);
+    // Cancelled once the drain deadline passes, aborting turns still running
+    let abort = CancellationToken::new();
+    let options = SaturnOptions::default()
+        .with_memory(memory.clone())
+        .with_recall(recall)
+        .with_corpus(Arc::new(DocumentCorpus::open_default()?))
+        .with_cancel(abort.clone());
+    let sessions = Arc::new(SessionStore::open_default()?);
+    // Cancelled on the shutdown signal, to stop accepting connections and socket messages
+    let stopping = CancellationToken::new();
+    // Upgraded sockets and their turns outlive the requests that opened them,
+    // so the drain waits for them separately
+    let sockets = TaskTracker::new();
+    let services = Services {
+        keys,
+        limits,
+        options,
+        memory,
+        sessions,
+        readiness: Arc::new(ReadinessCache::default()),
+        sockets: sockets.clone(),
+        stopping: stopping.clone(),
+    };
+    let routes = routes(services)
+        .with(warp::trace(|info| {
+            info_span!(
+                "request",
+                method = %info.method(),
+                path = %info.path(),
+                request_id = field::Empty,
+            )
+        }))
+        .with(warp::log::custom(|info| {
+            metrics().record_request(
+                &route_label(info.path()),
+                info.status().as_u16(),
+                info.elapsed(),
+            )
+        }));
+    let stop_accepting = stopping.clone();
+    let (address, server) = serve(routes).try_bind_with_graceful_shutdown(address, async move {
+        stop_accepting.cancelled().await
+    })?;
+    let mut server = tokio::spawn(server);
+    info!(%address, "Saturn online");
+
+    shutdown_signal().await;
+    let grace = shutdown_grace();
+    info!(
+        grace_secs = grace.as_secs(),
+        "Shutting down; draining in-flight requests"
+    );
+    stopping.cancel();
+    sockets.close();
+    let drained = async {
+        let _ = (&mut server).await;
+        sockets.wait().await;
+    };
+    if timeout(grace, drained).await.is_err() {
+        warn!("Drain deadline passed; cancelling in-flight requests");
+        abort.cancel();
+        let cancelled = async {
+            let _ = server.await;
+            sockets.wait().await;
+        };
+        if timeout(CANCEL_GRACE, cancelled).await.is_err() {
+            warn!("Requests still open after cancellation; exiting anyway");
+        }
+    }
+    info!("Saturn stopped");
+    Ok(())
+}
+/// What the routes share: the client keys and limits, the stores, and the
+/// token and tracker shutdown uses to drain sockets.
+#[derive(Clone)]
+struct Services {
+    keys: Arc<KeyStore>,
+    limits: Arc<Limits>,
+    /// Holds the memory, recall and corpus stores and the abort token.
+    options: SaturnOptions,
+    memory: Arc<MemoryStore>,
+    sessions: Arc<SessionStore>,
+    readiness: Arc<ReadinessCache>,
+    sockets: TaskTracker,
+    stopping: CancellationToken,
+}
+/// Every route, with rejections turned into the error envelope.
+fn routes(services: Services) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone {
+    let Services {
+        keys,
+        limits,
+        options,
+        memory,
+        sessions,
+        readiness,
+        sockets,
+        stopping,
+    } = services;
+    // Sockets run many turns, so they start each deadline per turn instead
+    let socket_options = options.clone();
+    // Each request gets its own deadline, counted from when it arrives
+    let turn_limit = turn_timeout();
+    let with_options = warp::any().map(move || match turn_limit {
+        Some(timeout) => options.clone().with_timeout(timeout),
+        None => options.clone(),
+    });
+    let with_memory = warp::any().map(move || memory.clone());
+    // Memory changes also reach recall, so they take the stores from the options
+    let store_options = socket_options.clone();
+    let with_stores = warp::any().map(move || store_options.clone());
+    let with_sessions = warp::any().map(move || sessions.clone());
+
     let query = warp::path("query")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(admit(keys.clone(), limits.clone(), Scope::Chat))
+        .and(json_body())
+        .and(with_options.clone())
+        .and(with_sessions.clone())
         .and_then(handle_query);
-    println!("Routes registered...");
-    println!("Saturn online.");
-    serve(query).run(([127, 0, 0, 1], 2223)).await;
-    Ok(())
+    let list_memories = warp::path("memories")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticate(keys.clone(), Scope::Memory))
+        .and(with_memory.clone())
+        .and_then(handle_list_memories);
+    let edit_memory = warp::path!("memories" / i64)
+        .and(warp::put())
+        .and(authenticate(keys.clone(), Scope::Memory))
+        .and(json_body())
+        .and(with_stores.clone())
+        .and_then(handle_edit_memory);
+    let forget_memory = warp::path!("memories" / i64)
+        .and(warp::delete())
+        .and(authenticate(keys.clone(), Scope::Memory))
+        .and(with_stores)
+        .and_then(handle_forget_memory);
+    let create_session = warp::path("sessions")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticate(keys.clone(), Scope::Sessions))
+        .and(warp::body::bytes())
+        .and(with_sessions.clone())
+        .and_then(handle_create_session);
+    let list_sessions = warp::path("sessions")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticate(keys.clone(), Scope::Sessions))
+        .and(with_sessions.clone())
+        .and_then(handle_list_sessions);
+    let get_session = warp::path!("sessions" / String)
+        .and(warp::get())
+        .and(authenticate(keys.clone(), Scope::Sessions))
+        .and(with_sessions.clone())
+        .and_then(handle_get_session);
+    let continue_session = warp::path!("sessions" / String / "messages")
+        .and(warp::post())
+        .and(admit(keys.clone(), limits.clone(), Scope::Sessions))
+        .and(json_body())
+        .and(with_options.clone())
+        .and(with_sessions.clone())
+        .and_then(handle_continue_session);
+    let rename_session = warp::path!("sessions" / String)
+        .and(warp::patch())
+        .and(authenticate(keys.clone(), Scope::Sessions))
+        .and(json_body())
+        .and(with_sessions.clone())
+        .and_then(handle_rename_session);
+    let delete_session = warp::path!("sessions" / String)
+        .and(warp::delete())
+        .and(authenticate(keys.clone(), Scope::Sessions))
+        .and(with_sessions.clone())
+        .and_then(handle_delete_session);
+    let chat_socket = warp::path("ws")
+        .and(warp::path::end())
+        .and(warp::ws())
+        .and(authenticate(keys.clone(), Scope::Sessions))
+        .and(warp::any().map({
+            let limits = limits.clone();
+            move || limits.clone()
+        }))
+        .and(warp::any().map(move || socket_options.clone()))
+        .and(with_sessions.clone())
+        .and(warp::any().map({
+            let sockets = sockets.clone();
+            let stopping = stopping.clone();
+            move || (sockets.clone(), stopping.clone())
+        }))
+        .map(
+            |ws: Ws,
+             client: String,
+             _request_id: String,
+             limits: Arc<Limits>,
+             options: SaturnOptions,
+             sessions: Arc<SessionStore>,
+             (sockets, stopping): (TaskTracker, CancellationToken)| {
+                ws.on_upgrade(move |socket| {
+                    let connection = chat_socket(
+                        socket,
+                        client,
+                        limits,
+                        options,
+                        sessions,
+                        sockets.clone(),
+                        stopping,
+                    );
+                    sockets.track_future(connection)
+                })
+            },
+        );
+    let chat_completions = warp::path!("v1" / "chat" / "completions")
+        .and(warp::post())
+        .and(admit(keys.clone(), limits, Scope::Chat))
+        .and(json_body())
+        .and(with_options)
+        .and_then(handle_chat_completions);
+    let models = warp::path!("v1" / "models")
+        .and(warp::get())
+        .and(authorize(keys.clone(), Scope::Chat))
+        .and_then(handle_models);
+    // Probes are open so a supervisor can call them without a key
+    let healthz = warp::path("healthz")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(request_id())
+        .and_then(handle_healthz);
+    let readyz = warp::path("readyz")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(request_id())
+        .and(warp::any().map(move || readiness.clone()))
+        .and_then(handle_readyz);
+    let prometheus = warp::path("metrics")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorize(keys.clone(), Scope::Admin))
+        .and_then(handle_metrics);
+    query
+        .or(chat_socket)
+        .or(chat_completions)
+        .or(models)
+        .or(list_memories)
+        .or(edit_memory)
+        .or(forget_memory)
+        .or(create_session)
+        .or(list_sessions)
+        .or(get_session)
+        .or(continue_session)
+        .or(rename_session)
+        .or(delete_session)
+        .or(healthz)
+        .or(readyz)
+        .or(prometheus)
+        .recover(handle_rejection)
+}
+/// `SATURN_SHUTDOWN_GRACE_SECS`, or 30 seconds.
+fn shutdown_grace() -> Duration {
+    env::var("SATURN_SHUTDOWN_GRACE_SECS")
+        .ok()
+        .and_then(|secs| secs.trim().parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SHUTDOWN_GRACE)
+}
+/// Resolves on Ctrl-C, or on SIGTERM from a process supervisor.
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!(error = %e, "Could not listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "Could not listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+/// The client's `X-Request-Id`, or a fresh one.
+fn request_id() -> impl Filter<Extract = (String,), Error = Infallible> + Clone {
+    warp::header::optional::<String>(REQUEST_ID_HEADER)
+        .or(warp::any().map(|| None))
+        .unify()
+        .map(|id: Option<String>| {
+            let id = id
+                .filter(|id| !id.is_empty() && id.len() <= 128)
+                .unwrap_or_else(new_request_id);
+            tracing::Span::current().record("request_id", id.as_str());
+            id
+        })
+}
+/// Requires a bearer key allowing `scope` once any client keys exist, and
+/// extracts the client's name ([`LOCAL_CLIENT`] while authentication is
+/// disabled) with the request id. Stores are scoped to that name, so clients
+/// never see each other's memories, documents or sessions.
+fn authenticate(
+    keys: Arc<KeyStore>,
+    scope: Scope,
+) -> impl Filter<Extract = (String, String), Error = Rejection> + Clone {
+    request_id()
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(move |request_id: String, header: Option<String>| {
+            let keys = keys.clone();
+            async move {
+                if !keys.is_enabled() {
+                    return Ok((LOCAL_CLIENT.to_string(), request_id));
+                }
+                let Some(token) = header.as_deref().and_then(bearer_token) else {
+                    let error = ApiError::new(
+                        ErrorKind::Unauthorized,
+                        "Expected an 'Authorization: Bearer <key>' header",
+                    );
+                    return Err(ApiRejection::reject(error, &request_id));
+                };
+                match keys.authenticate(token) {
+                    Some(client) if client.allows(scope) => Ok((client.name.clone(), request_id)),
+                    Some(client) => Err(ApiRejection::reject(
+                        ApiError::new(
+                            ErrorKind::Forbidden,
+                            format!("The key for '{}' lacks the '{scope}' scope", client.name),
+                        ),
+                        &request_id,
+                    )),
+                    None => Err(ApiRejection::reject(
+                        ApiError::new(ErrorKind::Unauthorized, "Invalid API key"),
+                        &request_id,
+                    )),
+                }
+            }
+        })
+        .untuple_one()
+}
+/// [`authenticate`] for routes that don't care who the client is.
+fn authorize(
+    keys: Arc<KeyStore>,
+    scope: Scope,
+) -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+    authenticate(keys, scope).map(|_client, request_id| request_id)
+}
+/// [`authenticate`], then count the request against the rate limits and wait
+/// for a concurrency slot. Routes that run Saturn use this.
+fn admit(
+    keys: Arc<KeyStore>,
+    limits: Arc<Limits>,
+    scope: Scope,
+) -> impl Filter<Extract = (String, Admission, String), Error = Rejection> + Clone {
+    authenticate(keys, scope)
+        .and_then(move |client: String, request_id: String| {
+            let limits = limits.clone();
+            async move {
+                if let Err(e) = limits.check_rate(&client) {
+                    return Err(ApiRejection::reject(e, &request_id));
+                }
+                match limits.admit().await {
+                    Ok(admission) => Ok((client, admission, request_id)),
+                    Err(e) => Err(ApiRejection::reject(e, &request_id)),
+                }
+            }
+        })
+        .untuple_one()
+}
+/// The JSON body, or the error it was rejected with, which the handler
+/// answers under the request's id.
+fn json_body() -> impl Filter<Extract = (Result<Value, ApiError>,), Error = Infallible> + Clone {
+    warp::body::json()
+        .map(Ok)
+        .or_else(|rejection: Rejection| async move {
+            Ok::<_, Infallible>((Err(rejection_error(&rejection)),))
+        })
 }
-async fn handle_query(query: serde_json::Value) -> Result<impl Reply, Rejection> {
+/// A JSON body with `status`, tagged with the request id.
+fn json_reply(body: &impl Serialize, status: StatusCode, request_id: &str) -> reply::Response {
+    let reply = reply::with_status(reply::json(body), status);
+    reply::with_header(reply, REQUEST_ID_HEADER, request_id).into_response()
+}
+/// The error envelope, with the status matching the error's kind and
+/// `Retry-After` when the client should back off.
+fn error_reply(error: impl Into<ApiError>, request_id: &str) -> reply::Response {
+    let error = error.into();
+    let status =
+        StatusCode::from_u16(error.kind.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if status.is_server_error() {
+        error!(request_id, status = status.as_u16(), "{}", error.message);
+    }
+    let reply = json_reply(&error.envelope(request_id), status, request_id);
+    match error.retry_after_secs() {
+        Some(seconds) => {
+            reply::with_header(reply, "retry-after", seconds.to_string()).into_response()
+        }
+        None => reply,
+    }
+}
+/// Turns rejections into the error envelope, under the request's id when the
+/// rejecting filter knew it.
+async fn handle_rejection(rejection: Rejection) -> Result<reply::Response, Infallible> {
+    let Some(ApiRejection { error, request_id }) = rejection.find() else {
+        return Ok(error_reply(rejection_error(&rejection), &new_request_id()));
+    };
+    let reply = error_reply(error.clone(), request_id);
+    Ok(if error.kind == ErrorKind::Unauthorized {
+        reply::with_header(reply, "www-authenticate", "Bearer").into_response()
+    } else {
+        reply
+    })
+}
+/// The error for one of warp's own rejections (unknown route, malformed body, ...).
+fn rejection_error(rejection: &Rejection) -> ApiError {
+    if rejection.is_not_found() {
+        ApiError::not_found("No such route")
+    } else if let Some(e) = rejection.find::<warp::filters::body::BodyDeserializeError>() {
+        ApiError::bad_request(format!("Invalid JSON body: {e}"))
+    } else if rejection.find::<UnsupportedMediaType>().is_some() {
+        ApiError::bad_request("Expected Content-Type: application/json")
+    } else if rejection.find::<PayloadTooLarge>().is_some()
+        || rejection.find::<LengthRequired>().is_some()
+        || rejection.find::<MissingHeader>().is_some()
+        || rejection.find::<InvalidHeader>().is_some()
+    {
+        ApiError::bad_request(format!("{rejection:?}"))
+    } else if rejection.find::<MethodNotAllowed>().is_some() {
+        ApiError::new(
+            ErrorKind::MethodNotAllowed,
+            "Method not allowed on this route",
+        )
+    } else {
+        ApiError::new(
+            ErrorKind::Internal,
+            format!("Unhandled rejection: {rejection:?}"),
+        )
+    }
+}
+/// `/query` takes either any JSON value, answered statelessly, or
+/// `{"session_id": ..., "query": "..."}` to continue a stored session.
+async fn handle_query(
+    client: String,
+    _admission: Admission,
+    request_id: String,
+    query: Result<Value, ApiError>,
+    options: SaturnOptions,
+    sessions: Arc<SessionStore>,
+) -> Result<reply::Response, Rejection> {
+    let query = match query {
+        Ok(query) => query,
+        Err(e) => return Ok(error_reply(e, &request_id)),
+    };
+    let options = options.with_client(&client);
+    let sessions = sessions.for_client(&client);
+    if let Some(session_id) = query.get("session_id").and_then(|v| v.as_str()) {
+        let message = query
+            .get("query")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        return Ok(session_reply(&request_id, session_id, message, &options, &sessions).await);
+    }
     let query: String = format!("{query}");
-    let reply = match saturn(query.clone()).await {
-        Ok(saturn_response) => {
-            let json_response = json!({ "query": query, "response": saturn_response });
-            reply::with_status(json_response.to_string(), StatusCode::OK)
+    let reply = match saturn_with_options(query.clone(), &options).await {
+        Ok(saturn_response) => json_reply(
+            &json!({ "query": query, "response": saturn_response }),
+            StatusCode::OK,
+            &request_id,
+        ),
+        Err(e) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+async fn handle_list_memories(
+    client: String,
+    request_id: String,
+    memory: Arc<MemoryStore>,
+) -> Result<reply::Response, Rejection> {
+    let reply = match memory.for_client(&client).list_memories() {
+        Ok(memories) => json_reply(
+            &json!({ "memories": memories }),
+            StatusCode::OK,
+            &request_id,
+        ),
+        Err(e) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+async fn handle_edit_memory(
+    id: i64,
+    client: String,
+    request_id: String,
+    body: Result<Value, ApiError>,
+    options: SaturnOptions,
+) -> Result<reply::Response, Rejection> {
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return Ok(error_reply(e, &request_id)),
+    };
+    let options = options.with_client(&client);
+    let Some(content) = body.get("content").and_then(|v| v.as_str()) else {
+        let error = ApiError::bad_request("Expected a JSON body with a 'content' string");
+        return Ok(error_reply(error, &request_id));
+    };
+    Ok(memory_change_reply(
+        id,
+        options.edit_memory(id, content).await,
+        &request_id,
+    ))
+}
+async fn handle_forget_memory(
+    id: i64,
+    client: String,
+    request_id: String,
+    options: SaturnOptions,
+) -> Result<reply::Response, Rejection> {
+    let options = options.with_client(&client);
+    Ok(memory_change_reply(
+        id,
+        options.forget_memory(id).await,
+        &request_id,
+    ))
+}
+fn memory_change_reply(id: i64, result: Result<bool>, request_id: &str) -> reply::Response {
+    match result {
+        Ok(true) => json_reply(&json!({ "id": id }), StatusCode::OK, request_id),
+        Ok(false) => error_reply(
+            ApiError::not_found(format!("No memory with id {id}")),
+            request_id,
+        ),
+        Err(e) => error_reply(e, request_id),
+    }
+}
+fn no_session_reply(id: &str, request_id: &str) -> reply::Response {
+    error_reply(
+        ApiError::not_found(format!("No session with id {id}")),
+        request_id,
+    )
+}
+/// Answers `message` with the session's history, then stores both turns.
+async fn session_reply(
+    request_id: &str,
+    id: &str,
+    message: &str,
+    options: &SaturnOptions,
+    sessions: &SessionStore,
+) -> reply::Response {
+    if message.trim().is_empty() {
+        let error = ApiError::bad_request("Expected a non-empty 'query' string");
+        return error_reply(error, request_id);
+    }
+    let history = match sessions.get_session(id) {
+        Ok(Some(_)) => match sessions.history(id) {
+            Ok(history) => history,
+            Err(e) => return error_reply(e, request_id),
+        },
+        Ok(None) => return no_session_reply(id, request_id),
+        Err(e) => return error_reply(e, request_id),
+    };
+    let options = options.clone().with_history(history);
+    let response = match saturn_with_options(message.to_string(), &options).await {
+        Ok(response) => response,
+        Err(e) => return error_reply(e, request_id),
+    };
+    let stored = sessions.append_messages(
+        id,
+        &[
+            ChatMessage::user(message),
+            ChatMessage::assistant(response.clone()),
+        ],
+    );
+    match stored {
+        Ok(true) => json_reply(
+            &json!({ "session_id": id, "query": message, "response": response }),
+            StatusCode::OK,
+            request_id,
+        ),
+        // Deleted while the answer was being generated
+        Ok(false) => no_session_reply(id, request_id),
+        Err(e) => error_reply(e, request_id),
+    }
+}
+async fn handle_create_session(
+    client: String,
+    request_id: String,
+    body: warp::hyper::body::Bytes,
+    sessions: Arc<SessionStore>,
+) -> Result<reply::Response, Rejection> {
+    let sessions = sessions.for_client(&client);
+    // The body is optional, so it is parsed here rather than by `warp::body::json`
+    let body: Value = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(body) => body,
+            Err(e) => {
+                let error = ApiError::bad_request(format!("Invalid JSON body: {e}"));
+                return Ok(error_reply(error, &request_id));
+            }
         }
+    };
+    let title = body.get("title").and_then(|v| v.as_str());
+    let reply = match sessions.create_session(title) {
+        Ok(session) => json_reply(&session, StatusCode::CREATED, &request_id),
+        Err(e) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+async fn handle_list_sessions(
+    client: String,
+    request_id: String,
+    sessions: Arc<SessionStore>,
+) -> Result<reply::Response, Rejection> {
+    let reply = match sessions.for_client(&client).list_sessions() {
+        Ok(list) => json_reply(&json!({ "sessions": list }), StatusCode::OK, &request_id),
+        Err(e) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+async fn handle_get_session(
+    id: String,
+    client: String,
+    request_id: String,
+    sessions: Arc<SessionStore>,
+) -> Result<reply::Response, Rejection> {
+    let sessions = sessions.for_client(&client);
+    let reply = match (sessions.get_session(&id), sessions.messages(&id)) {
+        (Ok(Some(session)), Ok(messages)) => json_reply(
+            &json!({ "session": session, "messages": messages }),
+            StatusCode::OK,
+            &request_id,
+        ),
+        (Ok(None), _) => no_session_reply(&id, &request_id),
+        (Err(e), _) | (_, Err(e)) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+async fn handle_continue_session(
+    id: String,
+    client: String,
+    _admission: Admission,
+    request_id: String,
+    body: Result<Value, ApiError>,
+    options: SaturnOptions,
+    sessions: Arc<SessionStore>,
+) -> Result<reply::Response, Rejection> {
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return Ok(error_reply(e, &request_id)),
+    };
+    let message = body
+        .get("query")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let options = options.with_client(&client);
+    let sessions = sessions.for_client(&client);
+    Ok(session_reply(&request_id, &id, message, &options, &sessions).await)
+}
+async fn handle_rename_session(
+    id: String,
+    client: String,
+    request_id: String,
+    body: Result<Value, ApiError>,
+    sessions: Arc<SessionStore>,
+) -> Result<reply::Response, Rejection> {
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return Ok(error_reply(e, &request_id)),
+    };
+    let sessions = sessions.for_client(&client);
+    let Some(title) = body.get("title").and_then(|v| v.as_str()) else {
+        let error = ApiError::bad_request("Expected a JSON body with a 'title' string");
+        return Ok(error_reply(error, &request_id));
+    };
+    let reply = match sessions.rename_session(&id, title) {
+        Ok(true) => json_reply(
+            &json!({ "id": id, "title": title }),
+            StatusCode::OK,
+            &request_id,
+        ),
+        Ok(false) => no_session_reply(&id, &request_id),
+        Err(e) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+async fn handle_delete_session(
+    id: String,
+    client: String,
+    request_id: String,
+    sessions: Arc<SessionStore>,
+) -> Result<reply::Response, Rejection> {
+    let reply = match sessions.for_client(&client).delete_session(&id) {
+        Ok(true) => json_reply(&json!({ "id": id }), StatusCode::OK, &request_id),
+        Ok(false) => no_session_reply(&id, &request_id),
+        Err(e) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+/// OpenAI-compatible chat completions backed by the Saturn bot. The model
+/// `saturn` uses the configured routing; `provider/model` pins drafting to a provider.
+async fn handle_chat_completions(
+    client: String,
+    admission: Admission,
+    request_id: String,
+    body: Result<Value, ApiError>,
+    options: SaturnOptions,
+) -> Result<reply::Response, Rejection> {
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return Ok(error_reply(e, &request_id)),
+    };
+    let options = options.with_client(&client);
+    let request: ChatCompletionRequest = match serde_json::from_value(body) {
+        Ok(request) => request,
         Err(e) => {
-            let error = format!("{e}");
-            let json_response = json!({ "query": query, "error": error });
-            reply::with_status(json_response.to_string(), StatusCode::RESET_CONTENT)
+            return Ok(error_reply(
+                ApiError::bad_request(e.to_string()),
+                &request_id,
+            ))
+        }
+    };
+    let (stream, status) = (request.stream, request.saturn_status);
+    let turn = match request.into_turn(&options) {
+        Ok(turn) => turn,
+        Err(e) => {
+            let mut error = ApiError::bad_request(e.to_string());
+            if e.downcast_ref::<ModelNotFound>().is_some() {
+                error = error.with_code("model_not_found");
+            }
+            return Ok(error_reply(error, &request_id));
+        }
+    };
+    if stream {
+        return Ok(stream_chat_completion(turn, status, admission, request_id));
+    }
+    let reply = match saturn_with_options(turn.query.clone(), &turn.options).await {
+        Ok(content) => json_reply(
+            &ChatCompletionResponse::new(&turn, content),
+            StatusCode::OK,
+            &request_id,
+        ),
+        Err(e) => error_reply(e, &request_id),
+    };
+    Ok(reply)
+}
+/// Streams a completion as server-sent events: the role chunk goes out at once,
+/// then, if the client asked for them with `saturn_status`, a named `status`
+/// event for each step of the turn. Saturn judges an answer before returning it,
+/// so nothing is streamed as it is generated: the finished answer is replayed
+/// in word-sized chunks. The admission is held until the answer has been sent,
+/// and the turn is dropped if the client disconnects first.
+fn stream_chat_completion(
+    turn: CompatTurn,
+    status: bool,
+    admission: Admission,
+    request_id: String,
+) -> reply::Response {
+    let (sender, receiver) = mpsc::unbounded_channel::<Result<Event, Infallible>>();
+    let id = completion_id();
+    let watcher = sender.clone();
+    let send = move |data: String| sender.send(Ok(Event::default().data(data))).is_ok();
+    let chunk = |chunk: ChatCompletionChunk| json!(chunk).to_string();
+
+    send(chunk(ChatCompletionChunk::role(&id, &turn.model)));
+    let task_request_id = request_id.clone();
+    tokio::spawn(async move {
+        let _admission = admission;
+        let (progress, mut reports) = mpsc::unbounded_channel();
+        let mut options = turn.options.clone();
+        if status {
+            options = options.with_progress(progress);
+        }
+        let answer = saturn_with_options(turn.query.clone(), &options);
+        tokio::pin!(answer);
+        let result = loop {
+            tokio::select! {
+                result = &mut answer => break result,
+                _ = watcher.closed() => return, // The client went away
+                Some(progress) = reports.recv() => {
+                    let status = ServerEvent::status(progress).to_text();
+                    let _ = watcher.send(Ok(Event::default().event("status").data(status)));
+                }
+            }
+        };
+        match result {
+            Ok(content) => {
+                for piece in token_pieces(&content) {
+                    if !send(chunk(ChatCompletionChunk::content(&id, &turn.model, piece))) {
+                        return; // The client went away
+                    }
+                }
+                send(chunk(ChatCompletionChunk::stop(&id, &turn.model)));
+            }
+            Err(e) => {
+                // Headers are long gone, so the error travels in the stream
+                send(ApiError::from(e).envelope(&task_request_id).to_string());
+            }
+        }
+        send("[DONE]".to_string());
+    });
+
+    let events = UnboundedReceiverStream::new(receiver);
+    let reply = warp::sse::reply(warp::sse::keep_alive().stream(events));
+    reply::with_header(reply, REQUEST_ID_HEADER, request_id).into_response()
+}
+/// A chat session over a WebSocket: the client sends `message`, `cancel` and
+/// `resume` frames and receives `session`, `status`, `token`, `done`,
+/// `cancelled` and `error` events (see `interfaces::websocket`). Turns run one
+/// at a time, each counted against the rate limits like an HTTP request.
+/// Once `stopping` is cancelled the socket takes no more frames, and closes
+/// after the running turn has been answered and stored. Its tasks run on
+/// `sockets`, so shutdown can wait for them.
+async fn chat_socket(
+    socket: WebSocket,
+    client: String,
+    limits: Arc<Limits>,
+    options: SaturnOptions,
+    sessions: Arc<SessionStore>,
+    sockets: TaskTracker,
+    stopping: CancellationToken,
+) {
+    // Resuming, like everything else on the socket, only reaches this client's sessions
+    let options = options.with_client(&client);
+    let sessions = Arc::new(sessions.for_client(&client));
+    let (mut sink, mut incoming) = socket.split();
+    let (events, mut outgoing) = mpsc::unbounded_channel::<ServerEvent>();
+    sockets.spawn(async move {
+        while let Some(event) = outgoing.recv().await {
+            if sink.send(Message::text(event.to_text())).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+    let send_error = |error: ApiError| {
+        let _ = events.send(ServerEvent::error(error.envelope(&new_request_id())));
+    };
+
+    let mut session_id: Option<String> = None;
+    let mut turn: Option<(CancellationToken, JoinHandle<()>)> = None;
+    loop {
+        let frame = tokio::select! {
+            frame = incoming.next() => frame,
+            _ = stopping.cancelled() => break,
+        };
+        let Some(Ok(frame)) = frame else {
+            break;
+        };
+        if frame.is_close() {
+            break;
+        }
+        // Pings and binary frames carry nothing for Saturn
+        let Ok(text) = frame.to_str() else {
+            continue;
+        };
+        let running = turn.as_ref().is_some_and(|(_, task)| !task.is_finished());
+        match ClientMessage::parse(text) {
+            Err(e) => send_error(ApiError::bad_request(e.to_string())),
+            Ok(ClientMessage::Cancel) => {
+                if let Some((cancel, _)) = &turn {
+                    cancel.cancel();
+                }
+            }
+            Ok(_) if running => send_error(ApiError::bad_request(
+                "A turn is already in progress; cancel it or wait for it to finish",
+            )),
+            Ok(ClientMessage::Resume { session_id: id }) => {
+                match (sessions.get_session(&id), sessions.messages(&id)) {
+                    (Ok(Some(_)), Ok(messages)) => {
+                        let _ = events.send(ServerEvent::Session {
+                            session_id: id.clone(),
+                            messages,
+                        });
+                        session_id = Some(id);
+                    }
+                    (Ok(None), _) => {
+                        send_error(ApiError::not_found(format!("No session with id {id}")))
+                    }
+                    (Err(e), _) | (_, Err(e)) => send_error(e.into()),
+                }
+            }
+            Ok(ClientMessage::Message { content }) => {
+                if content.trim().is_empty() {
+                    send_error(ApiError::bad_request(
+                        "Expected a non-empty 'content' string",
+                    ));
+                    continue;
+                }
+                if let Err(e) = limits.check_rate(&client) {
+                    send_error(e);
+                    continue;
+                }
+                let id = match &session_id {
+                    Some(id) => id.clone(),
+                    None => match sessions.create_session(None) {
+                        Ok(session) => {
+                            let _ = events.send(ServerEvent::Session {
+                                session_id: session.id.clone(),
+                                messages: Vec::new(),
+                            });
+                            session_id.insert(session.id).clone()
+                        }
+                        Err(e) => {
+                            send_error(e.into());
+                            continue;
+                        }
+                    },
+                };
+                // A child of the shutdown token, so draining still aborts socket turns
+                let cancel = options
+                    .cancel
+                    .as_ref()
+                    .map(CancellationToken::child_token)
+                    .unwrap_or_default();
+                let task = sockets.spawn(socket_turn(
+                    content,
+                    id,
+                    limits.clone(),
+                    options.clone().with_cancel(cancel.clone()),
+                    sessions.clone(),
+                    events.clone(),
+                ));
+                turn = Some((cancel, task));
+            }
+        }
+    }
+    // Nobody is left to read the answer, unless the server is draining
+    if let Some((cancel, _)) = turn {
+        if !stopping.is_cancelled() {
+            cancel.cancel();
+        }
+    }
+}
+/// Runs one socket turn: waits for a concurrency slot, answers with the
+/// session's history while forwarding progress, then stores both messages
+/// and sends the answer.
+async fn socket_turn(
+    content: String,
+    session_id: String,
+    limits: Arc<Limits>,
+    options: SaturnOptions,
+    sessions: Arc<SessionStore>,
+    events: mpsc::UnboundedSender<ServerEvent>,
+) {
+    let request_id = new_request_id();
+    let send = |event: ServerEvent| {
+        let _ = events.send(event);
+    };
+    let send_error = |error: ApiError| send(ServerEvent::error(error.envelope(&request_id)));
+
+    let admitted = match &options.cancel {
+        Some(cancel) => tokio::select! {
+            admitted = limits.admit() => admitted,
+            _ = cancel.cancelled() => return send(ServerEvent::Cancelled),
+        },
+        None => limits.admit().await,
+    };
+    let _admission = match admitted {
+        Ok(admission) => admission,
+        Err(e) => return send_error(e),
+    };
+    let history = match sessions.history(&session_id) {
+        Ok(history) => history,
+        Err(e) => return send_error(e.into()),
+    };
+    let (progress, mut reports) = mpsc::unbounded_channel();
+    let mut options = options.with_history(history).with_progress(progress);
+    if let Some(timeout) = turn_timeout() {
+        options = options.with_timeout(timeout);
+    }
+
+    let answer = saturn_with_options(content.clone(), &options);
+    tokio::pin!(answer);
+    let result = loop {
+        tokio::select! {
+            result = &mut answer => break result,
+            Some(progress) = reports.recv() => send(ServerEvent::status(progress)),
+        }
+    };
+    let answer = match result {
+        Ok(answer) => answer,
+        Err(e) if e.downcast_ref::<Cancelled>().is_some() => return send(ServerEvent::Cancelled),
+        Err(e) => return send_error(e.into()),
+    };
+    let stored = sessions.append_messages(
+        &session_id,
+        &[
+            ChatMessage::user(content),
+            ChatMessage::assistant(answer.clone()),
+        ],
+    );
+    match stored {
+        Ok(true) => {}
+        // Deleted while the answer was being generated
+        Ok(false) => {
+            return send_error(ApiError::not_found(format!(
+                "No session with id {session_id}"
+            )))
         }
+        Err(e) => return send_error(e.into()),
+    }
+    for piece in token_pieces(&answer) {
+        send(ServerEvent::Token {
+            content: piece.to_string(),
+        });
+    }
+    send(ServerEvent::Done { content: answer });
+}
+async fn handle_models(request_id: String) -> Result<reply::Response, Rejection> {
+    Ok(json_reply(&list_models(), StatusCode::OK, &request_id))
+}
+/// Liveness: the process is up and serving requests.
+async fn handle_healthz(request_id: String) -> Result<reply::Response, Rejection> {
+    Ok(json_reply(
+        &json!({ "status": "ok" }),
+        StatusCode::OK,
+        &request_id,
+    ))
+}
+/// Readiness: the configured providers answer with the configured credentials.
+async fn handle_readyz(
+    request_id: String,
+    readiness: Arc<ReadinessCache>,
+) -> Result<reply::Response, Rejection> {
+    let readiness = readiness.check().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
     };
-    return Ok(reply);
+    Ok(json_reply(&readiness, status, &request_id))
+}
+async fn handle_metrics(request_id: String) -> Result<reply::Response, Rejection> {
+    let reply = reply::with_header(
+        metrics().render(),
+        "content-type",
+        "text/plain; version=0.0.4",
+    );
+    Ok(reply::with_header(reply, REQUEST_ID_HEADER, request_id).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{routes, Services, REQUEST_ID_HEADER};
+    use core_modules::chat_completions::bots::saturn::saturn::SaturnOptions;
+    use core_modules::chat_completions::interfaces::auth::auth::{KeyStore, Scope};
+    use core_modules::chat_completions::interfaces::health::health::ReadinessCache;
+    use core_modules::chat_completions::interfaces::limits::limits::{Limits, LimitsConfig};
+    use core_modules::chat_completions::memory::{
+        sessions::sessions::SessionStore, store::store::MemoryStore,
+    };
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tokio_util::sync::CancellationToken;
+    use tokio_util::task::TaskTracker;
+    use warp::http::{Response, StatusCode};
+    use warp::hyper::body::Bytes;
+
+    fn services(keys: KeyStore, limits: LimitsConfig) -> Services {
+        let memory = Arc::new(MemoryStore::in_memory().unwrap());
+        Services {
+            keys: Arc::new(keys),
+            limits: Arc::new(Limits::new(limits)),
+            options: SaturnOptions::default().with_memory(memory.clone()),
+            memory,
+            sessions: Arc::new(SessionStore::in_memory().unwrap()),
+            readiness: Arc::new(ReadinessCache::default()),
+            sockets: TaskTracker::new(),
+            stopping: CancellationToken::new(),
+        }
+    }
+
+    fn body(response: &Response<Bytes>) -> Value {
+        serde_json::from_slice(response.body()).unwrap()
+    }
+
+    /// Asserts `response` is the error envelope with `status` and `code`.
+    fn assert_error(response: &Response<Bytes>, status: StatusCode, code: &str) {
+        assert_eq!(response.status(), status);
+        assert_eq!(body(response)["error"]["code"], code);
+    }
+
+    #[tokio::test]
+    async fn test_session_crud_is_scoped_to_the_client() {
+        let mut keys = KeyStore::in_memory();
+        let ada = format!(
+            "Bearer {}",
+            keys.add_client("ada", vec![Scope::Sessions]).unwrap()
+        );
+        let grace = format!(
+            "Bearer {}",
+            keys.add_client("grace", vec![Scope::Sessions]).unwrap()
+        );
+        let routes = routes(services(keys, LimitsConfig::default()));
+        let request = |method: &str, path: &str, key: &str| {
+            warp::test::request()
+                .method(method)
+                .path(path)
+                .header("authorization", key)
+        };
+
+        let created = request("POST", "/sessions", &ada)
+            .json(&json!({ "title": "Plans" }))
+            .reply(&routes)
+            .await;
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let id = body(&created)["id"].as_str().unwrap().to_string();
+        let path = format!("/sessions/{id}");
+
+        let listed = request("GET", "/sessions", &ada).reply(&routes).await;
+        assert_eq!(body(&listed)["sessions"][0]["title"], "Plans");
+        let listed = request("GET", "/sessions", &grace).reply(&routes).await;
+        assert_eq!(body(&listed)["sessions"], json!([]));
+
+        // Another client's session looks like one that doesn't exist
+        let fetched = request("GET", &path, &grace).reply(&routes).await;
+        assert_error(&fetched, StatusCode::NOT_FOUND, "not_found");
+        let renamed = request("PATCH", &path, &grace)
+            .json(&json!({ "title": "Mine now" }))
+            .reply(&routes)
+            .await;
+        assert_error(&renamed, StatusCode::NOT_FOUND, "not_found");
+        let deleted = request("DELETE", &path, &grace).reply(&routes).await;
+        assert_error(&deleted, StatusCode::NOT_FOUND, "not_found");
+
+        let renamed = request("PATCH", &path, &ada)
+            .json(&json!({ "title": "Trip" }))
+            .reply(&routes)
+            .await;
+        assert_eq!(renamed.status(), StatusCode::OK);
+        let fetched = request("GET", &path, &ada).reply(&routes).await;
+        assert_eq!(fetched.status(), StatusCode::OK);
+        assert_eq!(body(&fetched)["session"]["title"], "Trip");
+        assert_eq!(body(&fetched)["messages"], json!([]));
+
+        let deleted = request("DELETE", &path, &ada).reply(&routes).await;
+        assert_eq!(deleted.status(), StatusCode::OK);
+        let fetched = request("GET", &path, &ada).reply(&routes).await;
+        assert_error(&fetched, StatusCode::NOT_FOUND, "not_found");
+    }
+
+    #[tokio::test]
+    async fn test_chat_completions_errors_use_the_envelope() {
+        let routes = routes(services(KeyStore::in_memory(), LimitsConfig::default()));
+        let request = || {
+            warp::test::request()
+                .method("POST")
+                .path("/v1/chat/completions")
+                .header(REQUEST_ID_HEADER, "req-42")
+        };
+
+        let unknown = request()
+            .json(&json!({
+                "model": "bard",
+                "messages": [{ "role": "user", "content": "Hi" }]
+            }))
+            .reply(&routes)
+            .await;
+        assert_error(&unknown, StatusCode::BAD_REQUEST, "model_not_found");
+        let error = &body(&unknown)["error"];
+        assert_eq!(error["type"], "invalid_request_error");
+        assert_eq!(error["request_id"], "req-42");
+        assert_eq!(unknown.headers()[REQUEST_ID_HEADER], "req-42");
+
+        let wrong_model = request()
+            .json(&json!({
+                "model": "local/no-such-model",
+                "messages": [{ "role": "user", "content": "Hi" }]
+            }))
+            .reply(&routes)
+            .await;
+        assert_error(&wrong_model, StatusCode::BAD_REQUEST, "model_not_found");
+
+        let no_user = request()
+            .json(&json!({ "messages": [{ "role": "assistant", "content": "Hello" }] }))
+            .reply(&routes)
+            .await;
+        assert_error(&no_user, StatusCode::BAD_REQUEST, "bad_request");
+
+        let malformed = request()
+            .header("content-type", "application/json")
+            .body("{not json")
+            .reply(&routes)
+            .await;
+        assert_error(&malformed, StatusCode::BAD_REQUEST, "bad_request");
+        assert_eq!(body(&malformed)["error"]["request_id"], "req-42");
+    }
+
+    #[tokio::test]
+    async fn test_auth_and_rate_limit_rejections() {
+        let mut keys = KeyStore::in_memory();
+        let key = format!(
+            "Bearer {}",
+            keys.add_client("ada", vec![Scope::Chat]).unwrap()
+        );
+        let limits = LimitsConfig {
+            client_per_minute: 1,
+            ..LimitsConfig::default()
+        };
+        let routes = routes(services(keys, limits));
+
+        let anonymous = warp::test::request()
+            .path("/sessions")
+            .header(REQUEST_ID_HEADER, "req-1")
+            .reply(&routes)
+            .await;
+        assert_error(&anonymous, StatusCode::UNAUTHORIZED, "unauthorized");
+        assert_eq!(anonymous.headers()["www-authenticate"], "Bearer");
+        assert_eq!(body(&anonymous)["error"]["request_id"], "req-1");
+        let invalid = warp::test::request()
+            .path("/sessions")
+            .header("authorization", "Bearer sk-saturn-wrong")
+            .reply(&routes)
+            .await;
+        assert_error(&invalid, StatusCode::UNAUTHORIZED, "unauthorized");
+
+        let forbidden = warp::test::request()
+            .path("/sessions")
+            .header("authorization", &key)
+            .reply(&routes)
+            .await;
+        assert_error(&forbidden, StatusCode::FORBIDDEN, "forbidden");
+
+        // The first request spends the client's only one this minute
+        let completion = || {
+            warp::test::request()
+                .method("POST")
+                .path("/v1/chat/completions")
+                .header("authorization", &key)
+                .json(&json!({ "model": "bard", "messages": [] }))
+        };
+        let first = completion().reply(&routes).await;
+        assert_error(&first, StatusCode::BAD_REQUEST, "model_not_found");
+        let limited = completion()
+            .header(REQUEST_ID_HEADER, "req-2")
+            .reply(&routes)
+            .await;
+        assert_error(&limited, StatusCode::TOO_MANY_REQUESTS, "rate_limited");
+        let retry_after: u64 = limited.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after > 0);
+        assert_eq!(body(&limited)["error"]["request_id"], "req-2");
+    }
 }